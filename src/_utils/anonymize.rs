@@ -0,0 +1,26 @@
+use crate::config::context::args;
+
+/// Replaces an identifying value (username, hostname, SSID, ...) with a placeholder under `--anonymize`
+pub fn redact(value: String, placeholder: &str) -> String {
+    if args().anonymize { placeholder.to_string() } else { value }
+}
+
+/// Masks an IP address under `--anonymize`, keeping whether it was IPv4 or IPv6 recognizable
+pub fn redact_ip(ip: String) -> String {
+    match (args().anonymize, ip.contains(':')) {
+        (false, _) => ip,
+        (true, false) => "x.x.x.x".to_string(),
+        (true, true) => "x:x:x:x::x".to_string(),
+    }
+}
+
+/// Masks the device-specific last three octets of a MAC address under `--anonymize`, the first three
+/// (OUI) only identify the NIC maker
+pub fn redact_mac(mac: String) -> String {
+    if !args().anonymize {
+        return mac;
+    }
+
+    let octets: Vec<&str> = mac.split(':').collect();
+    format!("{}:xx:xx:xx", octets[..3.min(octets.len())].join(":"))
+}
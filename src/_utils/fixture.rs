@@ -0,0 +1,312 @@
+use crate::_utils::run_command::ShellReturn;
+use crate::_utils::status::EXIT_USAGE;
+use crate::_utils::system_provider::{Interface, SystemProvider, Uname};
+#[cfg(test)]
+use crate::_utils::system_provider::with_provider;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::exit;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// A captured system, answering every query from a JSON fixture and never touching the real one
+///
+/// ```json
+/// {
+///   "uname": { "sysname": "Linux", "nodename": "archbox", "release": "6.9.1" },
+///   "user": "alice",
+///   "uid": 1000,
+///   "gid": 1000,
+///   "login_shell": "/bin/zsh",
+///   "commands": {
+///     "nproc": "16",
+///     "iw dev wlan0 link": { "stdout": "...", "stderr": "", "code": 0 }
+///   },
+///   "scripts": { "packages.sh": "1204 (pacman), 12 (flatpak)" },
+///   "files": { "/etc/os-release": "PRETTY_NAME=\"Arch Linux\"\n" },
+///   "dirs": { "/sys/class/backlight": ["intel_backlight"] },
+///   "links": { "/proc/4200/exe": "/usr/bin/kitty" },
+///   "env": { "XDG_SESSION_TYPE": "wayland", "XDG_CURRENT_DESKTOP": "Hyprland", "HOME": "/home/alice" },
+///   "parent_pid": 4242,
+///   "uptime": 93784,
+///   "os_version": "Linux (Arch Linux)",
+///   "memory": { "used": 4294967296, "total": 17179869184 },
+///   "interfaces": [
+///     { "name": "wlan0", "addresses": ["192.168.1.20"], "mac": "aa:bb:cc:dd:ee:ff", "rx_bytes": 0, "tx_bytes": 0 }
+///   ],
+///   "gpus": ["10de:2684"],
+///   "disks": { "/": { "used": 53687091200, "total": 511101108224 } }
+/// }
+/// ```
+///
+/// Commands are keyed by the program and its arguments joined with spaces, scripts run with
+/// `sh_raw!` by "sh -c <script>" and the bundled scripts by their file name. Unknown commands fail with exit code 127 as if not installed, and
+/// a program counts as installed when some command in the fixture runs it.
+pub struct FixtureSystem {
+    commands: HashMap<String, ShellReturn>,
+    scripts: HashMap<String, ShellReturn>,
+    files: HashMap<String, String>,
+    dirs: HashMap<String, Vec<String>>,
+    links: HashMap<String, String>,
+    env: HashMap<String, String>,
+    uname: Uname,
+    user: String,
+    ids: (u32, u32),
+    login_shell: Option<String>,
+    parent_pid: i32,
+    uptime: u64,
+    os_version: Option<String>,
+    memory: (u64, u64),
+    interfaces: Vec<Interface>,
+    gpus: Vec<(u16, u16)>,
+    disks: HashMap<String, (u64, u64)>,
+}
+
+impl FixtureSystem {
+
+    /// Loads a fixture, exiting with a usage error if it can't be read or parsed
+    pub fn load(path: &str) -> FixtureSystem {
+        let fixture = fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| serde_json::from_str::<Value>(&content).map_err(|err| err.to_string()))
+            .and_then(|json| FixtureSystem::from_json(&json));
+
+        fixture.unwrap_or_else(|err| {
+            eprintln!("vega: can't load fixture {}: {}", path, err);
+            exit(EXIT_USAGE);
+        })
+    }
+
+    pub fn from_json(json: &Value) -> Result<FixtureSystem, String> {
+        let object = |key: &str| json.get(key).and_then(Value::as_object).into_iter().flatten();
+
+        let outputs = |key: &str| object(key)
+            .map(|(command, output)| match output {
+                Value::String(stdout) => Ok((command.clone(), ShellReturn { err_code: 0, stdout: stdout.clone(), stderr: String::new() })),
+                Value::Object(_) => Ok((command.clone(), ShellReturn {
+                    err_code: output.get("code").and_then(Value::as_u64).unwrap_or(0) as u8,
+                    stdout: output.get("stdout").and_then(Value::as_str).unwrap_or("").to_string(),
+                    stderr: output.get("stderr").and_then(Value::as_str).unwrap_or("").to_string(),
+                })),
+                _ => Err(format!("'{}' must be a string or an object", command)),
+            })
+            .collect::<Result<HashMap<_, _>, _>>();
+        let commands = outputs("commands")?;
+        let scripts = outputs("scripts")?;
+
+        let files = object("files")
+            .map(|(path, content)| match content.as_str() {
+                Some(content) => Ok((path.clone(), content.to_string())),
+                None => Err(format!("file '{}' must be a string", path)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        let dirs = object("dirs")
+            .map(|(path, entries)| {
+                let mut entries: Vec<String> = entries.as_array()
+                    .ok_or(format!("dir '{}' must be a list of names", path))?
+                    .iter()
+                    .filter_map(|entry| entry.as_str().map(str::to_string))
+                    .collect();
+                entries.sort();
+                Ok((path.clone(), entries))
+            })
+            .collect::<Result<_, String>>()?;
+
+        let links = object("links")
+            .map(|(path, target)| match target.as_str() {
+                Some(target) => Ok((path.clone(), target.to_string())),
+                None => Err(format!("link '{}' must be a string", path)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        let env = object("env")
+            .map(|(name, value)| match value.as_str() {
+                Some(value) => Ok((name.clone(), value.to_string())),
+                None => Err(format!("variable '{}' must be a string", name)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        let gpus = json.get("gpus").and_then(Value::as_array).into_iter().flatten()
+            .map(|gpu| {
+                let (vendor, device) = gpu.as_str().and_then(|gpu| gpu.split_once(':')).ok_or(format!("invalid gpu {}", gpu))?;
+                let parse = |id: &str| u16::from_str_radix(id, 16).map_err(|_| format!("invalid PCI ID {}", id));
+                Ok((parse(vendor)?, parse(device)?))
+            })
+            .collect::<Result<_, String>>()?;
+
+        let disks = object("disks")
+            .map(|(path, disk)| (path.clone(), (
+                disk.get("used").and_then(Value::as_u64).unwrap_or(0),
+                disk.get("total").and_then(Value::as_u64).unwrap_or(0),
+            )))
+            .collect();
+
+        let memory = json.get("memory").map(|memory| (
+            memory.get("used").and_then(Value::as_u64).unwrap_or(0),
+            memory.get("total").and_then(Value::as_u64).unwrap_or(0),
+        ));
+
+        let interfaces = json.get("interfaces").and_then(Value::as_array).into_iter().flatten()
+            .map(|interface| Ok(Interface {
+                name: interface.get("name").and_then(Value::as_str).ok_or("every interface needs a name")?.to_string(),
+                addresses: interface.get("addresses").and_then(Value::as_array).into_iter().flatten()
+                    .map(|addr| addr.as_str().and_then(|addr| addr.parse().ok()).ok_or(format!("invalid address {}", addr)))
+                    .collect::<Result<_, String>>()?,
+                mac: interface.get("mac").and_then(Value::as_str).unwrap_or("00:00:00:00:00:00").to_string(),
+                rx_bytes: interface.get("rx_bytes").and_then(Value::as_u64).unwrap_or(0),
+                tx_bytes: interface.get("tx_bytes").and_then(Value::as_u64).unwrap_or(0),
+            }))
+            .collect::<Result<_, String>>()?;
+
+        Ok(FixtureSystem {
+            commands,
+            scripts,
+            files,
+            dirs,
+            links,
+            env,
+            uname: Uname {
+                sysname: json.pointer("/uname/sysname").and_then(Value::as_str).unwrap_or("Linux").to_string(),
+                nodename: json.pointer("/uname/nodename").and_then(Value::as_str).unwrap_or("localhost").to_string(),
+                release: json.pointer("/uname/release").and_then(Value::as_str).unwrap_or("").to_string(),
+            },
+            user: json.get("user").and_then(Value::as_str).unwrap_or("user").to_string(),
+            ids: (
+                json.get("uid").and_then(Value::as_u64).unwrap_or(1000) as u32,
+                json.get("gid").and_then(Value::as_u64).unwrap_or(1000) as u32,
+            ),
+            login_shell: json.get("login_shell").and_then(Value::as_str).map(str::to_string),
+            parent_pid: json.get("parent_pid").and_then(Value::as_i64).unwrap_or(1) as i32,
+            uptime: json.get("uptime").and_then(Value::as_u64).unwrap_or(0),
+            os_version: json.get("os_version").and_then(Value::as_str).map(str::to_string),
+            memory: memory.unwrap_or((0, 0)),
+            interfaces,
+            gpus,
+            disks,
+        })
+    }
+
+}
+
+impl SystemProvider for FixtureSystem {
+
+    fn is_simulated(&self) -> bool {
+        true
+    }
+
+    fn run(&self, program: &str, args: &[String]) -> ShellReturn {
+        // Programs run by the path `which` gave for them are keyed by their name
+        let program = program.strip_prefix("/usr/bin/").filter(|name| self.which(name).is_some()).unwrap_or(program);
+        let command = std::iter::once(program).chain(args.iter().map(String::as_str)).collect::<Vec<_>>().join(" ");
+        self.commands.get(&command).cloned().unwrap_or_else(|| ShellReturn {
+            err_code: 127,
+            stdout: String::new(),
+            stderr: format!("{}: not in fixture", program),
+        })
+    }
+
+    fn run_until(&self, program: &str, args: &[String], _deadline: Instant) -> Option<ShellReturn> {
+        Some(self.run(program, args))
+    }
+
+    fn run_script(&self, name: &str, _script: &str) -> ShellReturn {
+        self.scripts.get(name).cloned().unwrap_or_else(|| ShellReturn {
+            err_code: 127,
+            stdout: String::new(),
+            stderr: format!("{}: not in fixture", name),
+        })
+    }
+
+    fn which(&self, program: &str) -> Option<PathBuf> {
+        let installed = self.commands.keys().any(|command| command.split(' ').next() == Some(program));
+        installed.then(|| if program.contains('/') {
+            PathBuf::from(program)
+        } else {
+            PathBuf::from("/usr/bin").join(program)
+        })
+    }
+
+    fn uname(&self) -> Uname {
+        self.uname.clone()
+    }
+
+    fn username(&self) -> String {
+        self.user.clone()
+    }
+
+    fn ids(&self) -> (u32, u32) {
+        self.ids
+    }
+
+    fn login_shell(&self) -> Option<String> {
+        self.login_shell.clone()
+    }
+
+    fn read_file(&self, path: &str) -> Option<String> {
+        self.files.get(path).cloned()
+    }
+
+    fn list_dir(&self, path: &str) -> Vec<String> {
+        self.dirs.get(path).cloned().unwrap_or_default()
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.files.contains_key(path) || self.dirs.contains_key(path)
+    }
+
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        // A fixture has no timestamps, whatever is in it has been there since the epoch
+        let is_program = path.strip_prefix("/usr/bin/").is_some_and(|name| self.which(name).is_some());
+        (self.exists(path) || is_program).then_some(UNIX_EPOCH)
+    }
+
+    fn read_link(&self, path: &str) -> Option<String> {
+        self.links.get(path).cloned()
+    }
+
+    fn env_var(&self, name: &str) -> Option<String> {
+        self.env.get(name).cloned()
+    }
+
+    fn parent_pid(&self) -> i32 {
+        self.parent_pid
+    }
+
+    fn uptime(&self) -> u64 {
+        self.uptime
+    }
+
+    fn os_version(&self) -> Option<String> {
+        self.os_version.clone()
+    }
+
+    fn memory(&self) -> Option<(u64, u64)> {
+        Some(self.memory)
+    }
+
+    fn interfaces(&self) -> &[Interface] {
+        &self.interfaces
+    }
+
+    fn sample_interfaces(&self) -> Vec<Interface> {
+        self.interfaces.clone()
+    }
+
+    fn display_controllers(&self) -> Option<Vec<(u16, u16)>> {
+        Some(self.gpus.clone())
+    }
+
+    fn disk_usage(&self, path: &str) -> Option<(u64, u64)> {
+        self.disks.get(path).copied()
+    }
+
+}
+
+/// Runs `f` against a fixture written inline, for testing gatherers
+#[cfg(test)]
+pub fn with_fixture<T>(json: Value, f: impl FnOnce() -> T) -> T {
+    let system = FixtureSystem::from_json(&json).expect("invalid fixture");
+    with_provider(Box::leak(Box::new(system)), f)
+}
@@ -0,0 +1,23 @@
+use crate::_utils::read_file::cat;
+use crate::_utils::system_provider::provider;
+use crate::config::context::config;
+use std::path::{Path, PathBuf};
+
+/// The kernel's hardware monitoring devices as (driver name, sysfs directory), e.g. ("amdgpu", "/sys/class/hwmon/hwmon3")
+pub fn hwmon_devices() -> Vec<(String, PathBuf)> {
+    // list_dir already comes sorted
+    provider().list_dir("/sys/class/hwmon").into_iter()
+        .map(|entry| Path::new("/sys/class/hwmon").join(entry))
+        .map(|hwmon| (cat(&hwmon.join("name").to_string_lossy()).trim().to_string(), hwmon))
+        .collect()
+}
+
+/// A sensor reading, e.g. `temp1_input` in millidegrees or `fan1_input` in RPM
+pub fn read_sensor(hwmon: &Path, file: &str) -> Option<i64> {
+    cat(&hwmon.join(file).to_string_lossy()).trim().parse().ok()
+}
+
+/// Whether a temperature in °C reaches `temperature.warning`, shared by every temperature stat
+pub fn is_hot(celsius: i64) -> bool {
+    celsius >= config().get_int("temperature.warning", 80)
+}
@@ -1,4 +1,13 @@
+pub mod anonymize;
+pub mod fixture;
+pub mod hwmon;
 pub mod run_command;
+pub mod sandbox;
+pub mod procfs;
 pub mod read_file;
 pub mod sort_by_priority;
+pub mod status;
+pub mod system_provider;
+pub mod terminal;
+pub mod units;
 pub mod which;
\ No newline at end of file
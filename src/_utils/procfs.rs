@@ -0,0 +1,127 @@
+// Readers for the /proc files several modules parse. They borrow from the file's content and walk
+// its lines in place, so reading one costs a single allocation for the file itself. /proc files
+// can't be memory-mapped instead, the kernel generates them on read (with a size of 0) and mmap
+// fails on them.
+
+/// Per-interface traffic counters from /proc/net/dev
+pub struct NetDev<'a> {
+    pub name: &'a str,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// The "key : value" fields of /proc/cpuinfo, in order, with one block per logical CPU
+pub fn cpuinfo_fields(cpuinfo: &str) -> impl Iterator<Item = (&str, &str)> {
+    cpuinfo.lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim(), value.trim()))
+}
+
+/// The first value of a /proc/cpuinfo field, e.g. "model name"
+pub fn cpuinfo_field<'a>(cpuinfo: &'a str, key: &str) -> Option<&'a str> {
+    cpuinfo_fields(cpuinfo).find(|(field, _)| *field == key).map(|(_, value)| value)
+}
+
+/// The number of logical CPUs listed in /proc/cpuinfo
+pub fn cpuinfo_count(cpuinfo: &str) -> usize {
+    cpuinfo_fields(cpuinfo).filter(|(field, _)| *field == "processor").count()
+}
+
+/// A /proc/meminfo value in bytes, e.g. `meminfo_bytes(meminfo, "MemAvailable")` for
+/// "MemAvailable:    8123456 kB"
+pub fn meminfo_bytes(meminfo: &str, key: &str) -> Option<u64> {
+    let value = meminfo.lines().find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))?;
+    let mut fields = value.split_whitespace();
+    let amount = fields.next()?.parse::<u64>().ok()?;
+
+    // Everything but the HugePages_ counts is in kB
+    match fields.next() {
+        Some("kB") => Some(amount * 1024),
+        _ => Some(amount),
+    }
+}
+
+/// The interfaces in /proc/net/dev, "  eth0: 1234 56 0 0 0 0 0 0 7890 12 ..." after two header lines
+pub fn net_dev(net_dev: &str) -> impl Iterator<Item = NetDev<'_>> {
+    net_dev.lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            let mut counters = counters.split_whitespace();
+
+            // Received bytes come first, transmitted bytes after the other 7 receive counters
+            let rx_bytes = counters.next()?.parse().ok()?;
+            let tx_bytes = counters.nth(7)?.parse().ok()?;
+            Some(NetDev { name: name.trim(), rx_bytes, tx_bytes })
+        })
+}
+
+/// The parent PID from /proc/<pid>/stat, e.g. 1200 for "1234 (kitty) S 1200 ..."
+pub fn stat_parent(stat: &str) -> Option<i32> {
+    // The process name can contain spaces and parentheses itself, so look after the last ')'
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CPUINFO: &str = "processor\t: 0\nvendor_id\t: AuthenticAMD\nmodel name\t: AMD Ryzen 7 7800X3D 8-Core Processor\nflags\t\t: fpu vme avx2\n\n\
+                           processor\t: 1\nvendor_id\t: AuthenticAMD\nmodel name\t: AMD Ryzen 7 7800X3D 8-Core Processor\nflags\t\t: fpu vme avx2\n";
+
+    const MEMINFO: &str = "MemTotal:       32768000 kB\nMemFree:         1024000 kB\nMemAvailable:   16384000 kB\n\
+                           HugePages_Total:       8\nHugePages_Free:        2\nHugepagesize:       2048 kB\n";
+
+    #[test]
+    fn reads_cpuinfo_fields() {
+        assert_eq!(cpuinfo_field(CPUINFO, "model name"), Some("AMD Ryzen 7 7800X3D 8-Core Processor"));
+        assert_eq!(cpuinfo_field(CPUINFO, "flags"), Some("fpu vme avx2"));
+        assert_eq!(cpuinfo_field(CPUINFO, "Features"), None);
+        assert_eq!(cpuinfo_count(CPUINFO), 2);
+        assert_eq!(cpuinfo_count(""), 0);
+    }
+
+    #[test]
+    fn reads_meminfo_in_bytes() {
+        assert_eq!(meminfo_bytes(MEMINFO, "MemTotal"), Some(32768000 * 1024));
+        assert_eq!(meminfo_bytes(MEMINFO, "MemAvailable"), Some(16384000 * 1024));
+        assert_eq!(meminfo_bytes(MEMINFO, "Hugepagesize"), Some(2048 * 1024));
+        assert_eq!(meminfo_bytes(MEMINFO, "SwapTotal"), None);
+    }
+
+    #[test]
+    fn keeps_hugepage_counts_without_a_unit() {
+        assert_eq!(meminfo_bytes(MEMINFO, "HugePages_Total"), Some(8));
+        assert_eq!(meminfo_bytes(MEMINFO, "HugePages_Free"), Some(2));
+    }
+
+    #[test]
+    fn matches_whole_meminfo_keys() {
+        // "MemFree" mustn't be found by a key that's only its prefix
+        assert_eq!(meminfo_bytes(MEMINFO, "Mem"), None);
+    }
+
+    #[test]
+    fn reads_interface_counters() {
+        let dev = "Inter-|   Receive                                                |  Transmit\n \
+                   face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed\n    \
+                   lo:   5000      50    0    0    0     0          0         0     5000      50    0    0    0     0       0          0\n  \
+                   eth0: 1234567  1000    0    0    0     0          0         0   7654321    900    0    0    0     0       0          0\n";
+        let interfaces: Vec<NetDev> = net_dev(dev).collect();
+
+        assert_eq!(interfaces.len(), 2);
+        assert_eq!(interfaces[1].name, "eth0");
+        assert_eq!(interfaces[1].rx_bytes, 1234567);
+        assert_eq!(interfaces[1].tx_bytes, 7654321);
+    }
+
+    #[test]
+    fn reads_the_parent_pid() {
+        assert_eq!(stat_parent("1234 (kitty) S 1200 1234 1234 0 -1"), Some(1200));
+        // Process names can contain spaces and parentheses
+        assert_eq!(stat_parent("4321 (tmux: server (1)) S 1 4321 4321 0 -1"), Some(1));
+        assert_eq!(stat_parent(""), None);
+    }
+}
\ No newline at end of file
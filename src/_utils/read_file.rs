@@ -1,5 +1,15 @@
-use std::fs;
+use crate::_utils::system_provider::provider;
+use std::env;
+use std::path::PathBuf;
 
 pub fn cat(path: &str) -> String {
-    fs::read_to_string(path).unwrap_or("".to_string())
-}
\ No newline at end of file
+    provider().read_file(path).unwrap_or_default()
+}
+
+/// Expands a leading `~/` to the user's home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
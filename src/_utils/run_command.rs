@@ -1,68 +1,88 @@
+#[derive(Clone)]
 pub struct ShellReturn {
     pub err_code: u8,
     pub stdout: String,
     pub stderr: String
 }
 
-/// A macro that executes a shell command using `/bin/sh` and captures its output.
+/// Runs a program with its arguments and captures its output.
 ///
 /// # Usage
 ///
-/// ```rust
-/// use mycrate::sh;
-///
-/// let result = sh!("echo Hello, World!");
-/// assert_eq!(result.stdout, "Hello, World!\n");
+/// ```no_run
+/// # use vega::sh;
+/// # let pid = 1;
+/// let result = sh!("ps", "-p", pid, "-o", "comm=");
+/// println!("Exit code: {}", result.err_code);
+/// println!("Standard Output: {}", result.stdout);
 /// ```
 ///
 /// # Details
-/// - This macro takes a string-like input (formatted using `format!` if necessary) and passes it
-///   as a command to `/bin/sh -c`.
-/// - The command is executed synchronously, and its output, error, and exit code are captured in a `ShellReturn` struct.
+/// - Each argument is converted with `to_string` and passed to the program as-is, there is no
+///   shell in between, so PIDs, paths and names can't break quoting or inject commands.
+/// - Pipes, globs and redirections need a shell, see `sh_raw!` for those.
+/// - A program that can't be started (e.g. it isn't installed) gives exit code `127`, like in a shell.
 ///
 /// # Returns
 /// This macro evaluates to a `ShellReturn` struct with the following fields:
 /// - `err_code`: The exit code of the command (u8). Defaults to `1` if the exit code cannot be determined.
 /// - `stdout`: The standard output of the executed command, as a `String`.
 /// - `stderr`: The standard error output of the executed command, as a `String`.
+#[macro_export]
+macro_rules! sh {
+    ($program:expr $(, $arg:expr)* $(,)?) => {
+        $crate::_utils::system_provider::provider().run(&$program.to_string(), &[$($arg.to_string()),*])
+    };
+}
+
+/// Runs a shell script with `/bin/sh -c`, formatting it with `format!` first.
 ///
-/// # Panics
-/// This macro will panic if:
-/// - The command fails to execute (e.g., due to invalid syntax or the `sh` executable not being found).
-///
-/// # Example
-/// ```rust
-/// let result = sh!("ls -l");
-/// println!("Exit code: {}", result.err_code);
-/// println!("Standard Output: {}", result.stdout);
-/// println!("Standard Error: {}", result.stderr);
-/// ```
-///
-/// # Notes
-/// - This macro uses `std::process::Command` internally to execute the shell command.
-/// - The exit code is cast to a `u8`; if the exit code cannot be determined, it defaults to `1`.
-///
-/// # Requirements
-/// - The system must have a functional `/bin/sh` shell available to execute commands.
+/// Only meant for fixed scripts (the bundled ones, pipelines, globs), never interpolate values into
+/// it that could contain shell syntax; use `sh!` for those.
 ///
-/// ```rust,no_run
-/// let shell_return = sh!("invalid_command_123");
-/// println!("Error Code: {}", shell_return.err_code);
-/// println!("Standard Error: {}", shell_return.stderr);
+/// ```no_run
+/// # use vega::sh_raw;
+/// let result = sh_raw!("grep '^processor' /proc/cpuinfo | wc -l");
 /// ```
 #[macro_export]
-macro_rules! sh {
-    ($($arg:tt)*) => {{
-        let output = Command::new("sh")
-            .arg("-c")
-            .arg(format!($($arg)*))
-            .output()
-            .expect("failed to execute command");
+macro_rules! sh_raw {
+    ($($arg:tt)*) => {
+        $crate::_utils::system_provider::provider().run("sh", &["-c".to_string(), format!($($arg)*)])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::_utils::fixture::with_fixture;
+    use crate::_utils::system_provider::{with_provider, RealSystem};
+    use serde_json::json;
 
-        ShellReturn {
-            err_code: output.status.code().unwrap_or(1) as u8,
-            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-        }
-    }};
-}
\ No newline at end of file
+    fn on_this_machine<T>(f: impl FnOnce() -> T) -> T {
+        with_provider(Box::leak(Box::new(RealSystem::new(false))), f)
+    }
+
+    #[test]
+    fn passes_arguments_as_they_are() {
+        let result = on_this_machine(|| sh!("printf", "%s|", "two words", "$(id)", "'quoted'", "a;b", 42));
+        assert_eq!(result.err_code, 0);
+        assert_eq!(result.stdout, "two words|$(id)|'quoted'|a;b|42|");
+    }
+
+    #[test]
+    fn runs_scripts_in_a_shell() {
+        let result = on_this_machine(|| sh_raw!("printf '%s\\n' b a | sort | head -n {}", 1));
+        assert_eq!(result.stdout, "a\n");
+    }
+
+    #[test]
+    fn reports_a_missing_program_like_a_shell() {
+        assert_eq!(on_this_machine(|| sh!("vega-no-such-program")).err_code, 127);
+    }
+
+    #[test]
+    fn runs_through_the_provider() {
+        let result = with_fixture(json!({ "commands": { "uname -sr": "Linux 6.9.1" } }), || sh!("uname", "-sr"));
+        assert_eq!(result.stdout, "Linux 6.9.1");
+        assert_eq!(with_fixture(json!({}), || sh!("uname", "-sr")).err_code, 127);
+    }
+}
@@ -0,0 +1,57 @@
+use crate::_utils::read_file::cat;
+use crate::_utils::system_provider::provider;
+
+/// An app sandbox vega can find itself running in, which hides most of the host system
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Sandbox {
+    Flatpak,
+    Snap,
+}
+
+impl Sandbox {
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sandbox::Flatpak => "Flatpak",
+            Sandbox::Snap => "Snap",
+        }
+    }
+
+    /// Where the host's root filesystem shows through, as far as the sandbox exposes it
+    pub fn host_root(&self) -> &'static str {
+        match self {
+            Sandbox::Flatpak => "/run/host",
+            Sandbox::Snap => "/var/lib/snapd/hostfs",
+        }
+    }
+
+}
+
+/// The sandbox vega runs in, if any
+pub fn sandbox() -> Option<Sandbox> {
+    // Flatpak drops an info file at the sandbox root, snapd sets $SNAP for every snap
+    if provider().exists("/.flatpak-info") {
+        Some(Sandbox::Flatpak)
+    } else if provider().env_var("SNAP").is_some() && provider().env_var("SNAP_NAME").is_some() {
+        Some(Sandbox::Snap)
+    } else {
+        None
+    }
+}
+
+/// The host's os-release, rather than the sandbox runtime's when vega runs in one
+pub fn os_release() -> String {
+    if let Some(sandbox) = sandbox() {
+        let host = cat(&format!("{}/etc/os-release", sandbox.host_root()));
+        if !host.is_empty() {
+            return host;
+        }
+        // Older Flatpak only exposes it under /run/host
+        let host = cat("/run/host/os-release");
+        if !host.is_empty() {
+            return host;
+        }
+    }
+
+    cat("/etc/os-release")
+}
@@ -0,0 +1,141 @@
+use crate::config::context::args;
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::process::exit;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// Failed modules exit with how many there were, so the other codes are sysexits(3)'s, well above
+// the number of modules
+
+/// Everything was gathered
+pub const EXIT_SUCCESS: i32 = 0;
+/// Invalid command line, e.g. an unknown option or logo
+pub const EXIT_USAGE: i32 = 64;
+/// A file vega was given (e.g. with `--logo-file`) couldn't be read
+pub const EXIT_NO_INPUT: i32 = 66;
+/// The config file or theme couldn't be read, defaults were used instead
+pub const EXIT_CONFIG: i32 = 78;
+
+/// A problem worth telling the user about, e.g. a missing tool that left a stat degraded
+#[derive(Clone)]
+pub struct Warning {
+    /// The module it happened in, or None for general ones (config, logo, ...)
+    pub module: Option<String>,
+    pub message: String,
+}
+
+/// Warnings so far, shown after the output so they don't get lost in between the stats
+static WARNINGS: Mutex<Vec<Warning>> = Mutex::new(Vec::new());
+/// Modules that failed, each counted once however many of its stats failed
+static FAILED_MODULES: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+static CONFIG_ERROR: AtomicBool = AtomicBool::new(false);
+/// How long each module took to gather, for `vega bench`
+static TIMINGS: Mutex<Vec<(String, Duration)>> = Mutex::new(Vec::new());
+
+/// Records a general warning
+pub fn warn(message: &str) {
+    push_warning(None, message);
+}
+
+/// Records a warning about a module that fell back to something less accurate (or nothing), e.g.
+/// because a tool it uses isn't installed
+pub fn warn_module(module: &str, message: &str) {
+    push_warning(Some(module), message);
+}
+
+fn push_warning(module: Option<&str>, message: &str) {
+    if let Ok(mut warnings) = WARNINGS.lock() {
+        warnings.push(Warning { module: module.map(str::to_string), message: message.to_string() });
+    }
+}
+
+/// Every warning recorded so far
+pub fn warnings() -> Vec<Warning> {
+    WARNINGS.lock().map(|warnings| warnings.clone()).unwrap_or_default()
+}
+
+/// Prints the recorded warnings to stderr, unless `--quiet`
+pub fn print_warnings() {
+    if args().quiet {
+        return;
+    }
+
+    for warning in warnings() {
+        match warning.module {
+            Some(module) => eprintln!("vega: {}: {}", module, warning.message),
+            None => eprintln!("vega: {}", warning.message),
+        }
+    }
+
+    let failed = failed_modules();
+    if failed > 0 {
+        eprintln!("vega: {} module{} failed", failed, if failed == 1 { "" } else { "s" });
+    }
+}
+
+/// Records that a module couldn't gather its stats, or aborts right away under `--fail-fast`
+pub fn fail(module: &str, message: &str) {
+    warn_module(module, message);
+    if let Ok(mut failed) = FAILED_MODULES.lock() {
+        failed.insert(module.to_string());
+    }
+
+    if args().fail_fast {
+        print_warnings();
+        exit(exit_code());
+    }
+}
+
+/// Records a problem with the config file or a theme, which are then ignored in favour of defaults
+pub fn config_error(message: &str) {
+    warn(message);
+    CONFIG_ERROR.store(true, Ordering::Relaxed);
+
+    if args().fail_fast {
+        print_warnings();
+        exit(EXIT_CONFIG);
+    }
+}
+
+/// Runs a module's gatherer, turning a panic into a failure of that module instead of a crash
+pub fn run_module<T: Default>(module: &str, gather: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = panic::catch_unwind(AssertUnwindSafe(gather)).unwrap_or_else(|panic| {
+        fail(module, &format!("crashed: {}", panic_message(&*panic)));
+        T::default()
+    });
+
+    TIMINGS.lock().unwrap().push((module.to_string(), start.elapsed()));
+    result
+}
+
+/// The module timings recorded since the last call
+pub fn take_timings() -> Vec<(String, Duration)> {
+    std::mem::take(&mut *TIMINGS.lock().unwrap())
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic.downcast_ref::<&str>().copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown error")
+}
+
+/// The exit code for everything recorded so far: the number of failed modules (one or more, the rest
+/// of the output is still printed unless `--fail-fast`), else whether there was a config error
+pub fn exit_code() -> i32 {
+    let failed = failed_modules();
+    if failed > 0 {
+        failed.min(EXIT_USAGE as usize - 1) as i32
+    } else if CONFIG_ERROR.load(Ordering::Relaxed) {
+        EXIT_CONFIG
+    } else {
+        EXIT_SUCCESS
+    }
+}
+
+fn failed_modules() -> usize {
+    FAILED_MODULES.lock().map(|failed| failed.len()).unwrap_or(0)
+}
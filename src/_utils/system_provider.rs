@@ -0,0 +1,327 @@
+use crate::_utils::procfs::meminfo_bytes;
+use crate::_utils::run_command::ShellReturn;
+use crate::_utils::which::search_path;
+use crate::config::context::Context;
+use libc::{statvfs, statvfs as Statvfs};
+use pci_info::pci_enums::PciDeviceClass;
+use pci_info::PciInfo;
+use std::env;
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::mem::zeroed;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::io::Read;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use sysinfo::{Networks, System};
+
+/// A network interface with its addresses and traffic counters since boot
+#[derive(Clone)]
+pub struct Interface {
+    pub name: String,
+    pub addresses: Vec<IpAddr>,
+    pub mac: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// The kernel's identification, as `uname` prints it
+#[derive(Clone)]
+pub struct Uname {
+    /// e.g. "Linux", "Darwin" or "FreeBSD"
+    pub sysname: String,
+    pub nodename: String,
+    pub release: String,
+}
+
+/// Everything the gatherers learn from the outside world: programs, files and the few sysinfo
+/// queries. Going through `provider()` lets a fixture stand in for the real system, so any machine
+/// can be rendered (and any gatherer exercised) on any host.
+pub trait SystemProvider: Send + Sync {
+    /// Whether this is a captured system rather than the machine vega runs on, whose caches
+    /// (uptime record, segments) belong to the machine and mustn't be read or written
+    fn is_simulated(&self) -> bool;
+    fn run(&self, program: &str, args: &[String]) -> ShellReturn;
+    /// Like `run`, but kills the program and gives None if it's still running at `deadline`
+    fn run_until(&self, program: &str, args: &[String], deadline: Instant) -> Option<ShellReturn>;
+    /// Runs one of the bundled scripts (e.g. "packages.sh") with `/bin/sh -c`
+    fn run_script(&self, name: &str, script: &str) -> ShellReturn;
+    fn which(&self, program: &str) -> Option<PathBuf>;
+    fn uname(&self) -> Uname;
+    /// Name of the user vega runs as
+    fn username(&self) -> String;
+    /// Effective user and group ID vega runs as
+    fn ids(&self) -> (u32, u32);
+    /// The user's login shell from their passwd entry, e.g. "/bin/zsh"
+    fn login_shell(&self) -> Option<String>;
+    fn read_file(&self, path: &str) -> Option<String>;
+    /// Names of the entries in a directory, sorted
+    fn list_dir(&self, path: &str) -> Vec<String>;
+    fn exists(&self, path: &str) -> bool;
+    /// When a file was last modified, e.g. to tell whether a binary changed since it was cached
+    fn modified(&self, path: &str) -> Option<SystemTime>;
+    /// Where a symlink points, e.g. "/usr/bin/kitty" for /proc/<pid>/exe
+    fn read_link(&self, path: &str) -> Option<String>;
+    fn env_var(&self, name: &str) -> Option<String>;
+    /// PID of the process that started vega, usually the shell
+    fn parent_pid(&self) -> i32;
+    /// Seconds since boot
+    fn uptime(&self) -> u64;
+    /// Fallback OS name for systems without /etc/os-release, e.g. "macOS 15.1 Sequoia"
+    fn os_version(&self) -> Option<String>;
+    /// Used and total RAM in bytes, or `None` where only sysinfo can tell (see `get_ram`)
+    fn memory(&self) -> Option<(u64, u64)>;
+    /// The interfaces as of the first call, shared by every stat that needs them
+    fn interfaces(&self) -> &[Interface];
+    /// A fresh reading of the interfaces and their counters, for measuring throughput
+    fn sample_interfaces(&self) -> Vec<Interface>;
+    /// Vendor and device IDs of the PCI display controllers, or `None` if the bus can't be enumerated
+    fn display_controllers(&self) -> Option<Vec<(u16, u16)>>;
+    /// Used and total bytes of the filesystem holding `path`
+    fn disk_usage(&self, path: &str) -> Option<(u64, u64)>;
+}
+
+/// The machine vega runs on
+pub struct RealSystem {
+    /// `--fast`: never start a program, anything that needs one comes back as not installed
+    fast: bool,
+    /// Enumerating interfaces is relatively slow, so it only happens once something asks for them
+    interfaces: OnceLock<Vec<Interface>>,
+}
+
+impl RealSystem {
+
+    pub fn new(fast: bool) -> RealSystem {
+        RealSystem { fast, interfaces: OnceLock::new() }
+    }
+
+}
+
+impl SystemProvider for RealSystem {
+
+    fn is_simulated(&self) -> bool {
+        false
+    }
+
+    fn run(&self, program: &str, args: &[String]) -> ShellReturn {
+        if self.fast {
+            return not_run(program);
+        }
+
+        match Command::new(program).args(args).output() {
+            Ok(output) => ShellReturn {
+                err_code: output.status.code().unwrap_or(1) as u8,
+                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            },
+            // Like a shell would, report a missing program as exit code 127 rather than failing
+            Err(err) => ShellReturn {
+                err_code: 127,
+                stdout: String::new(),
+                stderr: err.to_string(),
+            },
+        }
+    }
+
+    fn run_until(&self, program: &str, args: &[String], deadline: Instant) -> Option<ShellReturn> {
+        if self.fast {
+            return Some(not_run(program));
+        }
+
+        // In a process group of its own, so whatever it starts (shims often spawn the real program)
+        // is killed along with it
+        let mut command = Command::new(program);
+        command.args(args).stdout(Stdio::piped()).stderr(Stdio::piped()).process_group(0);
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(err) => return Some(ShellReturn { err_code: 127, stdout: String::new(), stderr: err.to_string() }),
+        };
+
+        // Polled rather than waited on, so a hanging program can be killed instead of leaking along
+        // with the thread waiting for it
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(5)),
+                _ => {
+                    unsafe { libc::kill(-(child.id() as i32), libc::SIGKILL) };
+                    let _ = child.wait();
+                    return None;
+                },
+            }
+        };
+
+        // The output is read once the program exited, which is fine for the few lines expected here
+        let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+        let _ = child.stdout.take().map(|mut pipe| pipe.read_to_end(&mut stdout));
+        let _ = child.stderr.take().map(|mut pipe| pipe.read_to_end(&mut stderr));
+        Some(ShellReturn {
+            err_code: status.code().unwrap_or(1) as u8,
+            stdout: String::from_utf8_lossy(&stdout).to_string(),
+            stderr: String::from_utf8_lossy(&stderr).to_string(),
+        })
+    }
+
+    fn run_script(&self, _name: &str, script: &str) -> ShellReturn {
+        self.run("sh", &["-c".to_string(), script.to_string()])
+    }
+
+    fn which(&self, program: &str) -> Option<PathBuf> {
+        if self.fast {
+            return None;
+        }
+
+        search_path(program)
+    }
+
+    fn uname(&self) -> Uname {
+        let mut uts: libc::utsname = unsafe { zeroed() };
+        if unsafe { libc::uname(&mut uts) } != 0 {
+            return Uname { sysname: String::new(), nodename: String::new(), release: String::new() };
+        }
+
+        let field = |field: &[libc::c_char]| unsafe { CStr::from_ptr(field.as_ptr()) }.to_string_lossy().to_string();
+        Uname { sysname: field(&uts.sysname), nodename: field(&uts.nodename), release: field(&uts.release) }
+    }
+
+    fn username(&self) -> String {
+        let passwd = unsafe { libc::getpwuid(libc::geteuid()) };
+        if passwd.is_null() {
+            return env::var("USER").unwrap_or_default();
+        }
+
+        unsafe { CStr::from_ptr((*passwd).pw_name) }.to_string_lossy().to_string()
+    }
+
+    fn ids(&self) -> (u32, u32) {
+        unsafe { (libc::geteuid(), libc::getegid()) }
+    }
+
+    fn login_shell(&self) -> Option<String> {
+        let passwd = unsafe { libc::getpwuid(libc::geteuid()) };
+        if passwd.is_null() {
+            return None;
+        }
+
+        let shell = unsafe { CStr::from_ptr((*passwd).pw_shell) }.to_string_lossy().to_string();
+        (!shell.is_empty()).then_some(shell)
+    }
+
+    fn read_file(&self, path: &str) -> Option<String> {
+        fs::read_to_string(path).ok()
+    }
+
+    fn list_dir(&self, path: &str) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(path).into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        Path::new(path).exists()
+    }
+
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        fs::metadata(path).ok()?.modified().ok()
+    }
+
+    fn read_link(&self, path: &str) -> Option<String> {
+        fs::read_link(path).ok().map(|target| target.to_string_lossy().to_string())
+    }
+
+    fn env_var(&self, name: &str) -> Option<String> {
+        env::var(name).ok()
+    }
+
+    fn parent_pid(&self) -> i32 {
+        unsafe { libc::getppid() }
+    }
+
+    fn uptime(&self) -> u64 {
+        System::uptime()
+    }
+
+    fn os_version(&self) -> Option<String> {
+        System::long_os_version()
+    }
+
+    fn memory(&self) -> Option<(u64, u64)> {
+        // Linux: "used" as free(1) counts it, everything that isn't available
+        let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+        let total = meminfo_bytes(&meminfo, "MemTotal")?;
+        Some((total.saturating_sub(meminfo_bytes(&meminfo, "MemAvailable")?), total))
+    }
+
+    fn interfaces(&self) -> &[Interface] {
+        self.interfaces.get_or_init(|| self.sample_interfaces())
+    }
+
+    fn sample_interfaces(&self) -> Vec<Interface> {
+        Networks::new_with_refreshed_list().iter()
+            .map(|(name, data)| Interface {
+                name: name.clone(),
+                addresses: data.ip_networks().iter().map(|ip| ip.addr).collect(),
+                mac: data.mac_address().to_string(),
+                rx_bytes: data.total_received(),
+                tx_bytes: data.total_transmitted(),
+            })
+            .collect()
+    }
+
+    fn display_controllers(&self) -> Option<Vec<(u16, u16)>> {
+        let devices = PciInfo::enumerate_pci().ok()?;
+
+        // Ignores non-gpus
+        Some(devices.into_iter()
+            .flatten()
+            .filter(|device| device.device_class().unwrap_or(PciDeviceClass::Unclassified) == PciDeviceClass::DisplayController)
+            .map(|device| (device.vendor_id(), device.device_id()))
+            .collect())
+    }
+
+    fn disk_usage(&self, path: &str) -> Option<(u64, u64)> {
+        let path = CString::new(path).ok()?;
+        let mut stat: Statvfs = unsafe { zeroed() };
+
+        let result = unsafe { statvfs(path.as_ptr(), &mut stat) };
+        if result != 0 {
+            return None;
+        }
+
+        let total_space = stat.f_blocks as u64 * stat.f_frsize as u64;
+        let free_space = stat.f_bfree as u64 * stat.f_frsize as u64;
+
+        Some((total_space - free_space, total_space))
+    }
+
+}
+
+/// What `--fast` gives for a program it didn't start, the same as for one that isn't installed
+fn not_run(program: &str) -> ShellReturn {
+    ShellReturn {
+        err_code: 127,
+        stdout: String::new(),
+        stderr: format!("{}: not run with --fast", program),
+    }
+}
+
+/// The system being described, see `Context`: the fixture given with `--simulate` or `VEGA_FIXTURE`,
+/// otherwise the real machine
+pub fn provider() -> &'static dyn SystemProvider {
+    Context::current().system
+}
+
+/// Runs `f` with `provider()` answering from `system` on the current thread, e.g. to run a gatherer
+/// against a fixture in a test. Threads started by `f` see the installed context's provider unless
+/// they're entered into this one.
+pub fn with_provider<T>(system: &'static dyn SystemProvider, f: impl FnOnce() -> T) -> T {
+    Context::current().with_system(system).enter(f)
+}
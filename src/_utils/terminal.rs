@@ -0,0 +1,258 @@
+use crate::config::context::args;
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::iter;
+use std::mem::zeroed;
+use std::os::fd::AsRawFd;
+
+/// How long to wait for the terminal to answer a query, terminals that don't support it never do
+const RESPONSE_TIMEOUT_MS: i32 = 100;
+
+/// Whether stdout is attached to a terminal rather than a pipe or file
+pub fn stdout_is_terminal() -> bool {
+    unsafe { libc::isatty(libc::STDOUT_FILENO) != 0 }
+}
+
+/// Width of the terminal stdout is attached to, or None when output isn't going to a terminal
+pub fn terminal_width() -> Option<usize> {
+    if !stdout_is_terminal() {
+        return None;
+    }
+
+    let mut size: libc::winsize = unsafe { zeroed() };
+    if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0 && size.ws_col > 0 {
+        return Some(size.ws_col as usize);
+    }
+
+    env::var("COLUMNS").ok()?.parse().ok()
+}
+
+/// Part of a line: a whole escape sequence, or a character
+enum Piece<'a> {
+    Escape(&'a str),
+    Char(char),
+}
+
+/// Splits a line into its characters and escape sequences, each sequence whole: CSI ones (colors,
+/// cursor movement) up to their final byte, string ones (APC kitty images, DCS sixels, OSC 8 links)
+/// up to BEL or ST ("\x1b\\"), and two-byte ones such as saving and restoring the cursor ("\x1b7")
+fn pieces(line: &str) -> impl Iterator<Item = Piece<'_>> {
+    let mut chars = line.char_indices().peekable();
+
+    iter::from_fn(move || {
+        let (start, c) = chars.next()?;
+        if c != '\x1b' {
+            return Some(Piece::Char(c));
+        }
+
+        match chars.next().map(|(_, c)| c) {
+            Some('_' | 'P' | ']' | '^' | 'X') => while let Some((_, c)) = chars.next() {
+                if c == '\x07' || (c == '\x1b' && chars.next_if(|(_, c)| *c == '\\').is_some()) {
+                    break;
+                }
+            },
+            // Parameters and intermediates up to the final byte, e.g. the "m" of "\x1b[0;31m"
+            Some('[') => {
+                chars.by_ref().find(|(_, c)| ('@'..='~').contains(c));
+            },
+            _ => {},
+        }
+
+        let end = chars.peek().map_or(line.len(), |(i, _)| *i);
+        Some(Piece::Escape(&line[start..end]))
+    })
+}
+
+/// Columns a piece takes: one for a printable character, none for control characters (e.g. the
+/// newlines before an image logo) and escapes, except the ones moving the cursor forward
+fn columns(piece: &Piece) -> usize {
+    match piece {
+        Piece::Char(c) if c.is_control() => 0,
+        Piece::Char(_) => 1,
+        Piece::Escape(escape) => escape.strip_prefix("\x1b[")
+            .and_then(|escape| escape.strip_suffix('C'))
+            .map_or(0, |count| count.parse().unwrap_or(1)),
+    }
+}
+
+/// Width of a line as displayed, skipping ANSI escape sequences but counting the columns skipped
+/// with cursor-forward ones
+pub fn visible_width(line: &str) -> usize {
+    pieces(line).map(|piece| columns(&piece)).sum()
+}
+
+/// A line with every escape sequence removed, for output that isn't going to a terminal
+pub fn strip_escapes(line: &str) -> String {
+    pieces(line)
+        .filter_map(|piece| match piece {
+            Piece::Char(c) => Some(c),
+            Piece::Escape(_) => None,
+        })
+        .collect()
+}
+
+/// Keeps the visible characters whose column passes `keep`, along with every escape sequence (whole)
+/// and control character so colors still apply to what remains and images aren't cut apart
+pub fn filter_columns(line: &str, keep: impl Fn(usize) -> bool) -> String {
+    let mut filtered = String::with_capacity(line.len());
+    let mut column = 0;
+
+    for piece in pieces(line) {
+        let width = columns(&piece);
+        match piece {
+            Piece::Escape(escape) => filtered.push_str(escape),
+            Piece::Char(c) if width == 0 || keep(column) => filtered.push(c),
+            Piece::Char(_) => {},
+        }
+        column += width;
+    }
+
+    filtered
+}
+
+/// A line cut down to `width` columns, ending in "…" (or "..." under `--ascii`) when something was
+/// cut off
+pub fn truncate_to_width(line: &str, width: usize) -> String {
+    if visible_width(line) <= width {
+        return line.to_string();
+    }
+
+    let ellipsis = if args().ascii { "..." } else { "…" };
+    let truncated = filter_columns(line, |column| column + ellipsis.chars().count() < width);
+    if line.contains('\x1b') {
+        format!("{}{}\x1b[0m", truncated, ellipsis)
+    } else {
+        format!("{}{}", truncated, ellipsis)
+    }
+}
+
+/// A line with vega's box drawing, bar, arrow and ellipsis characters replaced by ASCII ones, for
+/// dumb terminals and serial consoles
+pub fn to_ascii(line: &str) -> String {
+    let mut ascii = String::with_capacity(line.len());
+    for c in line.chars() {
+        match c {
+            '╔' | '╚' | '═' => ascii.push('+'),
+            '╠' => ascii.push('|'),
+            '…' => ascii.push_str("..."),
+            '█' => ascii.push('#'),
+            '░' => ascii.push('-'),
+            '▁' => ascii.push('_'),
+            '▂' => ascii.push('.'),
+            '▃' => ascii.push('-'),
+            '▄' => ascii.push(':'),
+            '▅' => ascii.push('='),
+            '▆' => ascii.push('+'),
+            '▇' => ascii.push('*'),
+            '↓' => ascii.push('v'),
+            '↑' => ascii.push('^'),
+            '×' => ascii.push('x'),
+            // "42°C" reads fine as "42C"
+            '°' => {},
+            c => ascii.push(c),
+        }
+    }
+    ascii
+}
+
+/// Sends an escape sequence query to the terminal and reads its reply until `is_complete` accepts
+/// it, or None if stdout isn't a terminal or it doesn't answer in time
+pub fn query_terminal(query: &str, is_complete: impl Fn(&[u8]) -> bool) -> Option<String> {
+
+    // Only ask interactive terminals, output piped elsewhere shouldn't depend on the terminal
+    if !stdout_is_terminal() {
+        return None;
+    }
+
+    let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?;
+
+    // Disable canonical mode and echo so the response can be read immediately and isn't printed
+    let fd = tty.as_raw_fd();
+    let mut original: libc::termios = unsafe { zeroed() };
+    if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+        return None;
+    }
+    let mut raw = original;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) };
+
+    let response = read_response(&mut tty, query, is_complete);
+
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &original) };
+
+    response
+}
+
+fn read_response(tty: &mut File, query: &str, is_complete: impl Fn(&[u8]) -> bool) -> Option<String> {
+    tty.write_all(query.as_bytes()).ok()?;
+    tty.flush().ok()?;
+
+    let mut response = Vec::with_capacity(32);
+    let mut pollfd = libc::pollfd { fd: tty.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+
+    while !is_complete(&response) && response.len() < 256 {
+        if unsafe { libc::poll(&mut pollfd, 1, RESPONSE_TIMEOUT_MS) } <= 0 {
+            return None;
+        }
+
+        let mut byte = [0u8; 1];
+        if tty.read(&mut byte).ok()? == 0 {
+            return None;
+        }
+        response.push(byte[0]);
+    }
+
+    Some(String::from_utf8_lossy(&response).to_string())
+}
+
+/// Size of a character cell in pixels, when the terminal reports its pixel dimensions
+pub fn cell_size() -> Option<(u32, u32)> {
+    let mut size: libc::winsize = unsafe { zeroed() };
+    if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } != 0 {
+        return None;
+    }
+    if size.ws_col == 0 || size.ws_row == 0 || size.ws_xpixel == 0 || size.ws_ypixel == 0 {
+        return None;
+    }
+
+    Some((size.ws_xpixel as u32 / size.ws_col as u32, size.ws_ypixel as u32 / size.ws_row as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The first line of a two row, four column kitty image logo, see `get_image_logo`
+    const IMAGE_LINE: &str = "\n\n\x1b[2A\x1b7\x1b_Ga=T,f=32,c=4,r=2;AAAA\x1b\\\x1b8\x1b[4C";
+
+    #[test]
+    fn measures_escapes_as_displayed() {
+        assert_eq!(visible_width("\x1b[1;34malice\x1b[0m"), 5);
+        assert_eq!(visible_width(IMAGE_LINE), 4);
+        assert_eq!(visible_width("\x1b]8;;https://example.com\x07link\x1b]8;;\x07"), 4);
+        assert_eq!(visible_width("\x1bP0;1q#0;2;0;0;0\x1b\\ok"), 2);
+    }
+
+    #[test]
+    fn strips_every_kind_of_escape() {
+        assert_eq!(strip_escapes("\x1b[0;31mred\x1b[0m \x1b]8;;file:///\x1b\\link\x1b]8;;\x1b\\"), "red link");
+        assert_eq!(strip_escapes(IMAGE_LINE), "\n\n");
+        assert_eq!(strip_escapes("\x1b7a\x1b8b"), "ab");
+    }
+
+    #[test]
+    fn copies_escapes_through_whole() {
+        assert_eq!(filter_columns("\x1b[1mabc\x1b[0m", |column| column < 2), "\x1b[1mab\x1b[0m");
+        assert_eq!(filter_columns(&format!("{}   text", IMAGE_LINE), |column| column < 6), format!("{}  ", IMAGE_LINE));
+        assert_eq!(filter_columns("a\x1b7\x1b_Gq=2;AAAA\x1b\\\x1b8bc", |column| column != 1), "a\x1b7\x1b_Gq=2;AAAA\x1b\\\x1b8c");
+    }
+
+    #[test]
+    fn truncates_without_cutting_into_escapes() {
+        let line = format!("{}   \x1b[1mWallpaper\x1b[0m", IMAGE_LINE);
+        assert_eq!(truncate_to_width(&line, 10), format!("{}   \x1b[1mWa\x1b[0m…\x1b[0m", IMAGE_LINE));
+        assert_eq!(truncate_to_width("plain text", 6), "plain…");
+        assert_eq!(truncate_to_width("short", 6), "short");
+    }
+}
@@ -0,0 +1,43 @@
+use std::mem::zeroed;
+
+/// Formats a byte count with binary units, e.g. "1.2 MiB"
+pub fn human_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Seconds since the epoch
+pub fn now() -> u64 {
+    unsafe { libc::time(std::ptr::null_mut()) }.max(0) as u64
+}
+
+/// A timestamp broken down in the local timezone
+pub fn local_time(timestamp: u64) -> Option<libc::tm> {
+    let timestamp = timestamp as libc::time_t;
+    let mut local: libc::tm = unsafe { zeroed() };
+    if unsafe { libc::localtime_r(&timestamp, &mut local) }.is_null() {
+        return None;
+    }
+    Some(local)
+}
+
+/// A timestamp in local time, e.g. "2024-05-01 09:31"
+pub fn format_timestamp(timestamp: u64) -> Option<String> {
+    let local = local_time(timestamp)?;
+    Some(format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        local.tm_year + 1900, local.tm_mon + 1, local.tm_mday, local.tm_hour, local.tm_min
+    ))
+}
@@ -1,12 +1,25 @@
-use std::process::Command;
-use crate::_utils::run_command::ShellReturn;
-use std::path::PathBuf;
-use crate::sh;
+use crate::_utils::system_provider::provider;
+use std::env;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
 
+/// Finds an executable on `$PATH`, like `command -v`
 pub fn which(cmd: &str) -> Option<PathBuf> {
-    let result = sh!("command -v {}", cmd);
-    match result.err_code {
-        0 => Some(result.stdout.trim().into()),
-        _ => None,
+    provider().which(cmd)
+}
+
+/// The real `$PATH` search behind `which` on the actual system
+pub fn search_path(cmd: &str) -> Option<PathBuf> {
+    // Paths are taken as they are
+    if cmd.contains('/') {
+        return is_executable(Path::new(cmd)).then(|| PathBuf::from(cmd));
     }
-}
\ No newline at end of file
+
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(cmd))
+        .find(|path| is_executable(path))
+}
+
+fn is_executable(path: &Path) -> bool {
+    path.metadata().is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
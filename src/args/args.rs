@@ -0,0 +1,314 @@
+use crate::_utils::status::{EXIT_NO_INPUT, EXIT_USAGE};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::exit;
+
+const HELP: &str = "\
+Usage: vega [OPTIONS]
+       vega logo list
+       vega logo show <NAME>
+       vega schema
+       vega bench [RUNS] [--save]
+       vega segment <NAME>
+
+Commands:
+  logo list                 List the available logos
+  logo show <NAME>          Preview a logo with its colors
+  schema                    Print the JSON Schema of --format json
+  bench [RUNS] [--save]     Time RUNS (default 10) collections against the saved baseline,
+                            --save makes this run the new one
+  segment <NAME>            Print one stat as a colored, cached segment for prompts and tmux
+
+Options:
+      --accessible          Print each stat as a sentence without the logo or colors, for screen readers
+      --anonymize           Mask usernames, hostnames, IP and MAC addresses for shareable screenshots
+      --ascii               Draw boxes, bars and ellipses with ASCII and ignore image logos,
+                            for dumb terminals and serial consoles
+      --backend <BACKEND>   How to draw image logos: kitty, sixel, iterm2, braille, ascii or auto (default)
+      --collect-only[=timings]
+                            Gather the stats without printing them (or only how long each module
+                            took), to warm the caches or measure collection alone
+      --color <WHEN>        Color the output: auto (default, for terminals unless NO_COLOR is set),
+                            always or never
+      --copy[=MODE]         Also copy the output to the clipboard, as plain text (default) or with its
+                            colors (ansi)
+      --export <TARGET>     Print the stats for a widget toolkit: eww (defvar lines to include)
+                            or conky (text for execpi)
+      --fail-fast           Stop at the first module that fails instead of printing what was gathered
+      --fast                Don't start any programs, leaving out the stats that need one
+      --fields <LIST>       Comma separated columns for --format tsv, e.g. host,OS,RAM
+      --format <FORMAT>     Output format: text (default), plain, json, yaml, toml,
+                            tsv, env or html
+      --image <PATH>        Show an image as the logo, on terminals that can draw one
+      --logo <NAME>         Show another logo instead of the distro's, or 'random' for any of them
+      --logo-file <PATH>    Use plain text art from a file instead of the distro logo, - for stdin
+      --max-lines <N>       Print at most N lines
+      --oneline             Print a few stats on one line for shell prompts and tmux, e.g.
+                            arch | 6.9.3 | up 3d4h | 1842 pkgs | 12.1/32.0 GiB
+  -q, --quiet               Don't print warnings
+      --simulate <FIXTURE>  Describe the system captured in a JSON fixture instead of this one
+      --template <TEMPLATE> Lay out the stats with a template file or string, e.g.
+                            '{os} on {kernel}, up {uptime}'
+      --waybar              Print the JSON of a Waybar custom module: a one-line summary with the full
+                            output as its tooltip
+      --width <N>           Fit the output into N columns instead of the terminal's width, cutting off
+                            longer values
+  -h, --help                Print help
+  -V, --version             Print version";
+
+/// Command line options, and the options a library user gathers and renders with
+#[derive(Default)]
+pub struct Args {
+    pub anonymize: bool,
+    pub ascii: bool,
+    pub backend: Option<String>,
+    /// `--collect-only`, and whether to print the module timings
+    pub collect_only: Option<bool>,
+    /// `--color always` or `never`, None to decide from the terminal
+    pub color: Option<bool>,
+    pub copy: Option<CopyMode>,
+    pub fail_fast: bool,
+    pub fast: bool,
+    pub fields: Option<String>,
+    pub format: Format,
+    pub image: Option<String>,
+    pub logo: Option<String>,
+    pub logo_file: Option<String>,
+    pub max_lines: Option<usize>,
+    pub quiet: bool,
+    pub simulate: Option<String>,
+    pub subcommand: Option<Subcommand>,
+    /// The `--template` itself, read from the file when it names one
+    pub template: Option<String>,
+    pub width: Option<usize>,
+}
+
+/// Commands that run instead of the usual fetch output
+pub enum Subcommand {
+    LogoList,
+    LogoShow(String),
+    Schema,
+    Bench { runs: usize, save: bool },
+    Segment(String),
+}
+
+/// What `--copy` puts on the clipboard
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CopyMode {
+    /// The output without escape sequences, for pasting into chats
+    Plain,
+    /// The output as printed, colors included
+    Ansi,
+}
+
+/// How the gathered stats are printed
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// The logo with the stats beside it
+    #[default]
+    Text,
+    /// The stats alone, without the logo or colors
+    Plain,
+    Json,
+    Yaml,
+    Toml,
+    Tsv,
+    Env,
+    /// A standalone HTML page of the text output
+    Html,
+    /// Full sentences without the logo or colors, for screen readers (`--accessible`)
+    Accessible,
+    /// A few stats on one line, for prompts and status lines (`--oneline`)
+    Oneline,
+    /// Bar text and tooltip for a Waybar custom module (`--waybar`)
+    Waybar,
+    /// eww variable definitions (`--export eww`)
+    Eww,
+    /// conky text markup (`--export conky`)
+    Conky,
+    /// The user's own layout (`--template`)
+    Template,
+}
+
+impl Args {
+
+    /// The options vega was started with, exiting with usage errors, `--help` and `--version`
+    pub fn from_env() -> Args {
+        Args::parse(env::args().skip(1))
+    }
+
+    fn parse(args: impl Iterator<Item = String>) -> Args {
+        let mut parsed = Args::default();
+        let mut args = args.peekable();
+
+        // Subcommands come first, e.g. `vega logo show debian`
+        if args.peek().is_some_and(|arg| arg == "schema") {
+            args.next();
+            parsed.subcommand = Some(Subcommand::Schema);
+        } else if args.peek().is_some_and(|arg| arg == "bench") {
+            args.next();
+            let runs = match args.next_if(|arg| !arg.starts_with('-')) {
+                Some(runs) => runs.parse::<usize>().ok().filter(|runs| *runs > 0).unwrap_or_else(|| {
+                    eprintln!("vega: usage: vega bench [RUNS] [--save]");
+                    exit(EXIT_USAGE);
+                }),
+                None => 10,
+            };
+            parsed.subcommand = Some(Subcommand::Bench { runs, save: false });
+        } else if args.peek().is_some_and(|arg| arg == "segment") {
+            args.next();
+            let Some(name) = args.next_if(|arg| !arg.starts_with('-')) else {
+                eprintln!("vega: usage: vega segment <NAME>");
+                exit(EXIT_USAGE);
+            };
+            parsed.subcommand = Some(Subcommand::Segment(name));
+        } else if args.peek().is_some_and(|arg| arg == "logo") {
+            args.next();
+            parsed.subcommand = match (args.next().as_deref(), args.next()) {
+                (Some("list"), None) => Some(Subcommand::LogoList),
+                (Some("show"), Some(name)) => Some(Subcommand::LogoShow(name)),
+                _ => {
+                    eprintln!("vega: usage: vega logo list | vega logo show <NAME>");
+                    exit(EXIT_USAGE);
+                },
+            };
+        }
+
+        // The option that picked the output format, only one of them can
+        let mut format_option: Option<String> = None;
+
+        while let Some(arg) = args.next() {
+            // Options taking a value accept both `--option value` and `--option=value`
+            let (option, inline_value) = match arg.split_once('=') {
+                Some((option, value)) if option.starts_with("--") => (option, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || inline_value.clone().or_else(|| args.next()).unwrap_or_else(|| {
+                eprintln!("vega: option '{}' requires a value\n\n{}", option, HELP);
+                exit(EXIT_USAGE);
+            });
+            let positive = |value: String| value.parse::<usize>().ok().filter(|number| *number > 0).unwrap_or_else(|| {
+                eprintln!("vega: option '{}' expects a positive number, got '{}'", option, value);
+                exit(EXIT_USAGE);
+            });
+
+            if matches!(option, "--accessible" | "--export" | "--format" | "--oneline" | "--template" | "--waybar") {
+                if let Some(previous) = format_option.as_ref().filter(|previous| *previous != option) {
+                    eprintln!("vega: option '{}' can't be combined with '{}', both choose the output format", option, previous);
+                    exit(EXIT_USAGE);
+                }
+                format_option = Some(option.to_string());
+            }
+
+            match option {
+                "--accessible" => parsed.format = Format::Accessible,
+                "--anonymize" => parsed.anonymize = true,
+                "--ascii" => parsed.ascii = true,
+                "--backend" => {
+                    let backend = value();
+                    if !matches!(backend.as_str(), "kitty" | "sixel" | "iterm2" | "braille" | "ascii" | "auto") {
+                        eprintln!("vega: unknown backend '{}', expected kitty, sixel, iterm2, braille, ascii or auto", backend);
+                        exit(EXIT_USAGE);
+                    }
+                    parsed.backend = Some(backend);
+                },
+                "--collect-only" => parsed.collect_only = match inline_value.as_deref() {
+                    None => Some(false),
+                    Some("timings") => Some(true),
+                    Some(output) => {
+                        eprintln!("vega: unknown collect-only output '{}', expected timings", output);
+                        exit(EXIT_USAGE);
+                    },
+                },
+                "--color" => parsed.color = match value().as_str() {
+                    "auto" => None,
+                    "always" => Some(true),
+                    "never" => Some(false),
+                    when => {
+                        eprintln!("vega: unknown color mode '{}', expected auto, always or never", when);
+                        exit(EXIT_USAGE);
+                    },
+                },
+                // The mode is optional, so only `--copy=ansi` sets it and a following argument is left alone
+                "--copy" => parsed.copy = match inline_value.as_deref() {
+                    None | Some("plain") => Some(CopyMode::Plain),
+                    Some("ansi") => Some(CopyMode::Ansi),
+                    Some(mode) => {
+                        eprintln!("vega: unknown copy mode '{}', expected plain or ansi", mode);
+                        exit(EXIT_USAGE);
+                    },
+                },
+                "--export" => parsed.format = match value().as_str() {
+                    "eww" => Format::Eww,
+                    "conky" => Format::Conky,
+                    target => {
+                        eprintln!("vega: unknown export target '{}', expected eww or conky", target);
+                        exit(EXIT_USAGE);
+                    },
+                },
+                "--fail-fast" => parsed.fail_fast = true,
+                "--fast" => parsed.fast = true,
+                "--fields" => parsed.fields = Some(value()),
+                "--format" => parsed.format = match value().as_str() {
+                    "text" => Format::Text,
+                    "plain" => Format::Plain,
+                    "json" => Format::Json,
+                    "yaml" => Format::Yaml,
+                    "toml" => Format::Toml,
+                    "tsv" => Format::Tsv,
+                    "env" => Format::Env,
+                    "html" => Format::Html,
+                    format => {
+                        eprintln!("vega: unknown format '{}', expected text, plain, json, yaml, toml, tsv, env or html", format);
+                        exit(EXIT_USAGE);
+                    },
+                },
+                "--image" => parsed.image = Some(value()),
+                "--logo" => parsed.logo = Some(value()),
+                "--logo-file" => parsed.logo_file = Some(value()),
+                "--max-lines" => parsed.max_lines = Some(positive(value())),
+                "--oneline" => parsed.format = Format::Oneline,
+                "-q" | "--quiet" => parsed.quiet = true,
+                "--save" => match &mut parsed.subcommand {
+                    Some(Subcommand::Bench { save, .. }) => *save = true,
+                    _ => {
+                        eprintln!("vega: unknown option '{}'\n\n{}", arg, HELP);
+                        exit(EXIT_USAGE);
+                    },
+                },
+                "--simulate" => parsed.simulate = Some(value()),
+                "--template" => {
+                    // A path to a template file, or else the template itself
+                    let template = value();
+                    parsed.template = Some(if Path::new(&template).is_file() {
+                        fs::read_to_string(&template).unwrap_or_else(|err| {
+                            eprintln!("vega: can't read template {}: {}", template, err);
+                            exit(EXIT_NO_INPUT);
+                        })
+                    } else {
+                        template
+                    });
+                    parsed.format = Format::Template;
+                },
+                "--waybar" => parsed.format = Format::Waybar,
+                "--width" => parsed.width = Some(positive(value())),
+                "-h" | "--help" => {
+                    println!("{}", HELP);
+                    exit(0);
+                },
+                "-V" | "--version" => {
+                    println!("vega {}", env!("CARGO_PKG_VERSION"));
+                    exit(0);
+                },
+                _ => {
+                    eprintln!("vega: unknown option '{}'\n\n{}", arg, HELP);
+                    exit(EXIT_USAGE);
+                },
+            }
+        }
+
+        parsed
+    }
+
+}
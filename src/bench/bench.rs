@@ -0,0 +1,129 @@
+use crate::_utils::status::take_timings;
+use crate::config::config::cache_dir;
+use crate::config::context::Context;
+use crate::data::common::{format_system_info, get_system_info};
+use crate::theme::color::color_depth;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::time::{Duration, Instant};
+
+/// `vega bench`: times `runs` full collections and prints the p50/p95 of the total and of each
+/// module, next to the saved baseline's p50 when there is one
+///
+/// The first run fills what's only looked up once per process (the config, interfaces, os-release,
+/// ...), so it's shown on its own as the cold start and the percentiles are of the warm runs after it.
+pub fn run_bench(runs: usize, save: bool) {
+    let mut first = Duration::ZERO;
+    let mut totals = Vec::with_capacity(runs);
+    let mut modules: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+
+    for run in 0..runs {
+        take_timings();
+        let start = Instant::now();
+        format_system_info(&get_system_info(&Context::current()), color_depth(), None);
+        let elapsed = start.elapsed();
+        let timings = take_timings();
+
+        // A single run is all there is to go by
+        if run == 0 {
+            first = elapsed;
+            if runs > 1 {
+                continue;
+            }
+        }
+        totals.push(elapsed);
+        for (module, time) in timings {
+            modules.entry(module).or_default().push(time);
+        }
+    }
+
+    // The cold start and the total first, then the modules alphabetically
+    let results: Vec<(String, Duration, Duration)> = [("first run".to_string(), vec![first]), ("total".to_string(), totals)].into_iter()
+        .chain(modules)
+        .map(|(name, mut times)| {
+            times.sort();
+            (name, percentile(&times, 0.5), percentile(&times, 0.95))
+        })
+        .collect();
+
+    let baseline = read_baseline();
+    if runs == 1 {
+        println!("1 run");
+    } else {
+        println!("{} runs, percentiles of the {} after the first", runs, runs - 1);
+    }
+    println!("{:<14}{:>10}{:>10}{:>22}", "module", "p50", "p95", "baseline p50");
+    for (name, p50, p95) in &results {
+        let compared = match baseline.get(name) {
+            Some(base) if !base.is_zero() => format!(
+                "{} ({:+.0}%)",
+                format_duration(*base), (p50.as_secs_f64() / base.as_secs_f64() - 1.0) * 100.0
+            ),
+            _ => "-".to_string(),
+        };
+        println!("{:<14}{:>10}{:>10}{:>22}", name, format_duration(*p50), format_duration(*p95), compared);
+    }
+
+    if save {
+        write_baseline(&results);
+        println!("\nSaved as the new baseline");
+    }
+}
+
+/// `--collect-only`: gathers everything once without rendering it, optionally printing how long the
+/// collection and each module took
+pub fn collect_only(timings: bool) {
+    take_timings();
+    let start = Instant::now();
+    get_system_info(&Context::current());
+    let total = start.elapsed();
+
+    if timings {
+        let mut modules = take_timings();
+        modules.sort();
+        for (name, time) in [("total".to_string(), total)].into_iter().chain(modules) {
+            println!("{:<14}{:>10}", name, format_duration(time));
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted durations
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.saturating_sub(1)).copied().unwrap_or_default()
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// The p50 per module of the last `vega bench --save`
+fn read_baseline() -> HashMap<String, Duration> {
+    let Some(dir) = cache_dir() else {
+        return HashMap::new();
+    };
+
+    // One "module\tp50_us\tp95_us" line per module
+    fs::read_to_string(dir.join("bench")).unwrap_or_default().lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let name = fields.next()?.to_string();
+            let p50 = fields.next()?.parse().ok()?;
+            Some((name, Duration::from_micros(p50)))
+        })
+        .collect()
+}
+
+fn write_baseline(results: &[(String, Duration, Duration)]) {
+    let Some(dir) = cache_dir() else {
+        return;
+    };
+
+    let content: String = results.iter()
+        .map(|(name, p50, p95)| format!("{}\t{}\t{}\n", name, p50.as_micros(), p95.as_micros()))
+        .collect();
+
+    if let Err(err) = fs::create_dir_all(&dir).and_then(|_| fs::write(dir.join("bench"), content)) {
+        eprintln!("vega: can't save the baseline: {}", err);
+    }
+}
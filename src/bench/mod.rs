@@ -0,0 +1 @@
+pub mod bench;
\ No newline at end of file
@@ -0,0 +1,86 @@
+use crate::_utils::status::config_error;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use toml::{Table, Value};
+
+/// User configuration, the settings of `config.toml`. Without one every setting has its default.
+#[derive(Default)]
+pub struct Config {
+    table: Table,
+}
+
+impl Config {
+
+    /// Loads the user's `$XDG_CONFIG_HOME/vega/config.toml` (or `~/.config/vega/config.toml`)
+    pub fn load() -> Config {
+        config_dir()
+            .map(|dir| dir.join("config.toml"))
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|content| Config::parse(&content).unwrap_or_else(|err| {
+                config_error(&format!("ignoring invalid config file: {}", err));
+                Config::default()
+            }))
+            .unwrap_or_default()
+    }
+
+    /// Reads settings in the format of `config.toml`
+    pub fn parse(content: &str) -> Result<Config, String> {
+        let table = content.parse::<Table>().map_err(|err| err.message().to_string())?;
+        Ok(Config { table })
+    }
+
+    /// Looks up a value by dotted key, e.g. `macos.extended` reads `extended` from the `[macos]` table
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        let mut parts = key.split('.');
+        let mut value = self.table.get(parts.next()?)?;
+
+        for part in parts {
+            value = value.as_table()?.get(part)?;
+        }
+
+        Some(value)
+    }
+
+    pub fn get_bool(&self, key: &str, default: bool) -> bool {
+        self.get(key).and_then(Value::as_bool).unwrap_or(default)
+    }
+
+    pub fn get_int(&self, key: &str, default: i64) -> i64 {
+        self.get(key).and_then(Value::as_integer).unwrap_or(default)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(Value::as_str)
+    }
+
+    /// Reads an array of strings, skipping non-string entries, or an empty list if the key is unset
+    pub fn get_str_list(&self, key: &str) -> Vec<&str> {
+        self.get(key)
+            .and_then(Value::as_array)
+            .map(|values| values.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default()
+    }
+
+}
+
+/// vega's config directory, `$XDG_CONFIG_HOME/vega` or `~/.config/vega`
+pub fn config_dir() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_home.join("vega"))
+}
+
+/// vega's cache directory, `$XDG_CACHE_HOME/vega` or `~/.cache/vega`
+pub fn cache_dir() -> Option<PathBuf> {
+    Some(cache_home()?.join("vega"))
+}
+
+/// The user's cache directory shared by every program, `$XDG_CACHE_HOME` or `~/.cache`
+pub fn cache_home() -> Option<PathBuf> {
+    env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+}
@@ -0,0 +1,197 @@
+// What gathering and rendering run with: the options, the config, the theme made from it and the
+// system being described. The command line installs one for the whole process, library users hand
+// their own to `get_system_info` and `renderer` instead, so nothing reads the host's argv or config.
+// What belongs to the terminal vega's own stdout is attached to (its color depth and background, see
+// `color_depth` and `TERMINAL_BACKGROUND`) and the user's logos in the config directory (`LOGOS`)
+// stay process-wide.
+use crate::_utils::fixture::FixtureSystem;
+use crate::_utils::system_provider::{RealSystem, SystemProvider};
+use crate::args::args::Args;
+use crate::config::config::Config;
+use crate::logo::backend::{select_backend, Backend};
+use crate::theme::appearance::{get_appearance, Appearance};
+use crate::theme::theme::{load_theme, Theme};
+use std::cell::RefCell;
+use std::env;
+use std::ops::Deref;
+use std::sync::{Arc, OnceLock};
+
+/// The options, config, theme and system of one run, shared with every thread it's entered on
+#[derive(Clone)]
+pub struct Context {
+    pub args: Arc<Args>,
+    pub config: Shared<Config>,
+    pub theme: Shared<Theme>,
+    /// How images are drawn, from `--backend` and `--ascii` or else the terminal
+    pub backend: Shared<Backend>,
+    /// The described system's light/dark preference
+    pub appearance: Shared<Appearance>,
+    pub system: &'static dyn SystemProvider,
+}
+
+/// A value made on first use (e.g. the config file, read once something asks for a setting) unless
+/// it was given up front, and then shared by every clone
+pub struct Shared<T: 'static> {
+    cell: Arc<OnceLock<T>>,
+    init: fn() -> T,
+}
+
+impl<T> Shared<T> {
+
+    fn lazy(init: fn() -> T) -> Shared<T> {
+        Shared { cell: Arc::new(OnceLock::new()), init }
+    }
+
+    fn given(value: T, init: fn() -> T) -> Shared<T> {
+        Shared { cell: Arc::new(OnceLock::from(value)), init }
+    }
+
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Shared<T> {
+        Shared { cell: Arc::clone(&self.cell), init: self.init }
+    }
+}
+
+impl<T> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.cell.get_or_init(self.init)
+    }
+}
+
+/// Installed by the command line with `install`
+static INSTALLED: OnceLock<Context> = OnceLock::new();
+
+thread_local! {
+    /// Set by `enter`, takes precedence over the installed context on its thread
+    static ENTERED: RefCell<Option<Context>> = const { RefCell::new(None) };
+}
+
+impl Context {
+
+    /// A context for a library user: their options and config, describing `system`. The theme is
+    /// made from the config when first needed.
+    pub fn new(args: Args, config: Config, system: &'static dyn SystemProvider) -> Context {
+        Context {
+            args: Arc::new(args),
+            config: Shared::given(config, Config::default),
+            theme: Shared::lazy(load_theme),
+            backend: Shared::lazy(select_backend),
+            appearance: Shared::lazy(get_appearance),
+            system,
+        }
+    }
+
+    /// The command line's context: the config file, and the fixture given with `--simulate` or
+    /// `VEGA_FIXTURE` or else this machine. Exits if the fixture can't be loaded.
+    pub fn from_args(args: Args) -> Context {
+        let fixture = args.simulate.clone().or_else(|| env::var("VEGA_FIXTURE").ok().filter(|path| !path.is_empty()));
+        let system: Box<dyn SystemProvider> = match fixture {
+            Some(path) => Box::new(FixtureSystem::load(&path)),
+            None => Box::new(RealSystem::new(args.fast)),
+        };
+
+        Context {
+            args: Arc::new(args),
+            config: Shared::lazy(Config::load),
+            theme: Shared::lazy(load_theme),
+            backend: Shared::lazy(select_backend),
+            appearance: Shared::lazy(get_appearance),
+            system: Box::leak(system),
+        }
+    }
+
+    /// This context describing another system, with nothing learned from the current one carried over
+    pub fn with_system(self, system: &'static dyn SystemProvider) -> Context {
+        Context { system, appearance: Shared::lazy(get_appearance), ..self }
+    }
+
+    /// The context of the current thread: the entered one, else the installed one, else defaults
+    /// on this machine
+    pub fn current() -> Context {
+        if let Some(context) = ENTERED.with_borrow(Clone::clone) {
+            return context;
+        }
+
+        INSTALLED.get_or_init(|| Context::new(Args::default(), Config::default(), Box::leak(Box::new(RealSystem::new(false))))).clone()
+    }
+
+    /// Makes this the context of the whole process, for the command line. Only the first call counts.
+    pub fn install(self) {
+        let _ = INSTALLED.set(self);
+    }
+
+    /// Runs `f` in this context on the current thread. Threads started by `f` are in the installed
+    /// context unless they're entered into this one as well.
+    pub fn enter<T>(&self, f: impl FnOnce() -> T) -> T {
+        let previous = ENTERED.replace(Some(self.clone()));
+        let result = f();
+        ENTERED.set(previous);
+        result
+    }
+
+}
+
+/// The options of the current context
+pub fn args() -> Arc<Args> {
+    Context::current().args
+}
+
+/// The config of the current context
+pub fn config() -> Shared<Config> {
+    Context::current().config
+}
+
+/// The theme of the current context, selected with `theme.name` in its config
+pub fn theme() -> Shared<Theme> {
+    Context::current().theme
+}
+
+/// How the current context draws images
+pub fn backend() -> Backend {
+    *Context::current().backend
+}
+
+/// The light/dark preference of the current context's system, looked up once per context
+pub fn appearance() -> Appearance {
+    *Context::current().appearance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_utils::fixture::with_fixture;
+    use serde_json::json;
+
+    fn with_config(toml: &str) -> Context {
+        Context::new(Args::default(), Config::parse(toml).unwrap(), Box::leak(Box::new(RealSystem::new(true))))
+    }
+
+    #[test]
+    fn entered_contexts_are_separate() {
+        let (ayu, nord) = (with_config("[theme]\nname = \"ayu\""), with_config("[theme]\nname = \"nord\""));
+        // Nested entries restore the outer context when they return
+        ayu.enter(|| {
+            nord.enter(|| assert_eq!(config().get_str("theme.name"), Some("nord")));
+            assert_eq!(config().get_str("theme.name"), Some("ayu"));
+        });
+    }
+
+    #[test]
+    fn looks_up_the_appearance_of_each_system() {
+        let scheme = |scheme: &str| json!({ "commands": { "gsettings get org.gnome.desktop.interface color-scheme": scheme } });
+        assert_eq!(with_fixture(scheme("'prefer-dark'"), appearance), Appearance::Dark);
+        assert_eq!(with_fixture(scheme("'prefer-light'"), appearance), Appearance::Light);
+    }
+
+    #[test]
+    fn threads_entered_into_a_context_share_it() {
+        let context = with_config("[layout]\npadding_left = 4");
+        let inner = context.clone();
+        let padding = context.enter(|| std::thread::spawn(move || inner.enter(|| config().get_int("layout.padding_left", 0))).join().unwrap());
+        assert_eq!(padding, 4);
+    }
+}
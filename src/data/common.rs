@@ -1,38 +1,287 @@
-use crate::_utils::run_command::ShellReturn;
+use crate::_utils::system_provider::provider;
+use crate::_utils::anonymize::redact;
+use crate::_utils::status::run_module;
+use crate::config::context::{args, config, theme, Context};
+use crate::data::gaming::gaming::get_gaming_info;
+use crate::data::git::git::get_git_info;
 use crate::data::hardware::hardware::get_hardware_info;
+use crate::data::hardware::individual_stats::start_cpu_sample;
+use crate::data::macos::macos::get_macos_info;
+use crate::data::network::individual_stats::start_throughput_sample;
+use crate::data::network::network::get_network_info;
+use crate::data::raspberry_pi::raspberry_pi::get_raspberry_pi_info;
+use crate::data::rules::apply_rules;
+use crate::data::software::individual_stats::get_hostname;
 use crate::data::software::software::get_software_info;
-use crate::sh;
-use colored::Colorize;
-use std::process::Command;
-use sysinfo::{MemoryRefreshKind, RefreshKind, System};
+use crate::data::storage::individual_stats::start_disk_io_sample;
+use crate::data::storage::storage::get_storage_info;
+use crate::data::time::time::get_time_info;
+use crate::data::toolchains::toolchains::get_toolchains_info;
+use crate::logo::backend::Backend;
+use crate::logo::graphics::render_thumbnail;
+use crate::theme::color::{paint, ColorDepth, Style};
+use crate::theme::theme::parse_color;
+use colored::Color;
 
-pub fn get_system_info() -> impl Iterator<Item = String> {
+/// Everything vega gathered, independent of how it ends up being shown
+pub struct SystemInfo {
+    pub user: String,
+    pub host: String,
+    /// Hardware and Software, followed by whichever optional sections have something to show
+    pub sections: Vec<Section>,
+}
+
+#[derive(Default)]
+pub struct Section {
+    pub title: String,
+    pub stats: Vec<Stat>,
+    /// Header color from a `[[sections]]` entry, plain bold otherwise
+    pub color: Option<Color>,
+}
+
+/// A gathered stat, see `registry::STATS` for the IDs
+pub struct Stat {
+    pub id: String,
+    pub label: String,
+    pub value: StatValue,
+}
+
+/// A stat as displayed, plus the typed values behind it (e.g. "used_bytes") for machine-readable output
+pub struct StatValue {
+    pub display: String,
+    pub values: Vec<(&'static str, u64)>,
+    /// Shown in the theme's warning color, for things worth a second look (e.g. running as root)
+    pub warning: bool,
+    /// Path of an image drawn after the value where the output can show one, e.g. the wallpaper's
+    /// thumbnail on a terminal. Machine-readable output leaves it out.
+    pub image: Option<String>,
+}
+
+impl StatValue {
+
+    pub fn with(mut self, name: &'static str, value: u64) -> StatValue {
+        self.values.push((name, value));
+        self
+    }
+
+    pub fn warning(mut self, warning: bool) -> StatValue {
+        self.warning = warning;
+        self
+    }
+
+    pub fn image(mut self, path: String) -> StatValue {
+        self.image = Some(path);
+        self
+    }
 
-    let mut sys = System::new_with_specifics(
-        RefreshKind::nothing()
-            .with_memory(MemoryRefreshKind::nothing().with_ram())
-    );
+}
+
+impl From<String> for StatValue {
+    fn from(display: String) -> StatValue {
+        StatValue { display, values: Vec::new(), warning: false, image: None }
+    }
+}
 
-    let mut lines: Vec<String> = Vec::with_capacity(19);
-    
-    lines.append(&mut get_title());
-    lines.append(&mut get_hardware_info(&mut sys));
-    lines.push(String::new());
-    lines.append(&mut get_software_info());
-    
-    lines.into_iter()
-    
+/// Gathers every enabled stat of the system `context` describes, with its options and config
+pub fn get_system_info(context: &Context) -> SystemInfo {
+    context.enter(gather_system_info)
 }
 
+fn gather_system_info() -> SystemInfo {
+
+    // Kick off CPU, network and disk sampling first so their delay overlaps with the other gatherers
+    let with_processes = config().get_bool("processes.enabled", false);
+    let cpu_sample = (config().get_bool("cpu.usage", false) || config().get_bool("cpu.per_core", false) || with_processes)
+        .then(|| start_cpu_sample(with_processes));
+    let throughput_sample = config().get_bool("network.throughput", false).then(start_throughput_sample);
+    let disk_io_sample = config().get_bool("storage.io", false).then(start_disk_io_sample);
+
+    // Each section is a module of its own, one crashing only loses that section
+    let mut modules: Vec<Module> = vec![
+        ("hardware", Box::new(move || get_hardware_info(cpu_sample))),
+        ("software", Box::new(get_software_info)),
+        ("network", Box::new(move || get_network_info(throughput_sample))),
+        ("storage", Box::new(move || get_storage_info(disk_io_sample))),
+        ("raspberry_pi", Box::new(get_raspberry_pi_info)),
+        ("gaming", Box::new(get_gaming_info)),
+        ("toolchains", Box::new(get_toolchains_info)),
+        ("git", Box::new(get_git_info)),
+        ("time", Box::new(get_time_info)),
+    ];
+    if provider().uname().sysname.as_str() == "Darwin" {
+        modules.push(("macos", Box::new(get_macos_info)));
+    }
+    let mut sections = gather_sections(modules);
+    apply_rules(&mut sections);
+    let mut sections = regroup_sections(sections);
+
+    // Optional sections are left out entirely when empty
+    sections.retain(|section| !section.stats.is_empty());
 
-fn get_title() -> Vec<String> {
+    SystemInfo {
+        user: redact(provider().username(), "user"),
+        host: redact(get_hostname(), "hostname"),
+        sections,
+    }
 
-    let username = sh!("whoami").stdout.trim().to_string();
-    let hostname = sh!("uname -n").stdout.trim().to_string();
+}
+
+/// Regroups the gathered stats into the `[[sections]]` from the config, each a title, an optional
+/// header color and the stat IDs it shows in order. Stats no section lists are left out.
+fn regroup_sections(sections: Vec<Section>) -> Vec<Section> {
+    let config = config();
+    let Some(layout) = config.get("sections").and_then(|sections| sections.as_array()) else {
+        return sections;
+    };
+
+    let mut stats: Vec<Stat> = sections.into_iter().flat_map(|section| section.stats).collect();
+    layout.iter()
+        .filter_map(|entry| entry.as_table())
+        .map(|entry| {
+            let mut section = Section {
+                title: entry.get("title").and_then(|title| title.as_str()).unwrap_or_default().to_string(),
+                color: entry.get("color").and_then(|color| color.as_str()).and_then(parse_color),
+                ..Section::default()
+            };
+
+            // Stats shown more than once (e.g. a disk per mount) share an ID and move together
+            let ids = entry.get("stats").and_then(|ids| ids.as_array()).into_iter().flatten().filter_map(|id| id.as_str());
+            for id in ids {
+                let (matching, rest): (Vec<Stat>, Vec<Stat>) = std::mem::take(&mut stats).into_iter().partition(|stat| stat.id == id);
+                section.stats.extend(matching);
+                stats = rest;
+            }
+            section
+        })
+        .collect()
+}
 
-    let title = format!("{}@{}", username.blue().bold(), hostname.blue().bold());
-    let dashes = "=".repeat(username.len() + hostname.len() + 1);
+/// A section's module name and its gatherer
+type Module = (&'static str, Box<dyn FnOnce() -> Section + Send>);
 
-    vec![title, dashes]
+/// Gathers the modules one after the other, returning their sections in the same order.
+/// Software goes first (but is still shown after hardware) to give the CPU sample time.
+#[cfg(not(feature = "async"))]
+fn gather_sections(modules: Vec<Module>) -> Vec<Section> {
+    let mut modules: Vec<(usize, Module)> = modules.into_iter().enumerate().collect();
+    modules.sort_by_key(|(_, (module, _))| *module != "software");
 
-}
\ No newline at end of file
+    let mut sections: Vec<(usize, Section)> = modules.into_iter()
+        .map(|(i, (module, gather))| (i, run_module(module, gather)))
+        .collect();
+    sections.sort_by_key(|(i, _)| *i);
+
+    sections.into_iter().map(|(_, section)| section).collect()
+}
+
+/// Gathers the modules concurrently under tokio, returning their sections in the same order. The
+/// time limit is per stat, see `gather_stats`.
+#[cfg(feature = "async")]
+fn gather_sections(modules: Vec<Module>) -> Vec<Section> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_time()
+        .build()
+        .expect("can't start the tokio runtime");
+
+    let context = Context::current();
+    let sections = runtime.block_on(async {
+        // The gatherers block on files and programs, so each runs on the blocking pool
+        let tasks: Vec<_> = modules.into_iter()
+            .map(|(module, gather)| {
+                let context = context.clone();
+                tokio::task::spawn_blocking(move || context.enter(|| run_module(module, gather)))
+            })
+            .collect();
+
+        let mut sections = Vec::new();
+        for task in tasks {
+            sections.push(task.await.unwrap_or_default());
+        }
+        sections
+    });
+
+    // Don't wait for stats that timed out
+    runtime.shutdown_background();
+    sections
+}
+
+/// The lines shown beside the logo, colored for output at `depth`: a user@host title, then each
+/// section separated by `layout.section_spacing` blank lines (one by default). Stats with an image
+/// get a thumbnail drawn with `thumbnails`, for a terminal that can show one.
+pub fn format_system_info(info: &SystemInfo, depth: ColorDepth, thumbnails: Option<Backend>) -> Vec<String> {
+    let accent = Some(theme().accent);
+    let title = format!("{}@{}", paint(&info.user, accent, Style::Bold, depth), paint(&info.host, accent, Style::Bold, depth));
+    let dashes = "=".repeat(info.user.len() + info.host.len() + 1);
+    let spacing = config().get_int("layout.section_spacing", 1).max(0) as usize;
+
+    let mut lines = vec![title, dashes];
+    for (i, section) in info.sections.iter().enumerate() {
+        if i > 0 {
+            lines.extend(vec![String::new(); spacing]);
+        }
+        lines.append(&mut format_section_lines(section, depth, thumbnails));
+    }
+
+    lines
+}
+
+/// Builds a section from its title and stats
+pub fn format_section(title: &str, stats: Vec<Stat>) -> Section {
+    Section { title: title.to_string(), stats, color: None }
+}
+
+/// Formats a section as a bold "< Title >" followed by box-drawn `LBL = value` lines, sections
+/// without a title skip the header
+fn format_section_lines(section: &Section, depth: ColorDepth, thumbnails: Option<Backend>) -> Vec<String> {
+    let theme = theme();
+    let last = section.stats.len().saturating_sub(1);
+    let mut lines = if section.title.is_empty() {
+        Vec::new()
+    } else {
+        vec![paint(&format!("< {} >", section.title), section.color, Style::Bold, depth)]
+    };
+
+    for (i, Stat { label, value, .. }) in section.stats.iter().enumerate() {
+        let corner = match i {
+            0 if last == 0 => "═",
+            0 => "╔",
+            i if i == last => "╚",
+            _ => "╠",
+        };
+        let display = if value.warning {
+            paint(&value.display, Some(theme.warning), Style::Bold, depth)
+        } else {
+            paint(&value.display, None, Style::Dimmed, depth)
+        };
+        let bar = usage_bar(value).map(|bar| format!(" {}", paint(&bar, Some(theme.accent), Style::Regular, depth))).unwrap_or_default();
+        let thumbnail = value.image.as_ref()
+            .zip(thumbnails)
+            .and_then(|(path, backend)| render_thumbnail(path, backend))
+            .map(|thumbnail| format!(" {}", thumbnail))
+            .unwrap_or_default();
+        let label = paint(&format!("{} {:<3}{}", corner, label, theme.separator), Some(theme.label), Style::Bold, depth);
+        lines.push(format!("{}{}{}{}", label, display, bar, thumbnail));
+    }
+
+    lines
+}
+
+/// A bar of `layout.bar_width` (10) of the theme's bar characters for stats with a used and a total
+/// size (RAM, disks), e.g. "████░░░░░░" at 40%, when `layout.bars` is enabled
+fn usage_bar(value: &StatValue) -> Option<String> {
+    if !config().get_bool("layout.bars", false) {
+        return None;
+    }
+
+    let bytes = |name: &str| value.values.iter().find(|(key, _)| *key == name).map(|(_, bytes)| *bytes);
+    let (used, total) = (bytes("used_bytes")?, bytes("total_bytes")?);
+    if total == 0 {
+        return None;
+    }
+
+    let width = config().get_int("layout.bar_width", 10).max(1) as usize;
+    let filled = ((used as f64 / total as f64 * width as f64).round() as usize).min(width);
+    // --ascii only knows the default characters
+    let (full, empty) = if args().ascii { ('#', '-') } else { theme().bar };
+    Some(full.to_string().repeat(filled) + &empty.to_string().repeat(width - filled))
+}
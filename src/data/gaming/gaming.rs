@@ -0,0 +1,12 @@
+use crate::config::context::config;
+use crate::data::common::{format_section, Section};
+use crate::data::registry::{gather_stats, Samples};
+
+pub fn get_gaming_info() -> Section {
+    if !config().get_bool("gaming.enabled", true) {
+        return Section::default();
+    }
+
+    // Most desktops have nothing to report, and so get no section at all
+    format_section("Gaming", gather_stats("gaming", Samples::default()))
+}
@@ -0,0 +1,92 @@
+use crate::_utils::system_provider::provider;
+use crate::sh;
+use std::path::PathBuf;
+
+/// Gamescope session details when running under it (Steam Deck game mode, Bazzite, ChimeraOS),
+/// e.g. "Session (3.14.2)" or "Nested" for a gamescope window on a regular desktop
+pub fn get_gamescope() -> Option<String> {
+    let is_session = provider().env_var("XDG_CURRENT_DESKTOP").is_some_and(|desktop| desktop.eq_ignore_ascii_case("gamescope"))
+        || provider().env_var("XDG_SESSION_DESKTOP").is_some_and(|desktop| desktop.eq_ignore_ascii_case("gamescope"));
+    let is_nested = provider().env_var("GAMESCOPE_WAYLAND_DISPLAY").is_some();
+
+    if !is_session && !is_nested && sh!("pgrep", "-x", "gamescope").err_code != 0 {
+        return None;
+    }
+
+    // "gamescope version 3.14.2 (gcc 13.2.1)" on stderr
+    let version = sh!("gamescope", "--version");
+    let version = version.stderr.split_whitespace().chain(version.stdout.split_whitespace())
+        .skip_while(|word| *word != "version")
+        .nth(1)
+        .map(str::to_string);
+
+    let kind = if is_session { "Session" } else if is_nested { "Nested" } else { "Running" };
+    match version {
+        Some(version) => Some(format!("{} ({})", kind, version)),
+        None => Some(kind.to_string()),
+    }
+}
+
+/// Newest installed Proton, from Steam's own builds and custom ones in compatibilitytools.d,
+/// e.g. "Proton 9.0 (+2 more)"
+pub fn get_proton() -> Option<String> {
+    let home = PathBuf::from(provider().env_var("HOME")?);
+    let steam_roots = [
+        home.join(".steam/root"),
+        home.join(".local/share/Steam"),
+        home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+    ];
+
+    let mut versions: Vec<String> = steam_roots.iter()
+        .flat_map(|root| [root.join("steamapps/common"), root.join("compatibilitytools.d")])
+        .flat_map(|dir| provider().list_dir(&dir.to_string_lossy()))
+        .filter(|name| name.contains("Proton") || name.starts_with("GE-Proton"))
+        .collect();
+
+    // ~/.steam/root is usually a symlink to one of the other roots
+    versions.sort_by(|a, b| compare_versions(b, a));
+    versions.dedup();
+
+    let newest = versions.first()?;
+    match versions.len() - 1 {
+        0 => Some(newest.clone()),
+        more => Some(format!("{} (+{} more)", newest, more)),
+    }
+}
+
+/// Orders "Proton 8.0" < "Proton 9.0" < "GE-Proton9-20" by the numbers they contain
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let numbers = |name: &str| -> Vec<u32> {
+        name.split(|c: char| !c.is_ascii_digit())
+            .filter_map(|part| part.parse().ok())
+            .collect()
+    };
+
+    numbers(a).cmp(&numbers(b)).then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_utils::fixture::with_fixture;
+    use serde_json::json;
+
+    #[test]
+    fn picks_the_newest_proton() {
+        let fixture = json!({
+            "env": { "HOME": "/home/alice" },
+            "dirs": {
+                "/home/alice/.steam/root/steamapps/common": ["Proton 8.0", "Proton 9.0", "Steamworks Shared"],
+                "/home/alice/.steam/root/compatibilitytools.d": ["GE-Proton9-20"],
+                // The same library again, through the ~/.steam/root symlink
+                "/home/alice/.local/share/Steam/steamapps/common": ["Proton 8.0", "Proton 9.0", "Steamworks Shared"],
+            },
+        });
+        assert_eq!(with_fixture(fixture, get_proton).as_deref(), Some("GE-Proton9-20 (+2 more)"));
+    }
+
+    #[test]
+    fn has_no_proton_without_steam() {
+        assert_eq!(with_fixture(json!({ "env": { "HOME": "/home/alice" } }), get_proton), None);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,2 @@
+pub mod gaming;
+pub mod individual_stats;
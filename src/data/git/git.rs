@@ -0,0 +1,17 @@
+use crate::config::context::config;
+use crate::data::common::{format_section, Section};
+use crate::data::git::individual_stats::get_repo_root;
+use crate::data::registry::{gather_stats, Samples};
+
+pub fn get_git_info() -> Section {
+    if !config().get_bool("git.enabled", false) {
+        return Section::default();
+    }
+
+    // Only shown when run inside a work tree
+    if get_repo_root().is_none() {
+        return Section::default();
+    }
+
+    format_section("Git", gather_stats("git", Samples::default()))
+}
@@ -0,0 +1,43 @@
+use crate::sh;
+
+/// Top level directory of the git work tree vega was run from, if any
+pub fn get_repo_root() -> Option<String> {
+    let root = sh!("git", "rev-parse", "--show-toplevel");
+    let root = root.stdout.trim();
+
+    if root.is_empty() { None } else { Some(root.to_string()) }
+}
+
+/// Current branch, or the short commit hash when HEAD is detached
+pub fn get_branch() -> String {
+    let branch = sh!("git", "symbolic-ref", "--short", "-q", "HEAD");
+    if branch.err_code == 0 {
+        return branch.stdout.trim().to_string();
+    }
+
+    match sh!("git", "rev-parse", "--short", "HEAD").stdout.trim() {
+        "" => "None".to_string(),
+        hash => format!("detached at {}", hash),
+    }
+}
+
+/// "Clean", or a summary of uncommitted changes, e.g. "Dirty (2 modified, 1 untracked)"
+pub fn get_status() -> String {
+    // Porcelain lines start with a two letter XY status, "??" for untracked files
+    let status = sh!("git", "status", "--porcelain");
+    let (untracked, modified) = status.stdout.lines()
+        .filter(|line| !line.is_empty())
+        .fold((0, 0), |(untracked, modified), line| {
+            if line.starts_with("??") { (untracked + 1, modified) } else { (untracked, modified + 1) }
+        });
+
+    let mut changes = Vec::new();
+    if modified > 0 {
+        changes.push(format!("{} modified", modified));
+    }
+    if untracked > 0 {
+        changes.push(format!("{} untracked", untracked));
+    }
+
+    if changes.is_empty() { "Clean".to_string() } else { format!("Dirty ({})", changes.join(", ")) }
+}
@@ -0,0 +1,40 @@
+use crate::_utils::read_file::cat;
+use crate::_utils::system_provider::provider;
+
+/// Whether vega is running inside a container (Docker, Podman, systemd-nspawn, LXC, ...)
+pub fn in_container() -> bool {
+    provider().exists("/.dockerenv")
+        || provider().exists("/run/.containerenv")
+        || provider().env_var("container").is_some()
+        || cat("/proc/1/environ").split('\0').any(|var| var.starts_with("container="))
+}
+
+/// The container's memory usage and limit in bytes from cgroup v2, or `None` if unlimited
+///
+/// Inside a container's cgroup namespace, `/sys/fs/cgroup` is the container's own cgroup.
+pub fn get_memory_limit() -> Option<(u64, u64)> {
+    if !in_container() {
+        return None;
+    }
+
+    // "max" when unlimited
+    let limit = cat("/sys/fs/cgroup/memory.max").trim().parse::<u64>().ok()?;
+    let used = cat("/sys/fs/cgroup/memory.current").trim().parse::<u64>().ok()?;
+
+    Some((used, limit))
+}
+
+/// The container's CPU quota in CPUs from cgroup v2, e.g. 1.5, or `None` if unlimited
+pub fn get_cpu_limit() -> Option<f64> {
+    if !in_container() {
+        return None;
+    }
+
+    // "<quota> <period>" in microseconds, with quota "max" when unlimited
+    let cpu_max = cat("/sys/fs/cgroup/cpu.max");
+    let mut fields = cpu_max.split_whitespace();
+    let quota = fields.next()?.parse::<f64>().ok()?;
+    let period = fields.next()?.parse::<f64>().ok()?;
+
+    if period > 0.0 { Some(quota / period) } else { None }
+}
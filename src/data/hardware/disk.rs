@@ -0,0 +1,140 @@
+use crate::_utils::read_file::cat;
+use crate::_utils::status::fail;
+use crate::_utils::system_provider::provider;
+use crate::_utils::which::which;
+use crate::data::common::StatValue;
+use crate::sh;
+use std::path::Path;
+
+pub struct Mount {
+    pub device: String,
+    pub mount_point: String,
+    pub fs_type: String,
+}
+
+/// Disk usage and filesystem type of the filesystem holding `path`, e.g. "17GB / 251GB (/, ext4)"
+///
+/// Pooled filesystems (btrfs, zfs) report pool-level usage, since `statvfs` only sees one subvolume
+/// or dataset and its free space estimate is unreliable there.
+pub fn get_drive(path: &str) -> StatValue {
+    let mount = get_mount(path);
+
+    let usage = match &mount {
+        Some(mount) if mount.fs_type == "btrfs" => get_btrfs_usage(&mount.device),
+        Some(mount) if mount.fs_type == "zfs" => get_zfs_usage(&mount.device),
+        _ => None,
+    };
+
+    let Some((used_space, total_space)) = usage.or_else(|| provider().disk_usage(path)) else {
+        fail("hardware", &format!("can't read disk usage of {}", path));
+        return "Failed / Not Supported".to_string().into();
+    };
+
+    let usage = format!("{}GB / {}GB", used_space / 1073741824, total_space / 1073741824);
+    let display = match mount {
+        Some(mount) => format!("{} ({}, {})", usage, mount.mount_point, mount.fs_type),
+        None => usage,
+    };
+
+    StatValue::from(display)
+        .with("used_bytes", used_space)
+        .with("total_bytes", total_space)
+}
+
+/// Finds the mount holding `path`, i.e. the one with the longest mount point that is a prefix of it
+fn get_mount(path: &str) -> Option<Mount> {
+    get_mounts().into_iter()
+        .filter(|mount| Path::new(path).starts_with(&mount.mount_point))
+        .max_by_key(|mount| mount.mount_point.len())
+}
+
+/// All mounted filesystems
+pub fn get_mounts() -> Vec<Mount> {
+    match cat("/proc/mounts") {
+        // Linux: "/dev/nvme0n1p2 / btrfs rw,relatime 0 0"
+        proc_mounts if !proc_mounts.is_empty() => proc_mounts.lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                Some(Mount {
+                    device: fields.next()?.to_string(),
+                    mount_point: fields.next()?.replace("\\040", " "),
+                    fs_type: fields.next()?.to_string(),
+                })
+            })
+            .collect(),
+        // macOS and the BSDs: "/dev/disk3s1s1 on / (apfs, sealed, local, read-only, journaled)"
+        _ => sh!("mount").stdout.lines()
+            .filter_map(|line| {
+                let (device, rest) = line.split_once(" on ")?;
+                let (mount_point, options) = rest.rsplit_once(" (")?;
+                Some(Mount {
+                    device: device.to_string(),
+                    mount_point: mount_point.to_string(),
+                    fs_type: options.split([',', ')']).next()?.trim().to_string(),
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Pool-level usage of a btrfs filesystem from sysfs, accounting for RAID profiles
+fn get_btrfs_usage(device: &str) -> Option<(u64, u64)> {
+    // The filesystem's sysfs directory (named after its UUID) lists its member devices
+    let device_name = Path::new(device).file_name()?.to_str()?.to_string();
+    let fs_dir = provider().list_dir("/sys/fs/btrfs").into_iter()
+        .map(|uuid| format!("/sys/fs/btrfs/{}", uuid))
+        .find(|dir| provider().exists(&format!("{}/devices/{}", dir, device_name)))?;
+
+    // Device sizes are in 512 byte sectors
+    let total: u64 = provider().list_dir(&format!("{}/devices", fs_dir)).iter()
+        .filter_map(|dev| cat(&format!("{}/devices/{}/size", fs_dir, dev)).trim().parse::<u64>().ok())
+        .sum::<u64>() * 512;
+
+    let used: u64 = ["data", "metadata", "system"].iter()
+        .filter_map(|kind| cat(&format!("{}/allocation/{}/disk_used", fs_dir, kind)).trim().parse::<u64>().ok())
+        .sum();
+
+    if total == 0 { None } else { Some((used, total)) }
+}
+
+/// Pool-level usage of the zpool a dataset (e.g. "rpool/ROOT/ubuntu") belongs to
+fn get_zfs_usage(dataset: &str) -> Option<(u64, u64)> {
+    which("zpool")?;
+
+    // "-Hp" prints tab-separated exact byte counts: "<alloc>\t<size>"
+    let pool = dataset.split('/').next()?;
+    let output = sh!("zpool", "list", "-Hp", "-o", "alloc,size", pool);
+    let mut fields = output.stdout.split_whitespace().map(|field| field.parse::<u64>());
+
+    match (fields.next()?, fields.next()?) {
+        (Ok(used), Ok(total)) if output.err_code == 0 => Some((used, total)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_utils::fixture::with_fixture;
+    use serde_json::json;
+
+    #[test]
+    fn sums_a_btrfs_pool_across_its_devices() {
+        let fixture = json!({
+            "dirs": {
+                "/sys/fs/btrfs": ["0b1e6c2a", "features"],
+                "/sys/fs/btrfs/0b1e6c2a/devices": ["nvme0n1p2", "sda1"],
+                "/sys/fs/btrfs/0b1e6c2a/devices/sda1": [],
+            },
+            "files": {
+                "/sys/fs/btrfs/0b1e6c2a/devices/nvme0n1p2/size": "2097152\n",
+                "/sys/fs/btrfs/0b1e6c2a/devices/sda1/size": "2097152\n",
+                "/sys/fs/btrfs/0b1e6c2a/allocation/data/disk_used": "1073741824\n",
+                "/sys/fs/btrfs/0b1e6c2a/allocation/metadata/disk_used": "268435456\n",
+                "/sys/fs/btrfs/0b1e6c2a/allocation/system/disk_used": "16384\n",
+            },
+        });
+
+        assert_eq!(with_fixture(fixture, || get_btrfs_usage("/dev/sda1")), Some((1342193664, 2147483648)));
+    }
+}
\ No newline at end of file
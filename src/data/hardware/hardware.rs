@@ -1,15 +1,13 @@
-use colored::Colorize;
+use crate::data::common::{format_section, Section};
+use crate::data::registry::{gather_stats, Samples};
+use std::thread::JoinHandle;
 use sysinfo::System;
-use crate::data::hardware::individual_stats::{get_cpu, get_drive, get_gpu, get_model, get_ram, get_screen_res};
 
-pub fn get_hardware_info(sys: &mut System) -> Vec<String> {
-    vec![
-        format!("{}", "< Hardware >".bold()),
-        format!("{}{}", "╔ MDL = ".blue().bold(), get_model().dimmed()),
-        format!("{}{}", "╠ CPU = ".blue().bold(), get_cpu().dimmed()),
-        format!("{}{}", "╠ GPU = ".blue().bold(), get_gpu().dimmed()),
-        format!("{}{}", "╠ RAM = ".blue().bold(), get_ram(sys).dimmed()),
-        format!("{}{}", "╠ HDD = ".blue().bold(), get_drive().dimmed()),
-        format!("{}{}", "╚ RES = ".blue().bold(), get_screen_res().dimmed()),
-    ]
-}
\ No newline at end of file
+pub fn get_hardware_info(cpu_sample: Option<JoinHandle<System>>) -> Section {
+    let samples = Samples {
+        cpu: cpu_sample.and_then(|sample| sample.join().ok()),
+        ..Samples::default()
+    };
+
+    format_section("Hardware", gather_stats("hardware", samples))
+}
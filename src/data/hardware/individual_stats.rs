@@ -1,103 +1,545 @@
-use std::ffi::CString;
-use std::mem::zeroed;
-use libc::{statvfs, statvfs as Statvfs};
+use crate::_utils::hwmon::{hwmon_devices, is_hot, read_sensor};
+use crate::_utils::procfs::{cpuinfo_count, cpuinfo_field, meminfo_bytes};
 use crate::_utils::read_file::cat;
-use crate::_utils::run_command::ShellReturn;
+use crate::_utils::read_file::expand_home;
+use crate::data::hardware::cgroup::{get_cpu_limit, get_memory_limit};
+use crate::data::hardware::mac_models::get_mac_marketing_name;
+use crate::data::common::StatValue;
+use crate::_utils::status::warn_module;
+use crate::_utils::system_provider::provider;
+use crate::_utils::units::human_bytes;
 use crate::sh;
-use std::process::Command;
+use crate::sh_raw;
 use pci_ids::{FromId, Vendor};
-use pci_info::pci_enums::PciDeviceClass;
-use sysinfo::{MemoryRefreshKind, System};
-use pci_info::PciInfo;
+use std::thread::{self, JoinHandle};
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, ProcessRefreshKind, ProcessesToUpdate, RefreshKind, System, MINIMUM_CPU_UPDATE_INTERVAL};
 
 
 pub fn get_model() -> String {
 
-    match sh!("uname").stdout.trim() {
-        "Linux" => cat("/sys/devices/virtual/dmi/id/product_name").trim().to_string(),
-        "FreeBSD" => sh!("grep -i \"smbios: product\" /var/run/dmesg.boot | sed 's/.*[Pp]roduct: *//'").stdout.trim().to_string(),
-        "Darwin" => sh!("sysctl hw.model | awk '{{print $2}}'").stdout.trim().to_string(),
+    match provider().uname().sysname.as_str() {
+        "Linux" => {
+            let product_name = cat("/sys/devices/virtual/dmi/id/product_name").trim().to_string();
+            if product_name.is_empty() {
+                get_device_tree_model().unwrap_or(product_name)
+            } else {
+                product_name
+            }
+        },
+        "FreeBSD" => sh_raw!("grep -i \"smbios: product\" /var/run/dmesg.boot | sed 's/.*[Pp]roduct: *//'").stdout.trim().to_string(),
+        "Darwin" => {
+            let identifier = sh!("sysctl", "-n", "hw.model").stdout.trim().to_string();
+            match get_mac_marketing_name(&identifier).map(str::to_string).or_else(get_cached_mac_marketing_name) {
+                Some(name) => format!("{} [{}]", name, identifier),
+                None => identifier,
+            }
+        },
         _ => "Not Supported".to_string()
     }
 
 }
 
+/// Names ARM single-board computers (and other boards without DMI) from the device tree,
+/// e.g. "Radxa ROCK Pi 4B (Rockchip RK3399)"
+fn get_device_tree_model() -> Option<String> {
+    // Both files are NUL terminated, `compatible` lists the board first and the SoC last,
+    // e.g. "radxa,rockpi4b\0rockchip,rk3399\0"
+    let model = cat("/proc/device-tree/model").trim_end_matches('\0').trim().to_string();
+    let compatible = cat("/proc/device-tree/compatible");
+    let mut compatible = compatible.split('\0').filter(|entry| !entry.is_empty());
+
+    let board = compatible.next().map(format_compatible);
+    let soc = compatible.next_back().map(format_compatible);
+
+    let name = if model.is_empty() { board? } else { model };
+    match soc {
+        Some(soc) if !name.contains(&soc) => Some(format!("{} ({})", name, soc)),
+        _ => Some(name),
+    }
+}
+
+/// Turns a device tree `vendor,device` compatible string into a readable name
+fn format_compatible(compatible: &str) -> String {
+    const VENDORS: [(&str, &str); 14] = [
+        ("allwinner", "Allwinner"),
+        ("amlogic", "Amlogic"),
+        ("brcm", "Broadcom"),
+        ("friendlyarm", "FriendlyElec"),
+        ("hardkernel", "Hardkernel"),
+        ("nvidia", "NVIDIA"),
+        ("pine64", "Pine64"),
+        ("qcom", "Qualcomm"),
+        ("radxa", "Radxa"),
+        ("raspberrypi", "Raspberry Pi"),
+        ("rockchip", "Rockchip"),
+        ("sinovoip", "Banana Pi"),
+        ("starfive", "StarFive"),
+        ("xunlong", "Orange Pi"),
+    ];
+
+    let (vendor, device) = compatible.split_once(',').unwrap_or(("", compatible));
+    let vendor = VENDORS.iter()
+        .find(|(id, _)| *id == vendor)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or(vendor.to_string());
+
+    format!("{} {}", vendor, device.to_uppercase()).trim().to_string()
+}
+
+/// Reads the marketing name macOS caches for "About This Mac", used for models missing from our table
+fn get_cached_mac_marketing_name() -> Option<String> {
+    // Output looks like `{ "XXXX-en-US_US" = "MacBook Pro (14-inch, 2023)"; }`
+    let cpu_names = sh!("defaults", "read", expand_home("~/Library/Preferences/com.apple.SystemProfiler.plist").to_string_lossy(), "CPU Names");
+    let name = cpu_names.stdout.split('=').nth(1)?
+        .split('"').nth(1)?
+        .trim()
+        .to_string();
+
+    if name.is_empty() { None } else { Some(name) }
+}
+
 pub fn get_cpu() -> String {
 
-    let [cpu, corecount] = match sh!("uname").stdout.trim() {
-        "Linux" => {
-            let cpuname = sh!("awk -F: '/model name/ {{print $2; exit}}' /proc/cpuinfo | sed 's/^ //'").stdout;
-            let nproc = sh!("nproc");
+    if let Some(cpu) = get_apple_silicon_cpu() {
+        return cpu;
+    }
 
-            if nproc.err_code == 0 {
-                [cpuname, nproc.stdout]
-            } else {
-                [cpuname, sh!("grep '^processor' /proc/cpuinfo | wc -l").stdout]
-            }
+    let [cpu, corecount] = match provider().uname().sysname.as_str() {
+        "Linux" => {
+            // One block per logical CPU, each with a "model name\t: AMD Ryzen 7 7800X3D 8-Core Processor" line
+            let cpuinfo = cat("/proc/cpuinfo");
+            [cpuinfo_field(&cpuinfo, "model name").unwrap_or("").to_string(), cpuinfo_count(&cpuinfo).to_string()]
         },
-        "FreeBSD" => [sh!("sysctl -n hw.model").stdout, sh!("sysctl -n hw.ncpu").stdout],
-        "Darwin" => [sh!("sysctl -n machdep.cpu.brand_string").stdout, sh!("sysctl -n hw.ncpu").stdout],
+        "FreeBSD" => [sh!("sysctl", "-n", "hw.model").stdout, sh!("sysctl", "-n", "hw.ncpu").stdout],
+        "Darwin" => [sh!("sysctl", "-n", "machdep.cpu.brand_string").stdout, sh!("sysctl", "-n", "hw.ncpu").stdout],
         _ => ["Not Supported".to_string(), "0".to_string()]
     };
 
-    format!("{} ({})", cpu.trim(), corecount.trim())
+    // In a container, show the CPU quota it is actually allowed to use
+    match get_cpu_limit() {
+        Some(limit) => format!("{} ({} of {})", cpu.trim(), (limit * 100.0).round() / 100.0, corecount.trim()),
+        None => format!("{} ({})", cpu.trim(), corecount.trim()),
+    }
 
 }
 
-pub fn get_gpu() -> String {
+/// Cache sizes, notable instruction set extensions and virtualization support,
+/// e.g. "L2 8 MiB, L3 96 MiB, AVX-512, AMD-V (KVM)"
+pub fn get_cpu_details() -> String {
+    let mut details = Vec::new();
 
-    // Enumerate the devices on the PCI bus
-    let info = PciInfo::enumerate_pci();
-
-    if let Ok(devices) = info {
-
-        for r in devices {
-            if let Ok(device) = r {
-                // Ignores non-gpus
-                if device.device_class().unwrap_or(PciDeviceClass::Unclassified) == PciDeviceClass::DisplayController {
-
-                    // Extracts user-friendly strings for the first GPU
-                    let vendor = Vendor::from_id(device.vendor_id());
-                    if let Some(vendor) = vendor {
-                        for d in vendor.devices() {
-                            if d.id() == device.device_id() {
-                                return format!("{} {} [{:04X}:{:04X}]", vendor.name(), d.name(), device.vendor_id(), device.device_id());
-                            }
-                        }
-                    }
-                    
+    match provider().uname().sysname.as_str() {
+        "Linux" => {
+            details.extend(get_cache_sizes());
+
+            // x86 lists its extensions as "flags", ARM as "Features"
+            let cpuinfo = cat("/proc/cpuinfo");
+            let flags: Vec<&str> = cpuinfo_field(&cpuinfo, "flags").or_else(|| cpuinfo_field(&cpuinfo, "Features"))
+                .unwrap_or("")
+                .split_whitespace()
+                .collect();
+            let has = |flag: &str| flags.contains(&flag);
+
+            const EXTENSIONS: [(&str, &str); 5] = [
+                ("avx512f", "AVX-512"), ("amx_tile", "AMX"), ("sve2", "SVE2"), ("sve", "SVE"), ("avx2", "AVX2"),
+            ];
+            // Only the widest vector extension of a family, AVX-512 implies AVX2 and SVE2 implies SVE
+            let mut extensions: Vec<&str> = EXTENSIONS.iter().filter(|(flag, _)| has(flag)).map(|(_, name)| *name).collect();
+            if extensions.contains(&"AVX-512") {
+                extensions.retain(|name| *name != "AVX2");
+            }
+            if extensions.contains(&"SVE2") {
+                extensions.retain(|name| *name != "SVE");
+            }
+            details.extend(extensions.into_iter().map(str::to_string));
+
+            // Firmware that has virtualization turned off hides the flag, and /dev/kvm only exists
+            // once the kvm module loaded for it
+            let virtualization = if has("vmx") { Some("VT-x") } else if has("svm") { Some("AMD-V") } else { None };
+            match (virtualization, provider().exists("/dev/kvm")) {
+                (Some(name), true) => details.push(format!("{} (KVM)", name)),
+                (Some(name), false) => details.push(name.to_string()),
+                (None, true) => details.push("KVM".to_string()),
+                (None, false) if has("hypervisor") => details.push("virtual machine".to_string()),
+                (None, false) => details.push("no virtualization".to_string()),
+            }
+        },
+        "Darwin" => {
+            let sysctl = |name: &str| sh!("sysctl", "-n", name).stdout.trim().to_string();
+            for (level, name) in [("L2", "hw.l2cachesize"), ("L3", "hw.l3cachesize")] {
+                if let Ok(bytes) = sysctl(name).parse::<u64>() && bytes > 0 {
+                    details.push(format!("{} {}", level, human_bytes(bytes as f64)));
                 }
             }
+            if sysctl("hw.optional.avx512f") == "1" {
+                details.push("AVX-512".to_string());
+            }
+            if sysctl("kern.hv_support") == "1" {
+                details.push("Hypervisor.framework".to_string());
+            }
+        },
+        _ => {},
+    }
+
+    if details.is_empty() { "Unknown".to_string() } else { details.join(", ") }
+}
+
+/// Total L2 and L3 size across all cores, e.g. ["L2 8 MiB", "L3 96 MiB"]
+fn get_cache_sizes() -> Vec<String> {
+    // Every CPU lists the caches it uses, a shared cache shows up under each CPU sharing it but is
+    // only counted once per distinct set of sharing CPUs
+    let mut seen = Vec::new();
+    let mut totals = [0u64; 2];
+    for cpu in provider().list_dir("/sys/devices/system/cpu") {
+        if !cpu.strip_prefix("cpu").is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit())) {
+            continue;
+        }
+
+        let cache_dir = format!("/sys/devices/system/cpu/{}/cache", cpu);
+        for index in provider().list_dir(&cache_dir) {
+            let read = |file: &str| cat(&format!("{}/{}/{}", cache_dir, index, file)).trim().to_string();
+            let level = read("level");
+            let Some(slot) = ["2", "3"].iter().position(|l| *l == level) else {
+                continue;
+            };
+
+            let key = (level, read("shared_cpu_list"));
+            if seen.contains(&key) {
+                continue;
+            }
+            // "2048K"
+            if let Ok(kib) = read("size").trim_end_matches('K').parse::<u64>() {
+                totals[slot] += kib * 1024;
+            }
+            seen.push(key);
         }
+    }
 
-        "None".to_string()
+    totals.iter().zip(["L2", "L3"])
+        .filter(|(bytes, _)| **bytes > 0)
+        .map(|(bytes, level)| format!("{} {}", level, human_bytes(*bytes as f64)))
+        .collect()
+}
 
-    } else {
-        "Not Supported".to_string()
+/// Sockets, NUMA nodes and the memory attached to each node, e.g. "2 sockets, 2 nodes (128.0 GiB, 128.0 GiB)"
+pub fn get_numa() -> Option<String> {
+    let is_numbered = |entry: &str, prefix: &str| entry.strip_prefix(prefix).is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+
+    // Each CPU's physical package is its socket
+    let mut sockets: Vec<String> = provider().list_dir("/sys/devices/system/cpu").iter()
+        .filter(|cpu| is_numbered(cpu, "cpu"))
+        .map(|cpu| cat(&format!("/sys/devices/system/cpu/{}/topology/physical_package_id", cpu)).trim().to_string())
+        .filter(|package| !package.is_empty())
+        .collect();
+    sockets.sort();
+    sockets.dedup();
+
+    let mut nodes: Vec<String> = provider().list_dir("/sys/devices/system/node").into_iter()
+        .filter(|node| is_numbered(node, "node"))
+        .collect();
+    nodes.sort_by_key(|node| node[4..].parse::<u32>().unwrap_or(0));
+    if nodes.is_empty() {
+        return None;
+    }
+
+    // "Node 0 MemTotal:       131891204 kB"
+    let memory: Vec<String> = nodes.iter()
+        .filter_map(|node| {
+            let meminfo = cat(&format!("/sys/devices/system/node/{}/meminfo", node));
+            let kib = meminfo.lines()
+                .find_map(|line| line.split_once("MemTotal:"))
+                .and_then(|(_, value)| value.split_whitespace().next()?.parse::<u64>().ok())?;
+            Some(human_bytes((kib * 1024) as f64))
+        })
+        .collect();
+
+    let plural = |count: usize, noun: &str| format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" });
+    let mut numa = plural(nodes.len(), "node");
+    if !sockets.is_empty() {
+        numa = format!("{}, {}", plural(sockets.len(), "socket"), numa);
+    }
+    if !memory.is_empty() {
+        numa.push_str(&format!(" ({})", memory.join(", ")));
     }
+    Some(numa)
+}
 
+/// Reserved hugepages, e.g. "512 × 2.0 MiB (1.0 GiB, 128 free)" or "None"
+pub fn get_hugepages() -> Option<StatValue> {
+    let meminfo = cat("/proc/meminfo");
+    let total = meminfo_bytes(&meminfo, "HugePages_Total")?;
+    let free = meminfo_bytes(&meminfo, "HugePages_Free").unwrap_or(0);
+    let size = meminfo_bytes(&meminfo, "Hugepagesize").unwrap_or(0);
+
+    let display = match total {
+        0 => "None".to_string(),
+        _ => format!("{} × {} ({}, {} free)", total, human_bytes(size as f64), human_bytes((total * size) as f64), free),
+    };
+    Some(StatValue::from(display).with("total", total).with("free", free).with("size_bytes", size))
 }
 
-pub fn get_ram(sys: &mut System) -> String {
-    sys.refresh_memory_specifics(MemoryRefreshKind::nothing().with_ram());
+/// Kernel slab caches and how much of them could be reclaimed, e.g. "412.3 MiB (310.0 MiB reclaimable)"
+pub fn get_slab() -> Option<StatValue> {
+    let meminfo = cat("/proc/meminfo");
+    let slab = meminfo_bytes(&meminfo, "Slab")?;
+    let reclaimable = meminfo_bytes(&meminfo, "SReclaimable").unwrap_or(0);
 
-    format!("{}MB / {}MB", sys.used_memory() / 1048576, sys.total_memory() / 1048576)
+    Some(StatValue::from(format!("{} ({} reclaimable)", human_bytes(slab as f64), human_bytes(reclaimable as f64)))
+        .with("slab_bytes", slab)
+        .with("reclaimable_bytes", reclaimable))
 }
 
-pub fn get_drive() -> String {
-    let path = CString::new("/").unwrap();
-    let mut stat: Statvfs = unsafe { zeroed() };
+/// Pages waiting to be written back to disk, e.g. "12.5 MiB dirty, 0 B writeback"
+pub fn get_dirty_pages() -> Option<StatValue> {
+    let meminfo = cat("/proc/meminfo");
+    let dirty = meminfo_bytes(&meminfo, "Dirty")?;
+    let writeback = meminfo_bytes(&meminfo, "Writeback").unwrap_or(0);
 
-    let result = unsafe { statvfs(path.as_ptr(), &mut stat) };
-    if result != 0 {
-        return "Failed / Not Supported".to_string();
+    Some(StatValue::from(format!("{} dirty, {} writeback", human_bytes(dirty as f64), human_bytes(writeback as f64)))
+        .with("dirty_bytes", dirty)
+        .with("writeback_bytes", writeback))
+}
+
+/// Reports the chip, performance/efficiency core split and unified memory size on Apple Silicon,
+/// e.g. "Apple M2 Pro (8P + 4E, 16GB unified)"
+fn get_apple_silicon_cpu() -> Option<String> {
+    if provider().uname().sysname.as_str() != "Darwin" || sh!("sysctl", "-n", "hw.optional.arm64").stdout.trim() != "1" {
+        return None;
     }
 
-    let total_space = stat.f_blocks as u64 * stat.f_frsize as u64;
-    let free_space = stat.f_bfree as u64 * stat.f_frsize as u64;
-    let used_space = total_space - free_space;
+    let chip = sh!("sysctl", "-n", "machdep.cpu.brand_string").stdout.trim().to_string();
+    let memory = sh!("sysctl", "-n", "hw.memsize").stdout.trim().parse::<u64>().ok()? / 1073741824;
+
+    // perflevel0 is always the performance cluster, perflevel1 only exists on chips with efficiency cores
+    let pcores = sh!("sysctl", "-n", "hw.perflevel0.physicalcpu").stdout.trim().parse::<u32>();
+    let ecores = sh!("sysctl", "-n", "hw.perflevel1.physicalcpu").stdout.trim().parse::<u32>();
+
+    let cores = match (pcores, ecores) {
+        (Ok(p), Ok(e)) => format!("{}P + {}E", p, e),
+        (Ok(p), Err(_)) => format!("{}P", p),
+        _ => sh!("sysctl", "-n", "hw.ncpu").stdout.trim().to_string(),
+    };
+
+    Some(format!("{} ({}, {}GB unified)", chip, cores, memory))
+}
 
-    format!("{}GB / {}GB", used_space / 1073741824, total_space / 1073741824)
+/// Starts sampling CPU usage in the background, optionally including per-process usage
+///
+/// Usage is the difference between two samples taken `MINIMUM_CPU_UPDATE_INTERVAL` apart, so this
+/// should be started before the other gatherers run to hide the delay behind them. RAM is read
+/// along with it, so `get_ram` can use the same `System`.
+pub fn start_cpu_sample(with_processes: bool) -> JoinHandle<System> {
+    thread::spawn(move || {
+        let process_kind = ProcessRefreshKind::nothing().with_cpu().with_memory();
+        let mut refresh_kind = RefreshKind::nothing()
+            .with_cpu(CpuRefreshKind::nothing().with_cpu_usage())
+            .with_memory(MemoryRefreshKind::nothing().with_ram());
+        if with_processes {
+            refresh_kind = refresh_kind.with_processes(process_kind);
+        }
+
+        let mut sys = System::new_with_specifics(refresh_kind);
+        thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+        sys.refresh_cpu_usage();
+        if with_processes {
+            sys.refresh_processes_specifics(ProcessesToUpdate::All, true, process_kind);
+        }
+        sys
+    })
+}
+
+pub fn get_cpu_usage(sample: &System) -> String {
+    format!("{:.1}%", sample.global_cpu_usage())
+}
+
+/// One block glyph per core scaled by its usage, e.g. "▁▃▅█▂▁▁▄"
+pub fn get_per_core_usage(sample: &System) -> String {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    sample.cpus().iter()
+        .map(|cpu| {
+            let level = (cpu.cpu_usage() / 100.0 * LEVELS.len() as f32) as usize;
+            LEVELS[level.min(LEVELS.len() - 1)]
+        })
+        .collect()
+}
+
+/// The `count` busiest processes by CPU or memory (`sort_by` = "memory"), e.g. "firefox 12%, cargo 8%"
+pub fn get_top_processes(sample: &System, count: usize, sort_by: &str) -> String {
+    let total_memory = sample.total_memory().max(1) as f32;
+    let mut processes: Vec<(String, f32)> = sample.processes().values()
+        .map(|process| {
+            let usage = match sort_by {
+                "memory" => process.memory() as f32 / total_memory * 100.0,
+                _ => process.cpu_usage(),
+            };
+            (process.name().to_string_lossy().to_string(), usage)
+        })
+        .collect();
+
+    processes.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    processes.iter()
+        .take(count)
+        .map(|(name, usage)| format!("{} {:.0}%", name, usage))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Pressure stall information (Linux 4.20+): the share of the last 10s some task was stalled
+/// waiting on each resource, e.g. "cpu 1.2%, mem 0.0%, io 3.4%"
+pub fn get_pressure() -> String {
+    let pressure: Vec<String> = [("cpu", "cpu"), ("mem", "memory"), ("io", "io")].iter()
+        .filter_map(|(label, resource)| {
+            // "some avg10=1.23 avg60=0.87 avg300=0.42 total=123456"
+            let avg10 = cat(&format!("/proc/pressure/{}", resource)).lines()
+                .find(|line| line.starts_with("some"))?
+                .split_whitespace()
+                .find_map(|field| field.strip_prefix("avg10="))?
+                .to_string();
+            Some(format!("{} {}%", label, avg10))
+        })
+        .collect();
+
+    if pressure.is_empty() { "Not Supported".to_string() } else { pressure.join(", ") }
+}
+
+/// Every GPU, e.g. "2x AMD Instinct MI210 [1002:740F]"
+pub fn get_gpu() -> String {
+
+    let Some(controllers) = provider().display_controllers() else {
+        warn_module("hardware", "can't enumerate PCI devices, GPU detection unavailable");
+        return "Not Supported".to_string();
+    };
+
+    // Extracts user-friendly strings for each GPU, identical ones (e.g. a compute node's
+    // accelerators) are counted rather than repeated
+    let mut gpus: Vec<(String, usize)> = Vec::new();
+    for (vendor_id, device_id) in controllers {
+        let Some(vendor) = Vendor::from_id(vendor_id) else { continue };
+        let Some(device) = vendor.devices().find(|d| d.id() == device_id) else { continue };
+
+        let name = format!("{} {} [{:04X}:{:04X}]", vendor.name(), device.name(), vendor_id, device_id);
+        match gpus.iter_mut().find(|(gpu, _)| *gpu == name) {
+            Some((_, count)) => *count += 1,
+            None => gpus.push((name, 1)),
+        }
+    }
+
+    if gpus.is_empty() {
+        return "None".to_string();
+    }
+    gpus.into_iter()
+        .map(|(name, count)| if count > 1 { format!("{}x {}", count, name) } else { name })
+        .collect::<Vec<_>>()
+        .join(", ")
+
+}
+
+/// CPU package temperature, e.g. "54°C", `None` without a sensor vega knows
+pub fn get_cpu_temp() -> Option<StatValue> {
+    if provider().uname().sysname == "Darwin" {
+        // The SMC can only be read through IOKit, smctemp does that without needing root
+        return sh!("smctemp", "-c").stdout.trim().parse::<f64>().ok().map(|celsius| temperature(celsius as i64));
+    }
+
+    // k10temp/zenpower (AMD), coretemp (Intel) and the SoC sensors of ARM boards; the package
+    // sensor is labelled on the x86 ones and otherwise the first
+    const CPU_SENSORS: [&str; 5] = ["k10temp", "zenpower", "coretemp", "cpu_thermal", "cpu-thermal"];
+    const PACKAGE_LABELS: [&str; 3] = ["Tctl", "Tdie", "Package id 0"];
+
+    let (_, hwmon) = hwmon_devices().into_iter()
+        .filter(|(name, _)| CPU_SENSORS.contains(&name.as_str()))
+        .min_by_key(|(name, _)| CPU_SENSORS.iter().position(|sensor| sensor == name))?;
+    let input = (1..=8)
+        .find(|n| PACKAGE_LABELS.contains(&cat(&hwmon.join(format!("temp{}_label", n)).to_string_lossy()).trim()))
+        .unwrap_or(1);
+
+    read_sensor(&hwmon, &format!("temp{}_input", input)).map(|millidegrees| temperature(millidegrees / 1000))
+}
+
+/// GPU temperatures, the hottest one first when there are several, e.g. "61°C, 45°C"
+pub fn get_gpu_temp() -> Option<StatValue> {
+    let mut temps: Vec<i64> = if provider().uname().sysname == "Darwin" {
+        sh!("smctemp", "-g").stdout.trim().parse::<f64>().ok().map(|celsius| celsius as i64).into_iter().collect()
+    } else {
+        // amdgpu's first sensor is the edge temperature, which is what its own tools show
+        let mut temps: Vec<i64> = hwmon_devices().into_iter()
+            .filter(|(name, _)| matches!(name.as_str(), "amdgpu" | "radeon" | "nouveau" | "i915" | "xe"))
+            .filter_map(|(_, hwmon)| read_sensor(&hwmon, "temp1_input"))
+            .map(|millidegrees| millidegrees / 1000)
+            .collect();
+
+        // The proprietary NVIDIA driver has no hwmon device
+        temps.extend(sh!("nvidia-smi", "--query-gpu=temperature.gpu", "--format=csv,noheader,nounits").stdout.lines()
+            .filter_map(|line| line.trim().parse::<i64>().ok()));
+        temps
+    };
+    temps.sort_by(|a, b| b.cmp(a));
+
+    let hottest = *temps.first()?;
+    let display = temps.iter().map(|celsius| format!("{}°C", celsius)).collect::<Vec<_>>().join(", ");
+    Some(StatValue::from(display).with("celsius", hottest.max(0) as u64).warning(is_hot(hottest)))
+}
+
+/// Every spinning fan, by its label where the driver has one, e.g. "cpu_fan 1180 RPM, nct6798 fan2 740 RPM"
+pub fn get_fans() -> Option<String> {
+    if provider().uname().sysname == "Darwin" {
+        // "Fan: 1834.23 rpm", powermetrics needs root
+        let output = sh!("powermetrics", "--samplers", "smc", "-n", "1", "-i", "1");
+        let fans: Vec<String> = output.stdout.lines()
+            .filter_map(|line| line.trim().strip_prefix("Fan:")?.trim().strip_suffix("rpm")?.trim().parse::<f64>().ok())
+            .map(|rpm| format!("{:.0} RPM", rpm))
+            .collect();
+        return (!fans.is_empty()).then(|| fans.join(", "));
+    }
+
+    let mut has_fans = false;
+    let fans: Vec<String> = hwmon_devices().into_iter()
+        .flat_map(|(name, hwmon)| (1..=8).filter_map(move |n| {
+            let rpm = read_sensor(&hwmon, &format!("fan{}_input", n))?;
+            let label = cat(&hwmon.join(format!("fan{}_label", n)).to_string_lossy()).trim().to_string();
+            Some((if label.is_empty() { format!("{} fan{}", name, n) } else { label }, rpm))
+        }))
+        .inspect(|_| has_fans = true)
+        // Empty headers on motherboards read 0, as do fans that are stopped
+        .filter(|(_, rpm)| *rpm > 0)
+        .map(|(label, rpm)| format!("{} {} RPM", label, rpm))
+        .collect();
+
+    match (fans.is_empty(), has_fans) {
+        (false, _) => Some(fans.join(", ")),
+        (true, true) => Some("Stopped".to_string()),
+        (true, false) => None,
+    }
+}
+
+/// e.g. "54°C", in the warning color from `temperature.warning` up
+fn temperature(celsius: i64) -> StatValue {
+    StatValue::from(format!("{}°C", celsius)).with("celsius", celsius.max(0) as u64).warning(is_hot(celsius))
+}
+
+/// Used and total RAM, from the provider where it can tell and otherwise from the CPU sample's
+/// `System`, which only gets built here when there's no sample to share
+pub fn get_ram(sample: Option<&System>) -> StatValue {
+    // In a container, show its own usage against its memory limit instead of the host's
+    if let Some((used, limit)) = get_memory_limit() {
+        return StatValue::from(format!("{}MB / {}MB (container)", used / 1048576, limit / 1048576))
+            .with("used_bytes", used)
+            .with("total_bytes", limit);
+    }
+
+    let (used, total) = provider().memory().unwrap_or_else(|| match sample {
+        Some(sys) => (sys.used_memory(), sys.total_memory()),
+        None => {
+            let sys = System::new_with_specifics(RefreshKind::nothing().with_memory(MemoryRefreshKind::nothing().with_ram()));
+            (sys.used_memory(), sys.total_memory())
+        },
+    });
+
+    StatValue::from(format!("{}MB / {}MB", used / 1048576, total / 1048576))
+        .with("used_bytes", used)
+        .with("total_bytes", total)
 }
 
 #[cfg(target_os = "macos")]
@@ -121,16 +563,142 @@ pub fn get_screen_res() -> String {
 
 #[cfg(not(target_os = "macos"))]
 pub fn get_screen_res() -> String {
-    let screen_res = match sh!("uname").stdout.trim() {
-        "Linux" => sh!("head -n1 -q /sys/class/drm/*/modes | tr '\n' ' '"),
-        "FreeBSD" => sh!("sysctl -n kern.vt.fb.default_mode"),
-        _ => sh!("meow"),
+    let res = match provider().uname().sysname.as_str() {
+        // The first (preferred) mode of each connected output, e.g. /sys/class/drm/card1-DP-1/modes
+        "Linux" => provider().list_dir("/sys/class/drm").iter()
+            .filter_map(|output| cat(&format!("/sys/class/drm/{}/modes", output)).lines().next().map(str::to_string))
+            .collect::<Vec<_>>()
+            .join(" "),
+        "FreeBSD" => sh!("sysctl", "-n", "kern.vt.fb.default_mode").stdout.trim().to_string(),
+        _ => String::new(),
+    };
+
+    if res.is_empty() { "None".to_string() } else { res }
+}
+
+/// Backlight brightness as a percentage, or None on devices without a backlight
+#[cfg(target_os = "macos")]
+pub fn get_brightness() -> Option<String> {
+    // `brightness -l` prints "display 0: brightness 0.750000" for each display with a backlight
+    let result = sh!("brightness", "-l");
+    let level = result.stdout.lines()
+        .find_map(|line| line.split_once("brightness ")?.1.trim().parse::<f64>().ok())?;
+
+    Some(format!("{:.0}%", level * 100.0))
+}
+
+/// Backlight brightness as a percentage, or None on devices without a backlight
+#[cfg(not(target_os = "macos"))]
+pub fn get_brightness() -> Option<String> {
+    let backlight = provider().list_dir("/sys/class/backlight").into_iter().next()?;
+
+    let brightness = cat(&format!("/sys/class/backlight/{}/brightness", backlight)).trim().parse::<f64>().ok()?;
+    let max_brightness = cat(&format!("/sys/class/backlight/{}/max_brightness", backlight)).trim().parse::<f64>().ok()?;
+    if max_brightness <= 0.0 {
+        return None;
+    }
+
+    Some(format!("{:.0}%", brightness / max_brightness * 100.0))
+}
+
+/// The batteries powering the machine, e.g. "BAT0", leaving out the ones of wireless mice,
+/// keyboards and other peripherals
+fn batteries() -> Vec<String> {
+    provider().list_dir("/sys/class/power_supply").into_iter()
+        .filter(|supply| cat(&format!("/sys/class/power_supply/{}/type", supply)).trim() == "Battery")
+        .filter(|supply| cat(&format!("/sys/class/power_supply/{}/scope", supply)).trim() != "Device")
+        .collect()
+}
+
+/// Whether the machine runs on a battery, i.e. it's a laptop or a handheld
+pub fn has_battery() -> bool {
+    match provider().uname().sysname.as_str() {
+        "Darwin" => sh!("pmset", "-g", "batt").stdout.contains("InternalBattery"),
+        _ => !batteries().is_empty(),
+    }
+}
+
+/// Charge and state of the (first) battery, e.g. "85% (Discharging)"
+pub fn get_battery() -> Option<String> {
+    let (charge, state) = match provider().uname().sysname.as_str() {
+        // " -InternalBattery-0 (id=4653155)	85%; discharging; 3:12 remaining present: true"
+        "Darwin" => {
+            let result = sh!("pmset", "-g", "batt");
+            let line = result.stdout.lines().find(|line| line.contains("InternalBattery"))?;
+            let mut fields = line.split_once('\t')?.1.split(';').map(str::trim);
+            let charge = fields.next()?.trim_end_matches('%').to_string();
+            let state = fields.next().unwrap_or_default();
+            let mut chars = state.chars();
+            (charge, chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default())
+        },
+        _ => {
+            let battery = batteries().into_iter().next()?;
+            let charge = cat(&format!("/sys/class/power_supply/{}/capacity", battery)).trim().to_string();
+            (charge, cat(&format!("/sys/class/power_supply/{}/status", battery)).trim().to_string())
+        },
     };
-    let res = screen_res.stdout.trim().to_string();
 
-    if screen_res.err_code == 0 && !res.is_empty() {
-        res
+    if charge.is_empty() {
+        return None;
+    }
+    if state.is_empty() {
+        Some(format!("{}%", charge))
     } else {
-        "None".to_string()
+        Some(format!("{}% ({})", charge, state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_utils::fixture::with_fixture;
+    use serde_json::json;
+
+    #[test]
+    fn reads_ram_from_the_system() {
+        let ram = with_fixture(json!({ "memory": { "used": 4294967296u64, "total": 17179869184u64 } }), || get_ram(None));
+        assert_eq!(ram.display, "4096MB / 16384MB");
+    }
+
+    #[test]
+    fn reads_ram_against_a_containers_limit() {
+        let fixture = json!({
+            "files": {
+                "/.dockerenv": "",
+                "/sys/fs/cgroup/memory.max": "1073741824\n",
+                "/sys/fs/cgroup/memory.current": "268435456\n",
+            },
+            "memory": { "used": 4294967296u64, "total": 17179869184u64 },
+        });
+        assert_eq!(with_fixture(fixture, || get_ram(None)).display, "256MB / 1024MB (container)");
+    }
+
+    #[test]
+    fn reads_the_first_system_battery() {
+        let fixture = json!({
+            "dirs": { "/sys/class/power_supply": ["AC", "BAT0", "hidpp_battery_0"] },
+            "files": {
+                "/sys/class/power_supply/AC/type": "Mains\n",
+                "/sys/class/power_supply/BAT0/type": "Battery\n",
+                "/sys/class/power_supply/BAT0/capacity": "85\n",
+                "/sys/class/power_supply/BAT0/status": "Discharging\n",
+                // A wireless mouse's battery isn't the machine's
+                "/sys/class/power_supply/hidpp_battery_0/type": "Battery\n",
+                "/sys/class/power_supply/hidpp_battery_0/scope": "Device\n",
+            },
+        });
+        with_fixture(fixture, || {
+            assert!(has_battery());
+            assert_eq!(get_battery().as_deref(), Some("85% (Discharging)"));
+        });
+    }
+
+    #[test]
+    fn has_no_battery_on_a_desktop() {
+        let fixture = json!({ "dirs": { "/sys/class/power_supply": ["AC"] }, "files": { "/sys/class/power_supply/AC/type": "Mains\n" } });
+        with_fixture(fixture, || {
+            assert!(!has_battery());
+            assert_eq!(get_battery(), None);
+        });
     }
 }
\ No newline at end of file
@@ -0,0 +1,77 @@
+/// Marketing names for Mac model identifiers (`sysctl hw.model`), covering Apple Silicon and the
+/// last generations of Intel Macs. Identifiers shared by several configurations map to one name.
+const MAC_MODELS: [(&str, &str); 64] = [
+    // Apple Silicon
+    ("MacBookAir10,1", "MacBook Air (M1, 2020)"),
+    ("MacBookPro17,1", "MacBook Pro (13-inch, M1, 2020)"),
+    ("Macmini9,1", "Mac mini (M1, 2020)"),
+    ("iMac21,1", "iMac (24-inch, M1, 2021)"),
+    ("iMac21,2", "iMac (24-inch, M1, 2021)"),
+    ("MacBookPro18,1", "MacBook Pro (16-inch, 2021)"),
+    ("MacBookPro18,2", "MacBook Pro (16-inch, 2021)"),
+    ("MacBookPro18,3", "MacBook Pro (14-inch, 2021)"),
+    ("MacBookPro18,4", "MacBook Pro (14-inch, 2021)"),
+    ("Mac13,1", "Mac Studio (2022)"),
+    ("Mac13,2", "Mac Studio (2022)"),
+    ("Mac14,2", "MacBook Air (M2, 2022)"),
+    ("Mac14,7", "MacBook Pro (13-inch, M2, 2022)"),
+    ("Mac14,3", "Mac mini (2023)"),
+    ("Mac14,12", "Mac mini (2023)"),
+    ("Mac14,5", "MacBook Pro (14-inch, 2023)"),
+    ("Mac14,9", "MacBook Pro (14-inch, 2023)"),
+    ("Mac14,6", "MacBook Pro (16-inch, 2023)"),
+    ("Mac14,10", "MacBook Pro (16-inch, 2023)"),
+    ("Mac14,15", "MacBook Air (15-inch, M2, 2023)"),
+    ("Mac14,13", "Mac Studio (2023)"),
+    ("Mac14,14", "Mac Studio (2023)"),
+    ("Mac14,8", "Mac Pro (2023)"),
+    ("Mac15,3", "MacBook Pro (14-inch, M3, Nov 2023)"),
+    ("Mac15,4", "iMac (24-inch, 2023)"),
+    ("Mac15,5", "iMac (24-inch, 2023)"),
+    ("Mac15,6", "MacBook Pro (14-inch, Nov 2023)"),
+    ("Mac15,8", "MacBook Pro (14-inch, Nov 2023)"),
+    ("Mac15,10", "MacBook Pro (14-inch, Nov 2023)"),
+    ("Mac15,7", "MacBook Pro (16-inch, Nov 2023)"),
+    ("Mac15,9", "MacBook Pro (16-inch, Nov 2023)"),
+    ("Mac15,11", "MacBook Pro (16-inch, Nov 2023)"),
+    ("Mac15,12", "MacBook Air (13-inch, M3, 2024)"),
+    ("Mac15,13", "MacBook Air (15-inch, M3, 2024)"),
+    ("Mac15,14", "Mac Studio (2025)"),
+    ("Mac16,1", "MacBook Pro (14-inch, M4, 2024)"),
+    ("Mac16,6", "MacBook Pro (14-inch, 2024)"),
+    ("Mac16,8", "MacBook Pro (14-inch, 2024)"),
+    ("Mac16,5", "MacBook Pro (16-inch, 2024)"),
+    ("Mac16,7", "MacBook Pro (16-inch, 2024)"),
+    ("Mac16,2", "iMac (24-inch, 2024)"),
+    ("Mac16,3", "iMac (24-inch, 2024)"),
+    ("Mac16,10", "Mac mini (2024)"),
+    ("Mac16,11", "Mac mini (2024)"),
+    ("Mac16,12", "MacBook Air (13-inch, M4, 2025)"),
+    ("Mac16,13", "MacBook Air (15-inch, M4, 2025)"),
+    ("Mac16,9", "Mac Studio (2025)"),
+
+    // Intel
+    ("MacBookPro16,1", "MacBook Pro (16-inch, 2019)"),
+    ("MacBookPro16,4", "MacBook Pro (16-inch, 2019)"),
+    ("MacBookPro16,2", "MacBook Pro (13-inch, 2020, Four Thunderbolt 3 ports)"),
+    ("MacBookPro16,3", "MacBook Pro (13-inch, 2020, Two Thunderbolt 3 ports)"),
+    ("MacBookPro15,1", "MacBook Pro (15-inch, 2018-2019)"),
+    ("MacBookPro15,3", "MacBook Pro (15-inch, 2018-2019)"),
+    ("MacBookPro15,2", "MacBook Pro (13-inch, 2018-2019, Four Thunderbolt 3 ports)"),
+    ("MacBookPro15,4", "MacBook Pro (13-inch, 2019, Two Thunderbolt 3 ports)"),
+    ("MacBookAir9,1", "MacBook Air (Retina, 13-inch, 2020)"),
+    ("MacBookAir8,2", "MacBook Air (Retina, 13-inch, 2019)"),
+    ("MacBookAir8,1", "MacBook Air (Retina, 13-inch, 2018)"),
+    ("iMac20,1", "iMac (Retina 5K, 27-inch, 2020)"),
+    ("iMac20,2", "iMac (Retina 5K, 27-inch, 2020)"),
+    ("iMac19,1", "iMac (Retina 5K, 27-inch, 2019)"),
+    ("iMacPro1,1", "iMac Pro (2017)"),
+    ("Macmini8,1", "Mac mini (2018)"),
+    ("MacPro7,1", "Mac Pro (2019)"),
+];
+
+pub fn get_mac_marketing_name(identifier: &str) -> Option<&'static str> {
+    MAC_MODELS.iter()
+        .find(|(id, _)| *id == identifier)
+        .map(|(_, name)| *name)
+}
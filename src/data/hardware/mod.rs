@@ -1,2 +1,5 @@
-mod individual_stats;
-pub mod hardware;
\ No newline at end of file
+mod cgroup;
+pub mod disk;
+pub mod individual_stats;
+mod mac_models;
+pub mod hardware;
@@ -0,0 +1,86 @@
+use crate::_utils::system_provider::provider;
+use crate::sh;
+use std::path::Path;
+
+pub fn get_sip_status() -> String {
+    // "System Integrity Protection status: enabled."
+    let status = sh!("csrutil", "status");
+
+    match status.stdout.split_once(':') {
+        Some((_, state)) if status.err_code == 0 => capitalize(state.trim().trim_end_matches('.')),
+        _ => "Unknown".to_string(),
+    }
+}
+
+pub fn get_pending_updates() -> String {
+    // Read the count cached by the last background check, `softwareupdate -l` hits the network and takes seconds
+    let pending = sh!("defaults", "read", "/Library/Preferences/com.apple.SoftwareUpdate", "LastRecommendedUpdatesAvailable");
+
+    match pending.stdout.trim().parse::<u32>() {
+        Ok(0) => "Up to date".to_string(),
+        Ok(1) => "1 update pending".to_string(),
+        Ok(count) => format!("{} updates pending", count),
+        Err(_) => "Unknown".to_string(),
+    }
+}
+
+pub fn get_filevault() -> String {
+    // "FileVault is On." / "FileVault is Off."
+    let status = sh!("fdesetup", "status");
+
+    match status.stdout.lines().next().and_then(|line| line.strip_prefix("FileVault is ")) {
+        Some(state) if status.err_code == 0 => state.trim_end_matches('.').to_string(),
+        _ => "Unknown".to_string(),
+    }
+}
+
+pub fn get_homebrew() -> String {
+    let Some(prefix) = get_brew_prefix() else {
+        return "Not Installed".to_string();
+    };
+
+    // Count straight from the prefix, running `brew` itself costs a Ruby startup per call
+    // Every entry is a formula, cask or tap directory, apart from files like .DS_Store
+    let list_dirs = |path: &Path| provider().list_dir(&path.to_string_lossy()).into_iter().filter(|name| !name.starts_with('.')).collect::<Vec<_>>();
+    let formulae = list_dirs(&prefix.join("Cellar")).len();
+    let casks = list_dirs(&prefix.join("Caskroom")).len();
+    let taps: usize = list_dirs(&prefix.join("Library/Taps")).iter()
+        .map(|user| list_dirs(&prefix.join("Library/Taps").join(user)).len())
+        .sum();
+
+    let arch = if prefix == Path::new("/opt/homebrew") { "ARM" } else { "Intel" };
+
+    format!("{} formulae, {} casks, {} taps ({}, {})", formulae, casks, taps, prefix.display(), arch)
+}
+
+pub fn get_brew_doctor() -> String {
+    let Some(prefix) = get_brew_prefix() else {
+        return "Not Installed".to_string();
+    };
+
+    // `brew doctor` exits non-zero when it has any warnings
+    let doctor = sh!(prefix.join("bin/brew").to_string_lossy(), "doctor", "--quiet");
+    let warnings = doctor.stdout.lines().chain(doctor.stderr.lines()).filter(|line| line.starts_with("Warning:")).count();
+
+    match (doctor.err_code, warnings) {
+        (0, _) => "No issues".to_string(),
+        (_, 0) => "Issues found".to_string(),
+        (_, 1) => "1 warning".to_string(),
+        (_, n) => format!("{} warnings", n),
+    }
+}
+
+/// Homebrew installs to /opt/homebrew on Apple Silicon and /usr/local on Intel
+fn get_brew_prefix() -> Option<&'static Path> {
+    ["/opt/homebrew", "/usr/local"].into_iter()
+        .map(Path::new)
+        .find(|prefix| prefix.join("bin/brew").is_file())
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
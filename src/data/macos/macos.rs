@@ -0,0 +1,6 @@
+use crate::data::common::{format_section, Section};
+use crate::data::registry::{gather_stats, Samples};
+
+pub fn get_macos_info() -> Section {
+    format_section("macOS", gather_stats("macos", Samples::default()))
+}
@@ -0,0 +1,2 @@
+pub mod macos;
+pub mod individual_stats;
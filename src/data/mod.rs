@@ -1,3 +1,13 @@
+mod gaming;
+mod git;
 mod hardware;
+mod macos;
+mod network;
+mod raspberry_pi;
+mod rules;
 mod software;
-pub mod common;
\ No newline at end of file
+mod storage;
+mod time;
+mod toolchains;
+pub mod common;
+pub mod registry;
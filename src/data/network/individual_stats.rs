@@ -0,0 +1,322 @@
+use crate::_utils::anonymize::{redact, redact_ip, redact_mac};
+use crate::_utils::status::warn_module;
+use crate::_utils::procfs;
+use crate::_utils::read_file::cat;
+use crate::_utils::sort_by_priority::SortByPriority;
+use crate::_utils::system_provider::{provider, Interface};
+use crate::_utils::units::human_bytes;
+use crate::_utils::which::which;
+use crate::config::context::Context;
+use crate::sh;
+use std::cmp::Ordering;
+use std::net::{IpAddr, Ipv4Addr};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const THROUGHPUT_SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct Throughput {
+    pub interface: String,
+    pub rx_per_sec: f64,
+    pub tx_per_sec: f64,
+}
+
+/// Network interfaces ordered by how likely they are to be the primary connection
+pub fn get_sorted_interfaces(interfaces: &[Interface]) -> Vec<&Interface> {
+    let mut networks_sorted: Vec<&Interface> = interfaces.iter().collect();
+
+    // Sort the interfaces by priority
+    networks_sorted.sort_by_priority(|network| {
+        let nw_name = network.name.to_lowercase();
+
+        // Prioritize physical interfaces: Ethernet, Wifi, WWAN
+        if nw_name.starts_with("en") { 0 }
+        else if nw_name.starts_with("wl") { 1 }
+        else if nw_name.starts_with("wwan") { 2 }
+        // Deprioritize VPN interfaces
+        else if nw_name.starts_with("tailscale") { u32::MAX - 1 }
+        else if nw_name.starts_with("tun") { 1000 }
+        else if nw_name.starts_with("tap") { 1000 }
+        else if nw_name.starts_with("wg") { 1000 }
+        else if nw_name.starts_with("vpn") { 1000 }
+        // Also deprioritize NetworkManager stuff a bit more
+        else if nw_name.starts_with("nm") { 1001 }
+        // Make sure loopback is last
+        else if nw_name == "lo" { u32::MAX }
+        // Default priority for other interfaces (brX, hostX, etc.)
+        else { 69 }
+    });
+
+    networks_sorted
+}
+
+/// The highest priority interface with an IP address, along with that address
+pub fn get_primary_interface(interfaces: &[Interface]) -> Option<(&Interface, IpAddr)> {
+    get_sorted_interfaces(interfaces).into_iter()
+        .find_map(|interface| Some((interface, extract_ip(interface)?)))
+}
+
+/// Extract IP address from an `Interface` (prioritizing IPv4 over IPv6)
+fn extract_ip(interface: &Interface) -> Option<IpAddr> {
+    let mut addrs: Vec<IpAddr> = interface.addresses.clone();
+
+    addrs.sort_by(|a, b|
+        if a.is_ipv4() && b.is_ipv6() { Ordering::Less }
+        else if b.is_ipv6() && a.is_ipv4() { Ordering::Greater }
+        else { Ordering::Equal }
+    );
+
+    addrs.first().copied()
+}
+
+pub fn get_ip_addr() -> String {
+    // Return the first non-loopback interface with an IP address
+    match get_primary_interface(provider().interfaces()) {
+        Some((_, ip)) => redact_ip(ip.to_string()),
+        None => "No Connection".to_string(),
+    }
+}
+
+/// The primary interface's negotiated speed and medium, e.g. "1 Gbps Ethernet (enp5s0)"
+pub fn get_link() -> String {
+    let interfaces = provider().interfaces();
+    let Some((Interface { name, .. }, _)) = get_primary_interface(interfaces) else {
+        return "No Connection".to_string();
+    };
+
+    let sys_path = format!("/sys/class/net/{}", name);
+    let kind = if provider().exists(&format!("{}/wireless", sys_path)) || name.starts_with("wl") {
+        "WiFi"
+    } else if name.starts_with("ww") {
+        "WWAN"
+    } else if cat(&format!("{}/type", sys_path)).trim() == "1" || name.starts_with("en") || name.starts_with("eth") {
+        "Ethernet"
+    } else {
+        "Virtual"
+    };
+
+    // Wired links report their speed in Mbps, WiFi reports -1 so ask iw for the current bitrate
+    let speed_mbps = match cat(&format!("{}/speed", sys_path)).trim().parse::<i64>() {
+        Ok(speed) if speed > 0 => Some(speed as f64),
+        _ if kind == "WiFi" && which("iw").is_some() => {
+            sh!("iw", "dev", name, "link").stdout.lines()
+                .find_map(|line| line.trim().strip_prefix("tx bitrate:"))
+                .and_then(|bitrate| bitrate.split_whitespace().next()?.parse::<f64>().ok())
+        },
+        _ if kind == "WiFi" => {
+            warn_module("network", "iw not found, WiFi link speed unavailable");
+            None
+        },
+        _ => None,
+    };
+
+    match speed_mbps {
+        Some(speed) if speed >= 1000.0 => format!("{} Gbps {} ({})", speed / 1000.0, kind, name),
+        Some(speed) => format!("{} Mbps {} ({})", speed, kind, name),
+        None => format!("{} ({})", kind, name),
+    }
+}
+
+pub fn get_gateway() -> String {
+    // /proc/net/route lists the gateway as the raw in-memory (network order) u32, the default route has destination 0
+    let gateway = cat("/proc/net/route").lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| fields.len() > 2 && fields[1] == "00000000")
+        .and_then(|fields| u32::from_str_radix(fields[2], 16).ok())
+        .map(|gateway| Ipv4Addr::from(u32::from_be(gateway)));
+
+    if let Some(gateway) = gateway {
+        return redact_ip(gateway.to_string());
+    }
+
+    // macOS and the BSDs: "    gateway: 192.168.1.1"
+    sh!("route", "-n", "get", "default").stdout.lines()
+        .find_map(|line| line.trim().strip_prefix("gateway:"))
+        .map(|gateway| redact_ip(gateway.trim().to_string()))
+        .unwrap_or("None".to_string())
+}
+
+pub fn get_dns() -> String {
+    let mut servers: Vec<String> = cat("/etc/resolv.conf").lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .map(|server| server.trim().to_string())
+        .collect();
+
+    // systemd-resolved only lists its local stub in resolv.conf, ask it for the real upstream servers
+    if servers.iter().all(|server| server.starts_with("127.0.0.5")) && which("resolvectl").is_some() {
+        let upstream: Vec<String> = sh!("resolvectl", "dns").stdout.lines()
+            .filter_map(|line| line.split_once(':'))
+            .flat_map(|(_, addrs)| addrs.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+            .collect();
+        if !upstream.is_empty() {
+            servers = upstream;
+        }
+    }
+
+    servers.dedup();
+    if servers.is_empty() {
+        "None".to_string()
+    } else {
+        servers.into_iter().map(redact_ip).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/// The primary interface's MAC address
+pub fn get_mac_addr() -> String {
+    let interfaces = provider().interfaces();
+    let Some((interface, _)) = get_primary_interface(interfaces) else {
+        return "No Connection".to_string();
+    };
+
+    format!("{} ({})", redact_mac(interface.mac.clone()), interface.name)
+}
+
+/// Active VPN and overlay interfaces, e.g. "WireGuard (wg0), Tailscale (tailscale0, node: box)"
+pub fn get_vpn() -> String {
+    let interfaces = provider().interfaces();
+    let vpns: Vec<String> = get_sorted_interfaces(interfaces).into_iter()
+        .filter(|interface| extract_ip(interface).is_some())
+        .map(|interface| &interface.name)
+        .filter_map(|name| {
+            let kind = get_vpn_kind(name)?;
+
+            // Tailscale knows this machine's name on the tailnet
+            if kind == "Tailscale" && let Some(node) = get_tailscale_node() {
+                return Some(format!("{} ({}, node: {})", kind, name, node));
+            }
+
+            Some(format!("{} ({})", kind, name))
+        })
+        .collect();
+
+    if vpns.is_empty() { "None".to_string() } else { vpns.join(", ") }
+}
+
+/// Classifies VPN/overlay interfaces by name, these are the ones `get_sorted_interfaces` deprioritizes
+fn get_vpn_kind(name: &str) -> Option<&'static str> {
+    const VPN_PREFIXES: [(&str, &str); 7] = [
+        ("tailscale", "Tailscale"),
+        ("nordlynx", "NordLynx"),
+        ("wg", "WireGuard"),
+        ("zt", "ZeroTier"),
+        ("tun", "TUN"),
+        ("tap", "TAP"),
+        ("vpn", "VPN"),
+    ];
+
+    let name = name.to_lowercase();
+    VPN_PREFIXES.iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map(|(_, kind)| *kind)
+}
+
+fn get_tailscale_node() -> Option<String> {
+    which("tailscale")?;
+
+    let status = sh!("tailscale", "status", "--json");
+    let json: serde_json::Value = serde_json::from_str(&status.stdout).ok()?;
+    let node = json.get("Self")?.get("HostName")?.as_str()?;
+
+    Some(redact(node.to_string(), "node"))
+}
+
+/// Starts measuring the primary interface's throughput in the background
+///
+/// Throughput is the traffic between two refreshes `THROUGHPUT_SAMPLE_INTERVAL` apart, so this
+/// should be started before the other gatherers run to hide the delay behind them.
+pub fn start_throughput_sample() -> JoinHandle<Option<Throughput>> {
+    let context = Context::current();
+    thread::spawn(move || context.enter(|| {
+        let (interface, _) = get_primary_interface(provider().interfaces())?;
+        let name = interface.name.as_str();
+
+        let before = get_traffic(name)?;
+        let start = Instant::now();
+        thread::sleep(THROUGHPUT_SAMPLE_INTERVAL);
+        let after = get_traffic(name)?;
+        let elapsed = start.elapsed().as_secs_f64();
+
+        Some(Throughput {
+            interface: name.to_string(),
+            rx_per_sec: after.0.saturating_sub(before.0) as f64 / elapsed,
+            tx_per_sec: after.1.saturating_sub(before.1) as f64 / elapsed,
+        })
+    }))
+}
+
+/// Bytes received and transmitted by an interface so far, from /proc/net/dev where there is one
+fn get_traffic(name: &str) -> Option<(u64, u64)> {
+    let net_dev = cat("/proc/net/dev");
+    if !net_dev.is_empty() {
+        return procfs::net_dev(&net_dev)
+            .find(|dev| dev.name == name)
+            .map(|dev| (dev.rx_bytes, dev.tx_bytes));
+    }
+
+    provider().sample_interfaces().into_iter()
+        .find(|interface| interface.name == name)
+        .map(|interface| (interface.rx_bytes, interface.tx_bytes))
+}
+
+pub fn get_throughput(sample: Option<&Throughput>) -> String {
+    match sample {
+        Some(sample) => format!(
+            "↓ {}/s ↑ {}/s ({})",
+            human_bytes(sample.rx_per_sec), human_bytes(sample.tx_per_sec), sample.interface
+        ),
+        None => "No Connection".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_utils::fixture::with_fixture;
+    use serde_json::json;
+
+    fn interfaces() -> serde_json::Value {
+        json!([
+            { "name": "lo", "addresses": ["127.0.0.1"], "mac": "00:00:00:00:00:00" },
+            { "name": "wg0", "addresses": ["10.0.0.2"], "mac": "00:00:00:00:00:00" },
+            { "name": "enp5s0", "addresses": ["fe80::1", "192.168.1.20"], "mac": "a8:a1:59:3c:11:7e" },
+        ])
+    }
+
+    #[test]
+    fn prefers_the_physical_interface() {
+        with_fixture(json!({ "interfaces": interfaces() }), || {
+            assert_eq!(get_ip_addr(), "192.168.1.20");
+            assert_eq!(get_mac_addr(), "a8:a1:59:3c:11:7e (enp5s0)");
+            assert_eq!(get_vpn(), "WireGuard (wg0)");
+        });
+        assert_eq!(with_fixture(json!({ "interfaces": [] }), get_ip_addr), "No Connection");
+    }
+
+    #[test]
+    fn reads_the_link_speed() {
+        let fixture = json!({ "interfaces": interfaces(), "files": { "/sys/class/net/enp5s0/speed": "1000\n" } });
+        assert_eq!(with_fixture(fixture, get_link), "1 Gbps Ethernet (enp5s0)");
+    }
+
+    #[test]
+    fn reads_the_default_gateway() {
+        let route = "Iface\tDestination\tGateway\tFlags\n\
+                     enp5s0\t00000000\t0101A8C0\t0003\n\
+                     enp5s0\t0001A8C0\t00000000\t0001\n";
+        assert_eq!(with_fixture(json!({ "files": { "/proc/net/route": route } }), get_gateway), "192.168.1.1");
+        assert_eq!(with_fixture(json!({}), get_gateway), "None");
+    }
+
+    #[test]
+    fn asks_resolved_for_the_upstream_dns_servers() {
+        let fixture = json!({ "files": { "/etc/resolv.conf": "nameserver 1.1.1.1\nnameserver 9.9.9.9\n" } });
+        assert_eq!(with_fixture(fixture, get_dns), "1.1.1.1, 9.9.9.9");
+
+        let fixture = json!({
+            "files": { "/etc/resolv.conf": "nameserver 127.0.0.53\noptions edns0\n" },
+            "commands": { "resolvectl dns": "Global:\nLink 2 (enp5s0): 192.168.1.1\n" },
+        });
+        assert_eq!(with_fixture(fixture, get_dns), "192.168.1.1");
+    }
+}
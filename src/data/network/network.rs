@@ -0,0 +1,13 @@
+use crate::data::common::{format_section, Section};
+use crate::data::network::individual_stats::Throughput;
+use crate::data::registry::{gather_stats, Samples};
+use std::thread::JoinHandle;
+
+pub fn get_network_info(throughput_sample: Option<JoinHandle<Option<Throughput>>>) -> Section {
+    let samples = Samples {
+        throughput: throughput_sample.and_then(|sample| sample.join().ok()).flatten(),
+        ..Samples::default()
+    };
+
+    format_section("Network", gather_stats("network", samples))
+}
@@ -0,0 +1,82 @@
+use crate::_utils::procfs::cpuinfo_field;
+use crate::_utils::read_file::cat;
+use crate::sh;
+
+pub fn get_board() -> String {
+    // The device tree string is NUL terminated
+    let model = cat("/proc/device-tree/model").trim_end_matches('\0').trim().to_string();
+    let cpuinfo = cat("/proc/cpuinfo");
+
+    match cpuinfo_field(&cpuinfo, "Revision") {
+        Some(revision) => format!("{} [{}]", model, revision),
+        None => model,
+    }
+}
+
+pub fn get_firmware() -> String {
+    // First line is the build date, the version line holds the commit hash
+    //   Mar 17 2023 10:52:00
+    //   Copyright (c) 2012 Broadcom
+    //   version 82f3750a65fadae9a38077e3c2e217ad158c8d54 (clean) (release) (start)
+    let version = sh!("vcgencmd", "version");
+    if version.err_code != 0 {
+        return "Unknown".to_string();
+    }
+
+    let date = version.stdout.lines().next().unwrap_or_default().trim();
+    let hash = version.stdout.lines()
+        .find_map(|line| line.strip_prefix("version "))
+        .and_then(|line| line.split_whitespace().next())
+        .map(|hash| &hash[..hash.len().min(7)]);
+
+    match hash {
+        Some(hash) => format!("{} ({})", date, hash),
+        None => date.to_string(),
+    }
+}
+
+pub fn get_soc_temp() -> String {
+    match cat("/sys/class/thermal/thermal_zone0/temp").trim().parse::<f64>() {
+        Ok(millidegrees) => format!("{:.1}°C", millidegrees / 1000.0),
+        // "temp=48.3'C"
+        Err(_) => match sh!("vcgencmd", "measure_temp").stdout.trim().strip_prefix("temp=") {
+            Some(temp) => temp.replace('\'', "°"),
+            None => "Unknown".to_string(),
+        }
+    }
+}
+
+pub fn get_throttled() -> String {
+    // "throttled=0x50005", the low bits are current conditions and bits 16+ are sticky since boot
+    let throttled = sh!("vcgencmd", "get_throttled");
+    let Some(flags) = throttled.stdout.trim().strip_prefix("throttled=0x").and_then(|hex| u32::from_str_radix(hex, 16).ok()) else {
+        return "Unknown".to_string();
+    };
+
+    if flags == 0 {
+        return "OK".to_string();
+    }
+
+    const CONDITIONS: [(u32, &str); 4] = [
+        (0, "under-voltage"),
+        (1, "frequency capped"),
+        (2, "throttled"),
+        (3, "soft temperature limit"),
+    ];
+
+    let mut now: Vec<&str> = Vec::new();
+    let mut since_boot: Vec<&str> = Vec::new();
+    for (bit, condition) in CONDITIONS {
+        if flags & (1 << bit) != 0 {
+            now.push(condition);
+        } else if flags & (1 << (bit + 16)) != 0 {
+            since_boot.push(condition);
+        }
+    }
+
+    match (now.is_empty(), since_boot.is_empty()) {
+        (false, true) => format!("Now: {}", now.join(", ")),
+        (true, false) => format!("Since boot: {}", since_boot.join(", ")),
+        _ => format!("Now: {}; since boot: {}", now.join(", "), since_boot.join(", ")),
+    }
+}
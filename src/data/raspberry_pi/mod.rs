@@ -0,0 +1,2 @@
+pub mod raspberry_pi;
+pub mod individual_stats;
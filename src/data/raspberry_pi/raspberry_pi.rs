@@ -0,0 +1,12 @@
+use crate::_utils::read_file::cat;
+use crate::config::context::config;
+use crate::data::common::{format_section, Section};
+use crate::data::registry::{gather_stats, Samples};
+
+pub fn get_raspberry_pi_info() -> Section {
+    if !config().get_bool("raspberry_pi.enabled", true) || !cat("/proc/device-tree/model").starts_with("Raspberry Pi") {
+        return Section::default();
+    }
+
+    format_section("Raspberry Pi", gather_stats("raspberry_pi", Samples::default()))
+}
@@ -0,0 +1,325 @@
+use crate::config::context::{appearance, config};
+use crate::data::common::{Stat, StatValue};
+use crate::data::gaming::individual_stats::{get_gamescope, get_proton};
+use crate::data::git::individual_stats::{get_branch, get_repo_root, get_status};
+use crate::data::hardware::disk::get_drive;
+use crate::data::hardware::individual_stats::{get_battery, get_brightness, get_cpu, get_cpu_details, get_cpu_temp, get_cpu_usage, get_dirty_pages, get_fans, get_gpu, get_gpu_temp, get_hugepages, get_model, get_numa, get_per_core_usage, get_pressure, get_ram, get_screen_res, get_slab, get_top_processes, has_battery, start_cpu_sample};
+use crate::data::macos::individual_stats::{get_brew_doctor, get_filevault, get_homebrew, get_pending_updates, get_sip_status};
+use crate::data::network::individual_stats::{get_dns, get_gateway, get_ip_addr, get_link, get_mac_addr, get_throughput, get_vpn, start_throughput_sample, Throughput};
+use crate::data::raspberry_pi::individual_stats::{get_board, get_firmware, get_soc_temp, get_throttled};
+use crate::data::software::individual_stats::{format_wallpaper, get_boot_time, get_cmdline, get_console, get_editor, get_guix, get_image, get_kernel, get_keyboard_layout, get_nix, get_os, get_packages, get_prompt_framework, get_shell, get_taint, get_terminal, get_uptime, get_uptime_record, get_user, get_wallpaper, get_window_manager, get_wm_gaps, get_wm_monitors, get_wm_workspaces};
+use crate::data::storage::individual_stats::{get_btrfs_health, get_disk_io, get_drive_temps, get_smart_status, get_zpool_health, start_disk_io_sample, DiskIo};
+use crate::data::time::individual_stats::{get_clock, get_ntp_status, get_timezone};
+use crate::_utils::system_provider::provider;
+use crate::_utils::which::which;
+#[cfg(feature = "async")]
+use crate::_utils::status::fail;
+#[cfg(feature = "async")]
+use crate::config::context::Context;
+#[cfg(feature = "async")]
+use std::panic;
+#[cfg(feature = "async")]
+use std::sync::Arc;
+#[cfg(feature = "async")]
+use std::time::Duration;
+use sysinfo::System;
+
+/// A stat vega knows how to gather. Its `id` stays the same across versions and platforms (unlike
+/// the label, which can repeat between sections), so config, output and tooling refer to stats by it.
+pub struct StatDef {
+    /// e.g. "os", "kernel" or "wm"
+    pub id: &'static str,
+    /// Three letter label shown beside the value, e.g. "WMN"
+    pub label: &'static str,
+    /// What the label stands for, e.g. "Window manager"
+    pub name: &'static str,
+    /// Nerd Font glyph for the stat
+    pub icon: &'static str,
+    /// Module (section) the stat is gathered and shown in
+    pub module: &'static str,
+    /// `uname` sysnames the stat is available on, empty for all of them
+    pub platforms: &'static [&'static str],
+    /// What else the system needs for the stat to mean anything, it's left out silently otherwise
+    pub requires: &'static [Requirement],
+    /// Boolean config key turning the stat on or off and its default, `None` for always shown
+    pub config: Option<(&'static str, bool)>,
+    /// The stat's values, usually one, none when there's nothing to show and several for repeated
+    /// lines (e.g. one HDD per mount)
+    pub gather: fn(&Samples) -> Vec<StatValue>,
+}
+
+impl StatDef {
+
+    /// The fields most stats leave alone, for filling in a definition with `..StatDef::DEFAULT`: shown
+    /// on every platform, with nothing else required and no config key
+    pub const DEFAULT: StatDef = StatDef {
+        id: "",
+        label: "",
+        name: "",
+        icon: "",
+        module: "",
+        platforms: &[],
+        requires: &[],
+        config: None,
+        gather: |_| Vec::new(),
+    };
+
+}
+
+/// A condition a stat needs on top of the platform
+pub enum Requirement {
+    /// An X11, Wayland or macOS desktop, rather than e.g. an SSH session or a text console
+    GuiSession,
+    /// A battery powering the machine, i.e. a laptop or a handheld rather than a desktop or server
+    Battery,
+    /// Not running on a virtual console, where the stat would describe a desktop that isn't showing
+    NoConsole,
+    /// A program on the PATH, for stats that only it can tell
+    Program(&'static str),
+}
+
+impl Requirement {
+
+    pub fn is_met(&self) -> bool {
+        match self {
+            Requirement::GuiSession => has_gui_session(),
+            Requirement::Battery => has_battery(),
+            Requirement::NoConsole => get_console().is_none(),
+            Requirement::Program(program) => which(program).is_some(),
+        }
+    }
+
+}
+
+/// The measurements taken over time before gathering, for the stats that need them
+#[derive(Default)]
+pub struct Samples {
+    pub cpu: Option<System>,
+    pub throughput: Option<Throughput>,
+    pub disk_io: Option<DiskIo>,
+}
+
+impl Samples {
+
+    /// Takes the measurements one stat needs, for gathering it on its own (e.g. as a segment)
+    pub fn for_stat(id: &str) -> Samples {
+        match id {
+            "cpu_usage" | "cpu_cores" | "processes" => Samples {
+                cpu: start_cpu_sample(id == "processes").join().ok(),
+                ..Samples::default()
+            },
+            "throughput" => Samples {
+                throughput: start_throughput_sample().join().ok().flatten(),
+                ..Samples::default()
+            },
+            "disk_io" => Samples {
+                disk_io: start_disk_io_sample().join().ok().flatten(),
+                ..Samples::default()
+            },
+            _ => Samples::default(),
+        }
+    }
+
+}
+
+/// Every stat, in the order they are shown
+pub static STATS: &[StatDef] = &[
+    // Hardware
+    StatDef { id: "model", label: "MDL", name: "Model", icon: "󰌢", module: "hardware", gather: |_| one(get_model()), ..StatDef::DEFAULT },
+    StatDef { id: "cpu", label: "CPU", name: "Processor", icon: "", module: "hardware", gather: |_| one(get_cpu()), ..StatDef::DEFAULT },
+    StatDef { id: "cpu_details", label: "CPX", name: "Processor details", icon: "", module: "hardware", config: Some(("cpu.extended", false)), gather: |_| one(get_cpu_details()), ..StatDef::DEFAULT },
+    StatDef { id: "cpu_usage", label: "USG", name: "Processor usage", icon: "", module: "hardware", config: Some(("cpu.usage", false)), gather: |samples| {
+        samples.cpu.iter().map(|cpu| get_cpu_usage(cpu).into()).collect()
+    }, ..StatDef::DEFAULT },
+    StatDef { id: "cpu_cores", label: "COR", name: "Usage per core", icon: "", module: "hardware", config: Some(("cpu.per_core", false)), gather: |samples| {
+        samples.cpu.iter().map(|cpu| get_per_core_usage(cpu).into()).collect()
+    }, ..StatDef::DEFAULT },
+    StatDef { id: "cpu_temperature", label: "CTM", name: "Processor temperature", icon: "", module: "hardware", config: Some(("temperature.enabled", false)), gather: |_| optional(get_cpu_temp()), ..StatDef::DEFAULT },
+    StatDef { id: "processes", label: "TOP", name: "Busiest processes", icon: "", module: "hardware", config: Some(("processes.enabled", false)), gather: |samples| {
+        let config = config();
+        let count = config.get_int("processes.count", 5).max(0) as usize;
+        let sort_by = config.get_str("processes.sort").unwrap_or("cpu");
+        samples.cpu.iter().map(|cpu| get_top_processes(cpu, count, sort_by).into()).collect()
+    }, ..StatDef::DEFAULT },
+    StatDef { id: "pressure", label: "PSI", name: "Pressure stall", icon: "", module: "hardware", platforms: &["Linux"], config: Some(("pressure.enabled", false)), gather: |_| one(get_pressure()), ..StatDef::DEFAULT },
+    StatDef { id: "numa", label: "NUM", name: "NUMA topology", icon: "", module: "hardware", platforms: &["Linux"], config: Some(("numa.enabled", false)), gather: |_| optional(get_numa()), ..StatDef::DEFAULT },
+    StatDef { id: "gpu", label: "GPU", name: "Graphics", icon: "󰢮", module: "hardware", gather: |_| one(get_gpu()), ..StatDef::DEFAULT },
+    StatDef { id: "gpu_temperature", label: "GTM", name: "Graphics temperature", icon: "", module: "hardware", config: Some(("temperature.enabled", false)), gather: |_| optional(get_gpu_temp()), ..StatDef::DEFAULT },
+    StatDef { id: "fans", label: "FAN", name: "Fans", icon: "󰈐", module: "hardware", config: Some(("fans.enabled", false)), gather: |_| optional(get_fans()), ..StatDef::DEFAULT },
+    StatDef { id: "ram", label: "RAM", name: "Memory", icon: "", module: "hardware", gather: |samples| vec![get_ram(samples.cpu.as_ref())], ..StatDef::DEFAULT },
+    StatDef { id: "hugepages", label: "HPG", name: "Hugepages", icon: "", module: "hardware", platforms: &["Linux"], config: Some(("memory.kernel", false)), gather: |_| optional(get_hugepages()), ..StatDef::DEFAULT },
+    StatDef { id: "slab", label: "SLB", name: "Kernel slab", icon: "", module: "hardware", platforms: &["Linux"], config: Some(("memory.kernel", false)), gather: |_| optional(get_slab()), ..StatDef::DEFAULT },
+    StatDef { id: "dirty_pages", label: "DRT", name: "Dirty pages", icon: "", module: "hardware", platforms: &["Linux"], config: Some(("memory.kernel", false)), gather: |_| optional(get_dirty_pages()), ..StatDef::DEFAULT },
+    StatDef { id: "disk", label: "HDD", name: "Disk", icon: "", module: "hardware", gather: |_| {
+        let config = config();
+        let mounts = config.get_str_list("disk.mounts");
+        if mounts.is_empty() {
+            return vec![get_drive("/")];
+        }
+        mounts.into_iter().map(get_drive).collect()
+    }, ..StatDef::DEFAULT },
+    StatDef { id: "resolution", label: "RES", name: "Resolution", icon: "󰍹", module: "hardware", requires: &[Requirement::NoConsole], gather: |_| one(get_screen_res()), ..StatDef::DEFAULT },
+    StatDef { id: "brightness", label: "BRT", name: "Brightness", icon: "󰃟", module: "hardware", gather: |_| optional(get_brightness()), ..StatDef::DEFAULT },
+    StatDef { id: "battery", label: "BAT", name: "Battery", icon: "󰁹", module: "hardware", requires: &[Requirement::Battery], gather: |_| optional(get_battery()), ..StatDef::DEFAULT },
+
+    // Software
+    StatDef { id: "os", label: "OS", name: "Operating system", icon: "", module: "software", gather: |_| one(get_os()), ..StatDef::DEFAULT },
+    StatDef { id: "kernel", label: "KER", name: "Kernel", icon: "", module: "software", gather: |_| one(get_kernel()), ..StatDef::DEFAULT },
+    StatDef { id: "cmdline", label: "CMD", name: "Kernel parameters", icon: "", module: "software", platforms: &["Linux"], config: Some(("kernel.cmdline", false)), gather: |_| one(get_cmdline()), ..StatDef::DEFAULT },
+    StatDef { id: "taint", label: "TNT", name: "Kernel taint", icon: "", module: "software", platforms: &["Linux"], config: Some(("kernel.taint", false)), gather: |_| one(get_taint()), ..StatDef::DEFAULT },
+    StatDef { id: "user", label: "USR", name: "User", icon: "", module: "software", config: Some(("user.enabled", false)), gather: |_| vec![get_user()], ..StatDef::DEFAULT },
+    StatDef { id: "uptime", label: "UPT", name: "Uptime", icon: "󰅐", module: "software", gather: |_| vec![get_uptime()], ..StatDef::DEFAULT },
+    StatDef { id: "boot_time", label: "BOT", name: "Boot time", icon: "󰑓", module: "software", config: Some(("uptime.boot_time", false)), gather: |_| vec![get_boot_time()], ..StatDef::DEFAULT },
+    StatDef { id: "uptime_record", label: "REC", name: "Uptime record", icon: "󰔟", module: "software", config: Some(("uptime.record", false)), gather: |_| vec![get_uptime_record()], ..StatDef::DEFAULT },
+    StatDef { id: "packages", label: "PKG", name: "Packages", icon: "󰏖", module: "software", gather: |_| one(get_packages()), ..StatDef::DEFAULT },
+    StatDef { id: "image", label: "IMG", name: "System image", icon: "󰆧", module: "software", gather: |_| optional(get_image()), ..StatDef::DEFAULT },
+    // Nix and Guix live alongside the distro's own package manager, so they get lines of their own
+    StatDef { id: "nix", label: "NIX", name: "Nix", icon: "", module: "software", gather: |_| optional(get_nix()), ..StatDef::DEFAULT },
+    StatDef { id: "guix", label: "GUX", name: "Guix", icon: "", module: "software", gather: |_| optional(get_guix()), ..StatDef::DEFAULT },
+    StatDef { id: "wm", label: "WMN", name: "Window manager", icon: "", module: "software", requires: &[Requirement::GuiSession], gather: |_| one(get_window_manager()), ..StatDef::DEFAULT },
+    StatDef { id: "wm_monitors", label: "MON", name: "Monitors", icon: "󰍺", module: "software", requires: &[Requirement::GuiSession], config: Some(("wm.details", false)), gather: |_| optional(get_wm_monitors()), ..StatDef::DEFAULT },
+    StatDef { id: "wm_workspaces", label: "WSP", name: "Workspaces", icon: "", module: "software", requires: &[Requirement::GuiSession], config: Some(("wm.details", false)), gather: |_| optional(get_wm_workspaces()), ..StatDef::DEFAULT },
+    StatDef { id: "wm_gaps", label: "GAP", name: "Gaps and borders", icon: "󰕰", module: "software", requires: &[Requirement::GuiSession], config: Some(("wm.gaps", false)), gather: |_| optional(get_wm_gaps()), ..StatDef::DEFAULT },
+    StatDef { id: "terminal", label: "TER", name: "Terminal", icon: "", module: "software", gather: |_| one(get_terminal()), ..StatDef::DEFAULT },
+    StatDef { id: "shell", label: "SHL", name: "Shell", icon: "", module: "software", gather: |_| one(get_shell()), ..StatDef::DEFAULT },
+    StatDef { id: "editor", label: "EDT", name: "Editor", icon: "", module: "software", config: Some(("shell.editor", false)), gather: |_| one(get_editor()), ..StatDef::DEFAULT },
+    StatDef { id: "prompt", label: "PRM", name: "Prompt", icon: "", module: "software", config: Some(("shell.prompt", false)), gather: |_| one(get_prompt_framework()), ..StatDef::DEFAULT },
+    StatDef { id: "keyboard", label: "KBD", name: "Keyboard layout", icon: "", module: "software", gather: |_| one(get_keyboard_layout()), ..StatDef::DEFAULT },
+    StatDef { id: "appearance", label: "APR", name: "Appearance", icon: "", module: "software", requires: &[Requirement::GuiSession], gather: |_| one(appearance().to_string()), ..StatDef::DEFAULT },
+    StatDef { id: "wallpaper", label: "WAL", name: "Wallpaper", icon: "󰸉", module: "software", requires: &[Requirement::GuiSession], config: Some(("wallpaper.enabled", false)), gather: |_| one(format_wallpaper(get_wallpaper())), ..StatDef::DEFAULT },
+    StatDef { id: "ip", label: "IP4", name: "IP address", icon: "󰩟", module: "software", config: Some(("network.ip", true)), gather: |_| one(get_ip_addr()), ..StatDef::DEFAULT },
+
+    // Network
+    StatDef { id: "throughput", label: "NET", name: "Network throughput", icon: "󰓅", module: "network", config: Some(("network.throughput", false)), gather: |samples| one(get_throughput(samples.throughput.as_ref())), ..StatDef::DEFAULT },
+    StatDef { id: "link", label: "LNK", name: "Network link", icon: "󰖩", module: "network", config: Some(("network.link", false)), gather: |_| one(get_link()), ..StatDef::DEFAULT },
+    StatDef { id: "gateway", label: "GTW", name: "Gateway", icon: "󰑩", module: "network", config: Some(("network.gateway", false)), gather: |_| one(get_gateway()), ..StatDef::DEFAULT },
+    StatDef { id: "dns", label: "DNS", name: "DNS servers", icon: "󰇖", module: "network", config: Some(("network.dns", false)), gather: |_| one(get_dns()), ..StatDef::DEFAULT },
+    StatDef { id: "mac", label: "MAC", name: "MAC address", icon: "󰌗", module: "network", config: Some(("network.mac", false)), gather: |_| one(get_mac_addr()), ..StatDef::DEFAULT },
+    StatDef { id: "vpn", label: "VPN", name: "VPN", icon: "󰖂", module: "network", config: Some(("network.vpn", false)), gather: |_| one(get_vpn()), ..StatDef::DEFAULT },
+
+    // Storage, only what actually applies to this machine's drives and filesystems
+    StatDef { id: "zfs", label: "ZFS", name: "ZFS pools", icon: "", module: "storage", requires: &[Requirement::Program("zpool")], config: Some(("storage.health", false)), gather: |_| optional(get_zpool_health()), ..StatDef::DEFAULT },
+    StatDef { id: "btrfs", label: "BTR", name: "Btrfs", icon: "", module: "storage", requires: &[Requirement::Program("btrfs")], config: Some(("storage.health", false)), gather: |_| optional(get_btrfs_health()), ..StatDef::DEFAULT },
+    StatDef { id: "drive_temperature", label: "TMP", name: "Drive temperatures", icon: "", module: "storage", config: Some(("storage.temperature", false)), gather: |_| optional(get_drive_temps()), ..StatDef::DEFAULT },
+    StatDef { id: "disk_io", label: "I/O", name: "Disk activity", icon: "", module: "storage", config: Some(("storage.io", false)), gather: |samples| one(get_disk_io(samples.disk_io.as_ref())), ..StatDef::DEFAULT },
+    StatDef { id: "smart", label: "SMT", name: "SMART health", icon: "", module: "storage", requires: &[Requirement::Program("smartctl")], config: Some(("storage.smart", false)), gather: |_| optional(get_smart_status()), ..StatDef::DEFAULT },
+
+    // Raspberry Pi
+    StatDef { id: "rpi_board", label: "BRD", name: "Board", icon: "", module: "raspberry_pi", platforms: &["Linux"], gather: |_| one(get_board()), ..StatDef::DEFAULT },
+    StatDef { id: "rpi_firmware", label: "FWR", name: "Firmware", icon: "", module: "raspberry_pi", platforms: &["Linux"], gather: |_| one(get_firmware()), ..StatDef::DEFAULT },
+    StatDef { id: "rpi_temperature", label: "TMP", name: "SoC temperature", icon: "", module: "raspberry_pi", platforms: &["Linux"], gather: |_| one(get_soc_temp()), ..StatDef::DEFAULT },
+    StatDef { id: "rpi_throttled", label: "THR", name: "Throttling", icon: "", module: "raspberry_pi", platforms: &["Linux"], gather: |_| one(get_throttled()), ..StatDef::DEFAULT },
+
+    // Gaming, each line only shows up when there is something to report
+    StatDef { id: "gamescope", label: "GSC", name: "Gamescope", icon: "󰊴", module: "gaming", platforms: &["Linux"], gather: |_| optional(get_gamescope()), ..StatDef::DEFAULT },
+    StatDef { id: "proton", label: "PRT", name: "Proton", icon: "", module: "gaming", platforms: &["Linux"], gather: |_| optional(get_proton()), ..StatDef::DEFAULT },
+
+    // Git
+    StatDef { id: "git_repo", label: "RPO", name: "Repository", icon: "", module: "git", gather: |_| {
+        optional(get_repo_root().map(|root| root.rsplit('/').next().unwrap_or(&root).to_string()))
+    }, ..StatDef::DEFAULT },
+    StatDef { id: "git_branch", label: "BRN", name: "Branch", icon: "", module: "git", gather: |_| one(get_branch()), ..StatDef::DEFAULT },
+    StatDef { id: "git_status", label: "STS", name: "Status", icon: "", module: "git", gather: |_| one(get_status()), ..StatDef::DEFAULT },
+
+    // Time
+    StatDef { id: "clock", label: "CLK", name: "Local time", icon: "󰥔", module: "time", gather: |_| one(get_clock()), ..StatDef::DEFAULT },
+    StatDef { id: "timezone", label: "TZN", name: "Timezone", icon: "󰥔", module: "time", gather: |_| one(get_timezone()), ..StatDef::DEFAULT },
+    StatDef { id: "ntp", label: "NTP", name: "Clock synchronization", icon: "󰔛", module: "time", platforms: &["Linux"], requires: &[Requirement::Program("timedatectl")], config: Some(("time.ntp", false)), gather: |_| optional(get_ntp_status()) },
+
+    // macOS
+    StatDef { id: "sip", label: "SIP", name: "System Integrity Protection", icon: "", module: "macos", platforms: &["Darwin"], config: Some(("macos.extended", false)), gather: |_| one(get_sip_status()), ..StatDef::DEFAULT },
+    StatDef { id: "updates", label: "UPD", name: "Pending updates", icon: "󰚰", module: "macos", platforms: &["Darwin"], config: Some(("macos.extended", false)), gather: |_| one(get_pending_updates()), ..StatDef::DEFAULT },
+    StatDef { id: "filevault", label: "FVT", name: "FileVault", icon: "", module: "macos", platforms: &["Darwin"], config: Some(("macos.extended", false)), gather: |_| one(get_filevault()), ..StatDef::DEFAULT },
+    StatDef { id: "homebrew", label: "BRW", name: "Homebrew", icon: "", module: "macos", platforms: &["Darwin"], config: Some(("macos.homebrew", false)), gather: |_| one(get_homebrew()), ..StatDef::DEFAULT },
+    StatDef { id: "brew_doctor", label: "DOC", name: "Brew doctor", icon: "", module: "macos", platforms: &["Darwin"], config: Some(("macos.homebrew", false)), gather: |_| one(get_brew_doctor()), ..StatDef::DEFAULT },
+];
+
+/// The stat with an ID
+pub fn stat(id: &str) -> Option<&'static StatDef> {
+    STATS.iter().find(|stat| stat.id == id)
+}
+
+/// Gathers a module's enabled stats, in order
+pub fn gather_stats(module: &str, samples: Samples) -> Vec<Stat> {
+    let sysname = provider().uname().sysname;
+
+    let stats: Vec<&'static StatDef> = STATS.iter()
+        .filter(|stat| stat.module == module)
+        .filter(|stat| stat.platforms.is_empty() || stat.platforms.contains(&sysname.as_str()))
+        .filter(|stat| stat.config.is_none_or(|(key, default)| config().get_bool(key, default)))
+        .filter(|stat| stat.requires.iter().all(Requirement::is_met))
+        .collect();
+
+    gather_values(stats, samples).into_iter()
+        .flat_map(|(stat, values)| values.into_iter().map(|value| Stat {
+            id: stat.id.to_string(),
+            label: stat.label.to_string(),
+            value,
+        }))
+        .collect()
+}
+
+/// Runs the stats' gatherers one after the other
+#[cfg(not(feature = "async"))]
+fn gather_values(stats: Vec<&'static StatDef>, samples: Samples) -> Vec<(&'static StatDef, Vec<StatValue>)> {
+    stats.into_iter().map(|stat| (stat, (stat.gather)(&samples))).collect()
+}
+
+/// Runs the stats' gatherers concurrently on tokio's blocking pool, leaving out the ones still
+/// running after `modules.timeout_ms` as failures of their module. A gatherer can't be interrupted, so one that
+/// timed out keeps running in the background until it returns or vega exits, along with any program
+/// it started.
+#[cfg(feature = "async")]
+fn gather_values(stats: Vec<&'static StatDef>, samples: Samples) -> Vec<(&'static StatDef, Vec<StatValue>)> {
+    // Outside of vega's own runtime (e.g. called from a library user's thread) there's nothing to run on
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return stats.into_iter().map(|stat| (stat, (stat.gather)(&samples))).collect();
+    };
+    let timeout = Duration::from_millis(config().get_int("modules.timeout_ms", 5000).max(0) as u64);
+    let samples = Arc::new(samples);
+    let context = Context::current();
+
+    runtime.block_on(async {
+        // Each timeout starts counting now, not when the stats before it are done
+        let tasks: Vec<_> = stats.into_iter()
+            .map(|stat| {
+                let (samples, context) = (Arc::clone(&samples), context.clone());
+                (stat, tokio::time::timeout(timeout, tokio::task::spawn_blocking(move || context.enter(|| (stat.gather)(&samples)))))
+            })
+            .collect();
+
+        let mut values = Vec::new();
+        for (stat, task) in tasks {
+            values.push((stat, match task.await {
+                Ok(Ok(values)) => values,
+                // Crashes are the module's, as without the feature
+                Ok(Err(err)) if err.is_panic() => panic::resume_unwind(err.into_panic()),
+                Ok(Err(_)) => Vec::new(),
+                Err(_) => {
+                    fail(stat.module, &format!("{} timed out after {}ms", stat.id, timeout.as_millis()));
+                    Vec::new()
+                },
+            }));
+        }
+        values
+    })
+}
+
+fn has_gui_session() -> bool {
+    if provider().uname().sysname == "Darwin" {
+        return true;
+    }
+
+    let session = provider().env_var("XDG_SESSION_TYPE").unwrap_or_default();
+    session == "x11" || session == "wayland"
+        || provider().env_var("DISPLAY").is_some()
+        || provider().env_var("WAYLAND_DISPLAY").is_some()
+}
+
+fn one(value: impl Into<StatValue>) -> Vec<StatValue> {
+    vec![value.into()]
+}
+
+fn optional(value: Option<impl Into<StatValue>>) -> Vec<StatValue> {
+    value.into_iter().map(Into::into).collect()
+}
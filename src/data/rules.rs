@@ -0,0 +1,133 @@
+use crate::_utils::status::config_error;
+use crate::_utils::terminal::{strip_escapes, truncate_to_width};
+use crate::config::context::config;
+use crate::data::common::{Section, Stat};
+use regex::Regex;
+use toml::Value;
+
+// Per-stat rules from the `[stats.<id>]` config tables, applied to the gathered stats before they're
+// regrouped into sections and rendered
+
+/// Values that stand for "nothing to show", hidden by `hide_if_empty`
+const EMPTY_VALUES: [&str; 5] = ["", "none", "unknown", "no connection", "n/a"];
+
+/// Cleans up each stat's value with its `strip_prefix`, `replace` and `truncate` rules, then drops
+/// the stats whose `show_if` condition doesn't hold or that are empty under `hide_if_empty`
+pub fn apply_rules(sections: &mut [Section]) {
+    for section in sections {
+        section.stats.iter_mut().for_each(post_process);
+        section.stats.retain(is_shown);
+    }
+}
+
+/// Strips the first matching prefix, runs the regex replacements in order and truncates what's left,
+/// e.g. `replace = [["\\(R\\)|\\(TM\\)", ""]]` and `truncate = 30` for a long CPU model
+fn post_process(stat: &mut Stat) {
+    let config = config();
+    let rule = |key: &str| config.get(&format!("stats.{}.{}", stat.id, key));
+    let display = &mut stat.value.display;
+
+    // A single prefix or a list of them
+    let prefixes: Vec<&str> = match rule("strip_prefix") {
+        Some(Value::String(prefix)) => vec![prefix.as_str()],
+        Some(Value::Array(prefixes)) => prefixes.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if let Some(rest) = prefixes.iter().find_map(|prefix| display.strip_prefix(prefix)) {
+        *display = rest.trim_start().to_string();
+    }
+
+    for pair in rule("replace").and_then(Value::as_array).into_iter().flatten() {
+        let pair: Vec<&str> = pair.as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+        let [pattern, replacement] = pair[..] else {
+            config_error(&format!("ignoring stats.{}.replace entry, expected [\"pattern\", \"replacement\"]", stat.id));
+            continue;
+        };
+        match Regex::new(pattern) {
+            Ok(regex) => *display = regex.replace_all(display, replacement).trim().to_string(),
+            Err(err) => config_error(&format!("ignoring invalid stats.{}.replace pattern: {}", stat.id, err)),
+        }
+    }
+
+    if let Some(width) = rule("truncate").and_then(Value::as_integer) {
+        *display = truncate_to_width(display, width.max(1) as usize);
+    }
+}
+
+fn is_shown(stat: &Stat) -> bool {
+    let config = config();
+    let rule = |key: &str| config.get(&format!("stats.{}.{}", stat.id, key));
+    let value = strip_escapes(&stat.value.display);
+
+    if rule("hide_if_empty").and_then(|hide| hide.as_bool()).unwrap_or(false)
+        && EMPTY_VALUES.contains(&value.trim().to_lowercase().as_str()) {
+        return false;
+    }
+
+    match rule("show_if").and_then(|condition| condition.as_str()) {
+        Some(condition) => holds(condition, &value).unwrap_or_else(|| {
+            config_error(&format!("ignoring invalid stats.{}.show_if: {}", stat.id, condition));
+            true
+        }),
+        None => true,
+    }
+}
+
+/// Evaluates a condition on a stat's value: `value == 'text'`, `value != 'text'`,
+/// `value contains 'text'` or `value !contains 'text'`, or None if it can't be parsed
+fn holds(condition: &str, value: &str) -> Option<bool> {
+    let rest = condition.trim().strip_prefix("value")?.trim_start();
+    let (operator, literal) = ["==", "!=", "contains", "!contains"].into_iter()
+        .find_map(|operator| Some((operator, rest.strip_prefix(operator)?.trim())))?;
+
+    // Either quote works, TOML strings are easier to write with the other one inside
+    let literal = literal.strip_prefix('\'').and_then(|literal| literal.strip_suffix('\''))
+        .or_else(|| literal.strip_prefix('"').and_then(|literal| literal.strip_suffix('"')))?;
+
+    Some(match operator {
+        "==" => value == literal,
+        "!=" => value != literal,
+        "contains" => value.contains(literal),
+        _ => !value.contains(literal),
+    })
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_utils::system_provider::RealSystem;
+    use crate::args::args::Args;
+    use crate::config::config::Config;
+    use crate::config::context::Context;
+    use crate::data::common::StatValue;
+
+    fn processed(rules: &str, display: &str) -> String {
+        let config = Config::parse(rules).unwrap();
+        let context = Context::new(Args::default(), config, Box::leak(Box::new(RealSystem::new(true))));
+        let mut stat = Stat { id: "link".to_string(), label: "LNK".to_string(), value: StatValue::from(display.to_string()) };
+        context.enter(|| post_process(&mut stat));
+        stat.value.display
+    }
+
+    #[test]
+    fn truncates_around_links_and_images() {
+        let display = "\x1b]8;;https://example.com\x1b\\example.com\x1b]8;;\x1b\\ \x1b_Gq=2;AAAA\x1b\\ page";
+        assert_eq!(
+            processed("[stats.link]\ntruncate = 10", display),
+            "\x1b]8;;https://example.com\x1b\\example.c\x1b]8;;\x1b\\\x1b_Gq=2;AAAA\x1b\\…\x1b[0m",
+        );
+    }
+
+    #[test]
+    fn strips_prefixes_and_replaces_before_truncating() {
+        let rules = "[stats.link]\nstrip_prefix = [\"AMD\"]\nreplace = [[\"\\\\(R\\\\)\", \"\"]]\ntruncate = 8";
+        assert_eq!(processed(rules, "AMD Ryzen(R) 7 7800X3D"), "Ryzen 7…");
+    }
+
+    #[test]
+    fn evaluates_conditions() {
+        assert_eq!(holds("value == 'on'", "on"), Some(true));
+        assert_eq!(holds("value != \"on\"", "on"), Some(false));
+        assert_eq!(holds("value !contains 'vpn'", "wlan0"), Some(true));
+        assert_eq!(holds("value ~ 'on'", "on"), None);
+    }
+}
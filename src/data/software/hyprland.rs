@@ -0,0 +1,76 @@
+use crate::_utils::system_provider::provider;
+use serde_json::Value;
+
+/// Whether vega runs inside a Hyprland session, which sets its instance signature for hyprctl
+pub fn is_hyprland() -> bool {
+    provider().env_var("HYPRLAND_INSTANCE_SIGNATURE").is_some()
+}
+
+/// Parsed output of `hyprctl -j <command>`, e.g. "monitors" or "getoption general:gaps_in"
+fn hyprctl(command: &str) -> Option<Value> {
+    let args: Vec<String> = ["-j"].into_iter().chain(command.split_whitespace()).map(str::to_string).collect();
+    let output = provider().run("hyprctl", &args);
+    if output.err_code != 0 {
+        return None;
+    }
+
+    serde_json::from_str(&output.stdout).ok()
+}
+
+/// e.g. "Hyprland 0.45.2"
+pub fn get_hyprland_version() -> Option<String> {
+    let version = hyprctl("version")?;
+
+    // Older releases only have the git tag, e.g. "v0.41.2-b1"
+    let number = version.get("version").and_then(Value::as_str)
+        .or_else(|| version.get("tag").and_then(Value::as_str))?
+        .trim_start_matches('v');
+    let number = number.split('-').next().unwrap_or(number);
+
+    Some(format!("Hyprland {}", number))
+}
+
+/// Monitors from left to right, e.g. "DP-1 2560x1440@165Hz, HDMI-A-1 1920x1080@60Hz"
+pub fn get_hyprland_monitors() -> Option<String> {
+    let mut monitors: Vec<Value> = hyprctl("monitors")?.as_array()?.clone();
+    monitors.sort_by_key(|monitor| (
+        monitor.get("x").and_then(Value::as_i64).unwrap_or(0),
+        monitor.get("y").and_then(Value::as_i64).unwrap_or(0),
+    ));
+
+    let monitors: Vec<String> = monitors.iter()
+        .filter_map(|monitor| {
+            let name = monitor.get("name")?.as_str()?;
+            let width = monitor.get("width")?.as_u64()?;
+            let height = monitor.get("height")?.as_u64()?;
+            let refresh = monitor.get("refreshRate").and_then(Value::as_f64).unwrap_or(0.0);
+            Some(format!("{} {}x{}@{:.0}Hz", name, width, height, refresh))
+        })
+        .collect();
+
+    (!monitors.is_empty()).then(|| monitors.join(", "))
+}
+
+/// The number of workspaces and the focused one, e.g. "5 (on 2)"
+pub fn get_hyprland_workspaces() -> Option<String> {
+    let count = hyprctl("workspaces")?.as_array()?.len();
+    match hyprctl("activeworkspace").and_then(|active| active.get("name")?.as_str().map(str::to_string)) {
+        Some(active) => Some(format!("{} (on {})", count, active)),
+        None => Some(count.to_string()),
+    }
+}
+
+/// Gaps and border width, e.g. "5px inner, 10px outer, 2px border"
+pub fn get_hyprland_gaps() -> Option<String> {
+    // Gaps are CSS-style "top right bottom left" strings since 0.36, plain integers before that
+    let option = |name: &str| {
+        let option = hyprctl(&format!("getoption {}", name))?;
+        option.get("int").and_then(Value::as_i64)
+            .or_else(|| option.get("custom")?.as_str()?.split_whitespace().next()?.parse().ok())
+    };
+
+    Some(format!(
+        "{}px inner, {}px outer, {}px border",
+        option("general:gaps_in")?, option("general:gaps_out")?, option("general:border_size")?
+    ))
+}
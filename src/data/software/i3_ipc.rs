@@ -0,0 +1,127 @@
+use crate::_utils::system_provider::provider;
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+const GET_WORKSPACES: u32 = 1;
+const GET_OUTPUTS: u32 = 3;
+const GET_VERSION: u32 = 7;
+const GET_CONFIG: u32 = 9;
+
+/// The WM behind the i3 IPC socket, sway also sets $I3SOCK for compatibility so it's checked first
+fn ipc_socket() -> Option<(&'static str, String)> {
+    if let Some(socket) = provider().env_var("SWAYSOCK") {
+        return Some(("sway", socket));
+    }
+    provider().env_var("I3SOCK").map(|socket| ("i3", socket))
+}
+
+/// Sends an empty message of the given type and parses the JSON reply
+///
+/// Messages are "i3-ipc", then the payload length and message type as native-endian u32s, then
+/// the payload; replies have the same framing.
+fn ipc_request(socket: &str, message_type: u32) -> Option<Value> {
+    let mut stream = UnixStream::connect(socket).ok()?;
+    stream.set_read_timeout(Some(Duration::from_millis(500))).ok()?;
+
+    let mut request = b"i3-ipc".to_vec();
+    request.extend(0u32.to_ne_bytes());
+    request.extend(message_type.to_ne_bytes());
+    stream.write_all(&request).ok()?;
+
+    let mut header = [0u8; 14];
+    stream.read_exact(&mut header).ok()?;
+    if &header[..6] != b"i3-ipc" {
+        return None;
+    }
+    let length = u32::from_ne_bytes(header[6..10].try_into().ok()?) as usize;
+
+    let mut payload = vec![0u8; length];
+    stream.read_exact(&mut payload).ok()?;
+    serde_json::from_slice(&payload).ok()
+}
+
+/// Confirms the WM over its IPC socket, e.g. "sway 1.9" or "i3 4.23"
+pub fn get_i3_version() -> Option<String> {
+    let (name, socket) = ipc_socket()?;
+    let version = ipc_request(&socket, GET_VERSION)?;
+
+    let part = |key: &str| version.get(key).and_then(Value::as_u64);
+    match (part("major"), part("minor"), part("patch")) {
+        (Some(major), Some(minor), Some(0) | None) => Some(format!("{} {}.{}", name, major, minor)),
+        (Some(major), Some(minor), Some(patch)) => Some(format!("{} {}.{}.{}", name, major, minor, patch)),
+        _ => Some(name.to_string()),
+    }
+}
+
+/// Active outputs from left to right, e.g. "DP-1 2560x1440@165Hz, HDMI-A-1 1920x1080@60Hz"
+pub fn get_i3_outputs() -> Option<String> {
+    let (_, socket) = ipc_socket()?;
+    let mut outputs: Vec<Value> = ipc_request(&socket, GET_OUTPUTS)?.as_array()?.iter()
+        .filter(|output| output.get("active").and_then(Value::as_bool).unwrap_or(false))
+        .cloned()
+        .collect();
+    outputs.sort_by_key(|output| (
+        output.pointer("/rect/x").and_then(Value::as_i64).unwrap_or(0),
+        output.pointer("/rect/y").and_then(Value::as_i64).unwrap_or(0),
+    ));
+
+    let outputs: Vec<String> = outputs.iter()
+        .filter_map(|output| {
+            let name = output.get("name")?.as_str()?;
+
+            // Only sway reports the mode, i3 has the output's size in pixels
+            if let Some(mode) = output.get("current_mode") {
+                let width = mode.get("width")?.as_u64()?;
+                let height = mode.get("height")?.as_u64()?;
+                let refresh = mode.get("refresh").and_then(Value::as_u64).unwrap_or(0);
+                return Some(format!("{} {}x{}@{}Hz", name, width, height, (refresh + 500) / 1000));
+            }
+            let width = output.pointer("/rect/width")?.as_u64()?;
+            let height = output.pointer("/rect/height")?.as_u64()?;
+            Some(format!("{} {}x{}", name, width, height))
+        })
+        .collect();
+
+    (!outputs.is_empty()).then(|| outputs.join(", "))
+}
+
+/// The number of workspaces, the focused one and its output, e.g. "5 (on 2, DP-1)"
+pub fn get_i3_workspaces() -> Option<String> {
+    let (_, socket) = ipc_socket()?;
+    let workspaces = ipc_request(&socket, GET_WORKSPACES)?;
+    let workspaces = workspaces.as_array()?;
+
+    let focused = workspaces.iter()
+        .find(|workspace| workspace.get("focused").and_then(Value::as_bool).unwrap_or(false))
+        .and_then(|workspace| Some((workspace.get("name")?.as_str()?, workspace.get("output")?.as_str()?)));
+
+    match focused {
+        Some((name, output)) => Some(format!("{} (on {}, {})", workspaces.len(), name, output)),
+        None => Some(workspaces.len().to_string()),
+    }
+}
+
+/// Gaps and border width from the loaded config, e.g. "5px inner, 10px outer, 2px border"
+///
+/// Only the `gaps inner|outer <px>` and `default_border pixel|normal <px>` lines are read, anything
+/// not set keeps the default of no gaps and a 2px border.
+pub fn get_i3_gaps() -> Option<String> {
+    let (_, socket) = ipc_socket()?;
+    let config = ipc_request(&socket, GET_CONFIG)?;
+
+    let (mut inner, mut outer, mut border) = (0, 0, 2);
+    for line in config.get("config")?.as_str()?.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["gaps", "inner", px, ..] => inner = px.trim_end_matches("px").parse().unwrap_or(inner),
+            ["gaps", "outer", px, ..] => outer = px.trim_end_matches("px").parse().unwrap_or(outer),
+            ["default_border", "pixel" | "normal", px, ..] => border = px.trim_end_matches("px").parse().unwrap_or(border),
+            ["default_border", "none", ..] => border = 0,
+            _ => {},
+        }
+    }
+
+    Some(format!("{}px inner, {}px outer, {}px border", inner, outer, border))
+}
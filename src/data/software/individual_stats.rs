@@ -1,28 +1,180 @@
-use crate::_utils::run_command::ShellReturn;
-use crate::_utils::sort_by_priority::SortByPriority;
+use crate::_utils::anonymize::redact;
+use crate::_utils::procfs::stat_parent;
+use crate::_utils::read_file::cat;
+use crate::_utils::read_file::expand_home;
+use crate::_utils::sandbox::{os_release, sandbox, Sandbox};
+use crate::_utils::system_provider::provider;
+use crate::_utils::units::format_timestamp;
+use crate::_utils::which::which;
+use crate::config::context::{args, config};
+use crate::data::common::StatValue;
 use crate::sh;
-use std::cmp::Ordering;
-use std::net::IpAddr;
-use std::process::Command;
-use sysinfo::{NetworkData, Networks, System};
-use crate::_utils::which;
+use crate::data::software::hyprland::{get_hyprland_gaps, get_hyprland_monitors, get_hyprland_version, get_hyprland_workspaces, is_hyprland};
+use crate::data::software::i3_ipc::{get_i3_gaps, get_i3_outputs, get_i3_version, get_i3_workspaces};
+use crate::data::software::shell::{current_shell, is_shell};
+use crate::data::software::terminal::{terminal_from_term, terminal_name};
+use crate::data::software::uptime::{boot_time, uptime_record};
+use crate::data::software::window_manager::{get_bspwm_gaps, get_de_with_version, get_wayland_compositor, get_macos_wm, get_x11_wm, normalize_desktop};
 
 pub fn get_os() -> String {
-    let linux_os_ver = sh!("awk -F= '/^PRETTY_NAME=/ {{ gsub(/\"/, \"\", $2); print $2 }}' /etc/os-release");
-    
-    if linux_os_ver.err_code == 0 {
-        linux_os_ver.stdout.trim().to_string()
-    } else {
-        System::long_os_version().unwrap_or("Unknown OS".to_string())
+    let pretty_name = os_release().lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|name| name.trim().trim_matches('"').to_string());
+    let os = pretty_name.or_else(|| provider().os_version()).unwrap_or("Unknown OS".to_string());
+
+    match sandbox() {
+        Some(sandbox) => format!("{} (seen from {})", os, sandbox.name()),
+        None => os,
     }
 }
 
 pub fn get_kernel() -> String {
-    sh!("uname -sr").stdout.trim().to_string()
+    let uname = provider().uname();
+    format!("{} {}", uname.sysname, uname.release)
+}
+
+/// Kernel command line parameters worth knowing about (e.g. `mitigations=off`), leaving out the
+/// boot plumbing every system has
+pub fn get_cmdline() -> String {
+    const BORING_PARAMS: [&str; 16] = [
+        "BOOT_IMAGE", "initrd", "root", "rootflags", "rootfstype", "rootwait", "ro", "rw", "resume",
+        "resume_offset", "cryptdevice", "rd.luks.uuid", "rd.luks.name", "rd.lvm.lv", "zfs", "boot",
+    ];
+
+    let cmdline = cat("/proc/cmdline");
+    let params: Vec<&str> = cmdline.split_whitespace()
+        .filter(|param| !BORING_PARAMS.contains(&param.split('=').next().unwrap_or(param)))
+        .collect();
+
+    if params.is_empty() { "Default".to_string() } else { params.join(" ") }
+}
+
+/// Whether the kernel is tainted, with the decoded taint flags, e.g. "P O (proprietary module, out-of-tree module)"
+pub fn get_taint() -> String {
+    // Bit n of /proc/sys/kernel/tainted corresponds to the n-th flag letter the kernel prints in oopses
+    const TAINT_FLAGS: [(char, &str); 19] = [
+        ('P', "proprietary module"), ('F', "forced module load"), ('S', "unsafe SMP"),
+        ('R', "forced module unload"), ('M', "machine check"), ('B', "bad page"),
+        ('U', "user taint"), ('D', "kernel oops"), ('A', "ACPI table override"),
+        ('W', "kernel warning"), ('C', "staging driver"), ('I', "firmware workaround"),
+        ('O', "out-of-tree module"), ('E', "unsigned module"), ('L', "soft lockup"),
+        ('K', "live patched"), ('X', "auxiliary taint"), ('T', "struct randomization"),
+        ('N', "test module"),
+    ];
+
+    let Ok(tainted) = cat("/proc/sys/kernel/tainted").trim().parse::<u64>() else {
+        return "Unknown".to_string();
+    };
+    if tainted == 0 {
+        return "Not tainted".to_string();
+    }
+
+    let flags: Vec<&(char, &str)> = TAINT_FLAGS.iter()
+        .enumerate()
+        .filter(|(bit, _)| tainted & (1 << bit) != 0)
+        .map(|(_, flag)| flag)
+        .collect();
+
+    format!(
+        "{} ({})",
+        flags.iter().map(|(letter, _)| letter.to_string()).collect::<Vec<_>>().join(" "),
+        flags.iter().map(|(_, description)| *description).collect::<Vec<_>>().join(", "),
+    )
+}
+
+/// The machine's configured name rather than whatever DHCP or mDNS handed out, e.g. "archbox", or
+/// "archbox.lan" with `hostname.fqdn`
+pub fn get_hostname() -> String {
+    let nodename = provider().uname().nodename;
+    let fqdn = config().get_bool("hostname.fqdn", false);
+
+    let configured = if provider().uname().sysname == "Darwin" {
+        // ComputerName is the one set in System Settings, HostName is only there when set by hand
+        let key = if fqdn { "HostName" } else { "ComputerName" };
+        // Unset keys print "<key>: not set" and exit 1
+        let result = sh!("scutil", "--get", key);
+        (result.err_code == 0).then(|| result.stdout.trim().to_string())
+    } else {
+        // /etc/hostname holds the static hostname, on its first line that isn't a comment
+        provider().read_file("/etc/hostname").and_then(|content| content.lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string))
+    };
+    let hostname = configured.filter(|name| !name.is_empty()).unwrap_or(nodename);
+
+    if !fqdn {
+        return hostname.split('.').next().unwrap_or(&hostname).to_string();
+    }
+    if hostname.contains('.') {
+        return hostname;
+    }
+
+    // Only a resolver lookup knows the domain of a short static name. `-f` rather than GNU's
+    // `--fqdn`, which the BSD and macOS hostname don't take.
+    let result = sh!("hostname", "-f");
+    let resolved = result.stdout.trim().to_string();
+    if result.err_code == 0 && resolved.starts_with(&format!("{}.", hostname)) { resolved } else { hostname }
+}
+
+/// e.g. "alice (uid 1000, group users)", or "root (uid 0, group root), elevated via sudo by alice"
+/// in the warning color
+pub fn get_user() -> StatValue {
+    let (uid, gid) = provider().ids();
+
+    // "name:password:gid:members" lines, only local groups are known without asking NSS
+    let group = provider().read_file("/etc/group").and_then(|groups| groups.lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.get(2).is_some_and(|id| id.parse() == Ok(gid)))
+        .map(|fields| fields[0].to_string()))
+        .unwrap_or_else(|| gid.to_string());
+    let username = provider().username();
+
+    // A user's own group is named after them
+    let group = if group == username { redact(group, "user") } else { group };
+    let mut user = format!("{} (uid {}, group {})", redact(username, "user"), uid, group);
+    if uid == 0 {
+        user.push_str(", elevated");
+        let invoker = provider().env_var("SUDO_USER").map(|name| ("sudo", name))
+            .or_else(|| provider().env_var("DOAS_USER").map(|name| ("doas", name)));
+        if let Some((tool, name)) = invoker {
+            user.push_str(&format!(" via {} by {}", tool, redact(name, "user")));
+        }
+    }
+
+    StatValue::from(user).with("uid", uid as u64).with("gid", gid as u64).warning(uid == 0)
+}
+
+pub fn get_uptime() -> StatValue {
+    let uptime = provider().uptime();
+    StatValue::from(format_duration(uptime)).with("seconds", uptime)
 }
 
-pub fn get_uptime() -> String {
-    let uptime = System::uptime();
+/// e.g. "2024-05-01 09:31"
+pub fn get_boot_time() -> StatValue {
+    let boot = boot_time();
+    StatValue::from(format_timestamp(boot).unwrap_or_else(|| "Unknown".to_string())).with("timestamp", boot)
+}
+
+/// The longest uptime vega has seen, e.g. "41 days, 2 hours, 5 minutes (booted 2024-03-02)", or
+/// "current" instead of the date while this boot holds the record
+pub fn get_uptime_record() -> StatValue {
+    let (seconds, boot) = uptime_record();
+    // Boot times are worked out from the clock and the uptime, so the same boot can be a second off
+    let since = if boot.abs_diff(boot_time()) <= 2 {
+        "current".to_string()
+    } else {
+        let date = format_timestamp(boot).unwrap_or_default();
+        format!("booted {}", date.split(' ').next().unwrap_or_default())
+    };
+
+    StatValue::from(format!("{} ({})", format_duration(seconds), since))
+        .with("seconds", seconds)
+        .with("boot_timestamp", boot)
+}
+
+/// e.g. "3 days, 4 hours, 12 minutes" or "5 minutes, 2 seconds"
+fn format_duration(uptime: u64) -> String {
     let days = uptime / 86400;
     let hours = (uptime % 86400) / 3600;
     let minutes = (uptime % 3600) / 60;
@@ -50,123 +202,647 @@ pub fn get_packages() -> String {
     let script = include_str!("../../../static/sh/packages.sh");
     let mac_script = include_str!("../../../static/sh/packages_macos.sh");
     
-    if sh!("uname").stdout.trim() == "Darwin" {
-        sh!("{}", mac_script).stdout.trim().to_string()
+    if provider().uname().sysname.as_str() == "Darwin" {
+        provider().run_script("packages_macos.sh", mac_script).stdout.trim().to_string()
+    } else if let Some(sandbox) = sandbox() {
+        // The sandbox's own package managers would count its runtime, so ask the host's when
+        // Flatpak allows it and read the host's databases where they show through otherwise
+        let host = match sandbox {
+            Sandbox::Flatpak if !args().fast => sh!("flatpak-spawn", "--host", "sh", "-c", script).stdout.trim().to_string(),
+            _ => String::new(),
+        };
+        if host.is_empty() { get_packages_native(sandbox.host_root()) } else { host }
+    } else if args().fast {
+        get_packages_native("")
     } else {
-        sh!("{}", script).stdout.trim().to_string()
+        provider().run_script("packages.sh", script).stdout.trim().to_string()
+    }
+}
+
+/// Package counts read straight from the common package databases under `root`, for `--fast`
+/// and sandboxes where the package managers themselves can't be asked
+fn get_packages_native(root: &str) -> String {
+    let home = provider().env_var("HOME").unwrap_or_default();
+    let path = |path: &str| format!("{}{}", root, path);
+    let counts = [
+        // A directory per package, next to an ALPM_DB_VERSION file
+        ("pacman", provider().list_dir(&path("/var/lib/pacman/local")).iter().filter(|entry| *entry != "ALPM_DB_VERSION").count()),
+        ("dpkg", cat(&path("/var/lib/dpkg/status")).lines().filter(|line| *line == "Status: install ok installed").count()),
+        ("apk", cat(&path("/lib/apk/db/installed")).lines().filter(|line| line.starts_with("P:")).count()),
+        // /var/db/pkg/<category>/<package>
+        ("portage", provider().list_dir(&path("/var/db/pkg")).iter().map(|category| provider().list_dir(&path(&format!("/var/db/pkg/{}", category))).len()).sum()),
+        ("flatpak", provider().list_dir(&path("/var/lib/flatpak/app")).len() + provider().list_dir(&format!("{}/.local/share/flatpak/app", home)).len()),
+        ("snap", provider().list_dir(&path("/snap")).iter().filter(|entry| !matches!(entry.as_str(), "bin" | "README")).count()),
+    ];
+
+    counts.iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(manager, count)| format!("{} ({})", count, manager))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Nix version, e.g. "Nix 2.24.10", NixOS's generation is part of the system image instead
+pub fn get_nix() -> Option<String> {
+    // "nix (Nix) 2.24.10"
+    let version = sh!("nix", "--version").stdout.split_whitespace().last()?.to_string();
+    Some(format!("Nix {}", version))
+}
+
+/// Guix version and, on Guix System, the current system generation, e.g. "Guix 1.4.0 (generation 57)"
+pub fn get_guix() -> Option<String> {
+    // "guix (GNU Guix) 1.4.0" on the first line, followed by the copyright notice
+    let version = sh!("guix", "--version").stdout.lines().next()?.split_whitespace().last()?.to_string();
+
+    match get_system_generation("/var/guix/profiles/system") {
+        Some(generation) => Some(format!("Guix {} (generation {})", version, generation)),
+        None => Some(format!("Guix {}", version)),
+    }
+}
+
+/// Both Nix and Guix point the system profile at a "system-<generation>-link" symlink
+fn get_system_generation(profile: &str) -> Option<u32> {
+    provider().read_link(profile)?
+        .rsplit('/')
+        .next()?
+        .strip_prefix("system-")?
+        .strip_suffix("-link")?
+        .parse()
+        .ok()
+}
+
+/// Deployed image on immutable distros, with a note when a new deployment is waiting for a reboot,
+/// e.g. "40.20240501.0 (3f2a9c1b7e), update pending"
+pub fn get_image() -> Option<String> {
+    get_ostree_image().or_else(get_microos_image).or_else(get_steamos_image).or_else(get_nixos_image)
+}
+
+/// NixOS, identified by its version (which ends in the nixpkgs revision), the system generation and
+/// the flake's revision when built from one, e.g. "24.05.20240501.3f2a9c1 (generation 412, config 8a1b2c3d4e)"
+fn get_nixos_image() -> Option<String> {
+    // {"nixosVersion": "24.05.20240501.3f2a9c1", "nixpkgsRevision": "...", "configurationRevision": "..."}
+    let output = sh!("nixos-version", "--json");
+    let json: Option<serde_json::Value> = serde_json::from_str(&output.stdout).ok();
+    let field = |name: &str| json.as_ref()?.get(name)?.as_str().map(str::to_string);
+
+    // The current system also carries its version, for --fast
+    let version = field("nixosVersion")
+        .or_else(|| provider().read_file("/run/current-system/nixos-version").map(|version| version.trim().to_string()))
+        .filter(|version| !version.is_empty())?;
+
+    let mut details = Vec::new();
+    if let Some(generation) = get_system_generation("/nix/var/nix/profiles/system") {
+        details.push(format!("generation {}", generation));
+    }
+    // A dirty tree's revision ends in "-dirty", which is worth keeping
+    if let Some(revision) = field("configurationRevision") {
+        let (hash, dirty) = revision.split_once("-dirty").map_or((revision.as_str(), false), |(hash, _)| (hash, true));
+        details.push(format!("config {}{}", &hash[..hash.len().min(10)], if dirty { ", dirty" } else { "" }));
+    }
+
+    let mut image = if details.is_empty() { version } else { format!("{} ({})", version, details.join(", ")) };
+
+    // A rebuild switched to a new system that still runs the old kernel until the next boot
+    let current = provider().read_link("/run/current-system");
+    let booted = provider().read_link("/run/booted-system");
+    if current.is_some() && booted.is_some() && current != booted {
+        image.push_str(", reboot pending");
+    }
+
+    Some(image)
+}
+
+/// Fedora Atomic (Silverblue, Kinoite, ...) and other rpm-ostree systems
+fn get_ostree_image() -> Option<String> {
+    let status = sh!("rpm-ostree", "status", "--json");
+    let json: serde_json::Value = serde_json::from_str(&status.stdout).ok()?;
+    let deployments = json.get("deployments")?.as_array()?;
+
+    let booted = deployments.iter().find(|deployment| deployment["booted"].as_bool() == Some(true))?;
+    let checksum = booted["checksum"].as_str()?;
+    let mut image = match booted["version"].as_str() {
+        Some(version) => format!("{} ({})", version, &checksum[..checksum.len().min(10)]),
+        None => checksum[..checksum.len().min(10)].to_string(),
+    };
+
+    // Layered packages are the ones a package count would actually be interesting for
+    let layered = booted["requested-packages"].as_array().map_or(0, Vec::len);
+    if layered > 0 {
+        image.push_str(&format!(", {} layered", layered));
+    }
+    // Deployments are sorted by boot order, anything ahead of the booted one is applied on reboot
+    if deployments.first().is_some_and(|deployment| deployment["booted"].as_bool() != Some(true)) {
+        image.push_str(", update pending");
+    }
+
+    Some(image)
+}
+
+/// openSUSE MicroOS / Aeon, which boot a read-only btrfs snapshot managed by transactional-update
+fn get_microos_image() -> Option<String> {
+    which("transactional-update")?;
+
+    // "rootflags=subvol=@/.snapshots/42/snapshot"
+    let snapshot_number = |path: &str| path.split(".snapshots/").nth(1)?.split('/').next().map(str::to_string);
+    let booted = cat("/proc/cmdline").split_whitespace()
+        .find_map(|param| param.strip_prefix("rootflags=subvol="))
+        .and_then(snapshot_number)?;
+
+    // A finished transactional-update makes its new snapshot the default subvolume for the next boot
+    let default = sh!("btrfs", "subvolume", "get-default", "/");
+    match snapshot_number(default.stdout.trim()) {
+        Some(next) if next != booted => Some(format!("snapshot {}, update pending", booted)),
+        _ => Some(format!("snapshot {}", booted)),
+    }
+}
+
+/// SteamOS 3, which ships as A/B images identified by their build
+fn get_steamos_image() -> Option<String> {
+    let os_release = os_release();
+    let field = |name: &str| os_release.lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+        .map(|value| value.trim_matches('"').to_string());
+
+    if field("ID")? != "steamos" {
+        return None;
+    }
+
+    match (field("VERSION_ID"), field("BUILD_ID")) {
+        (Some(version), Some(build)) => Some(format!("{} (build {})", version, build)),
+        (None, Some(build)) => Some(format!("build {}", build)),
+        (version, None) => version,
     }
 }
 
 pub fn get_window_manager() -> String {
 
-    // macOS Hardcode
-    if sh!("uname").stdout.trim() == "Darwin" {
-        const SUPPORTED_WMS: [&str; 2] = ["yabai", "Amethyst"];
+    if provider().uname().sysname.as_str() == "Darwin" {
+        return get_macos_wm();
+    }
 
-        for wm in SUPPORTED_WMS {
-            if sh!("pgrep -x {}", wm).err_code == 0 {
-                return wm.to_string();
-            }
-        }
+    // Hyprland tells its own version over hyprctl
+    if is_hyprland() && let Some(version) = get_hyprland_version() {
+        return version;
+    }
+
+    // i3 and sway confirm themselves over their IPC socket
+    if let Some(version) = get_i3_version() {
+        return version;
+    }
 
-        return "aqua".to_string();
+    // Ask the X server directly on X11 sessions, this also covers bare WMs (i3, bspwm, dwm, ...)
+    // which usually don't set $XDG_CURRENT_DESKTOP
+    let session = provider().env_var("XDG_SESSION_TYPE").unwrap_or_default();
+    let is_x11 = session == "x11" || (session.is_empty() && provider().env_var("DISPLAY").is_some() && provider().env_var("WAYLAND_DISPLAY").is_none());
+    if is_x11 && let Some(wm) = get_x11_wm() {
+        return wm;
     }
 
     // Read $XDG_CURRENT_DESKTOP for Wayland and X11
-    let desktop = sh!(": \"${{XDG_CURRENT_DESKTOP:?}}\" && echo \"$XDG_CURRENT_DESKTOP\"");
-    if desktop.err_code == 0 && desktop.stdout.trim() != "" {
-        return desktop.stdout.trim().to_string();
-    }
-
-    // Fallback PID method for Wayland only
-    let wmpid: ShellReturn = if let Some(_) = which::which("fuser") {
-        let pid_raw = sh!("fuser \"${{XDG_RUNTIME_DIR}}/${{WAYLAND_DISPLAY:-wayland-0}}\"");
-        if pid_raw.err_code == 0 {
-            sh!("echo {} | awk '{{print $1}}'", pid_raw.stdout.trim())
-        } else { 
-            pid_raw
+    let desktop = provider().env_var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+    if let Some(desktop) = normalize_desktop(&desktop) {
+        return get_de_with_version(&desktop).unwrap_or(desktop);
+    }
+
+    // Fallback to asking the compositor over the Wayland socket
+    if let Some(compositor) = get_wayland_compositor() {
+        return compositor;
+    }
+
+    "None/Unknown".to_string()
+}
+
+/// The window manager's monitors from left to right, where it can tell them
+pub fn get_wm_monitors() -> Option<String> {
+    if is_hyprland() {
+        return get_hyprland_monitors();
+    }
+
+    get_i3_outputs()
+}
+
+/// The window manager's workspace count and focused workspace, where it can tell them
+pub fn get_wm_workspaces() -> Option<String> {
+    if is_hyprland() {
+        return get_hyprland_workspaces();
+    }
+
+    get_i3_workspaces()
+}
+
+/// Gap and border sizes of the tiling window managers that can tell them
+pub fn get_wm_gaps() -> Option<String> {
+    if is_hyprland() {
+        return get_hyprland_gaps();
+    }
+
+    get_i3_gaps().or_else(get_bspwm_gaps)
+}
+
+/// The terminal emulator among vega's ancestors, found by process or executable name (comm can be
+/// anything, e.g. "python3" for terminator), skipping shells and whatever else runs in between
+///
+/// Without a known one, `$TERM_PROGRAM`, the terminal `$TERM` names (e.g. "xterm-kitty", which
+/// survives SSH) or the closest ancestor that isn't a shell (e.g. "sshd") is shown instead.
+pub fn get_terminal() -> String {
+    if let Some(console) = get_console() {
+        return console;
+    }
+
+    let mut pid = provider().parent_pid();
+    let mut closest = None;
+
+    // Bounded in case /proc changes under the walk
+    for _ in 0..64 {
+        if pid <= 1 {
+            break;
         }
-    } else if let Some(_) = which::which("lsof") {
-        sh!("lsof -t \"${{XDG_RUNTIME_DIR}}/${{WAYLAND_DISPLAY:-wayland-0}}\" 2>&1")
-    } else {
-        ShellReturn {
-            stdout: "".to_string(),
-            stderr: "".to_string(),
-            err_code: 1,
+
+        let name = get_process_name(pid);
+        let exe = provider().read_link(&format!("/proc/{}/exe", pid));
+        if let Some(terminal) = exe.as_deref().and_then(terminal_name).or_else(|| terminal_name(&name)) {
+            return terminal.to_string();
+        }
+        if closest.is_none() && !name.is_empty() && !is_shell(&name) {
+            closest = Some(name);
         }
-    };
 
-    if wmpid.err_code == 0 {
-        return sh!("ps -p {} -o comm=", wmpid.stdout.trim()).stdout.trim().to_string();
+        pid = get_process_parent(pid);
     }
 
-    "None/Unknown".to_string()
+    provider().env_var("TERM_PROGRAM")
+        .or_else(|| provider().env_var("TERM").as_deref().and_then(terminal_from_term).map(str::to_string))
+        .or(closest)
+        .unwrap_or("Unknown".to_string())
 }
 
-pub fn get_terminal() ->  String {
-    let mut pid = unsafe { libc::getppid() };
-    let mut pname = sh!("ps -p {} -o comm=", pid).stdout.trim().to_string();
-    
-    while pname.ends_with("sh") {
-        pid = sh!("ps -p {} -o ppid=", pid).stdout.trim().parse::<i32>().unwrap_or(1);
-        pname = sh!("ps -p {} -o comm=", pid).stdout.trim().to_string();
+/// The virtual console vega runs on, e.g. "TTY3", from the shell's stdin: /dev/tty3 on Linux, or
+/// ttyv2 on FreeBSD (counted from 1 to match). None in terminal emulators, over SSH and on serial
+/// consoles.
+pub fn get_console() -> Option<String> {
+    let pid = provider().parent_pid();
+    let tty = provider().read_link(&format!("/proc/{}/fd/0", pid))
+        .unwrap_or_else(|| sh!("ps", "-p", pid, "-o", "tty=").stdout.trim().to_string());
+
+    // ps leaves out /dev/ and, on FreeBSD, "tty" as well
+    let tty = tty.trim_start_matches("/dev/");
+    let tty = tty.strip_prefix("tty").unwrap_or(tty);
+    match tty.strip_prefix('v') {
+        Some(number) => number.parse::<u32>().ok().map(|number| format!("TTY{}", number + 1)),
+        None => tty.parse::<u32>().ok().filter(|number| *number > 0).map(|number| format!("TTY{}", number)),
     }
-    
-    pname
 }
 
+/// The shell vega was started from, or `$SHELL` and then the passwd entry's when its parent isn't one
 pub fn get_shell() -> String {
-    let ppid = unsafe { libc::getppid() };
-    sh!("ps -p {} -o comm=", ppid).stdout.trim().to_string()
+    current_shell(&get_process_name(provider().parent_pid()))
+}
+
+/// A process's command name, from /proc where there is one and `ps` elsewhere
+fn get_process_name(pid: i32) -> String {
+    let comm = cat(&format!("/proc/{}/comm", pid));
+    if !comm.is_empty() {
+        return comm.trim().to_string();
+    }
+
+    sh!("ps", "-p", pid, "-o", "comm=").stdout.trim().to_string()
+}
+
+/// The PID of a process's parent, or 1 if it can't be found
+fn get_process_parent(pid: i32) -> i32 {
+    if let Some(ppid) = stat_parent(&cat(&format!("/proc/{}/stat", pid))) {
+        return ppid;
+    }
+
+    sh!("ps", "-p", pid, "-o", "ppid=").stdout.trim().parse::<i32>().unwrap_or(1)
+}
+
+/// Active keyboard layout, e.g. "us (colemak)"
+pub fn get_keyboard_layout() -> String {
+
+    // macOS: the selected input source, e.g. "KeyboardLayout Name" = "U.S.";
+    if provider().uname().sysname.as_str() == "Darwin" {
+        let sources = sh!("defaults", "read", expand_home("~/Library/Preferences/com.apple.HIToolbox.plist").to_string_lossy(), "AppleSelectedInputSources");
+        return sources.stdout.lines()
+            .find_map(|line| line.trim().strip_prefix("\"KeyboardLayout Name\" = "))
+            .map(|name| name.trim_end_matches(';').trim_matches('"').to_string())
+            .unwrap_or("Unknown".to_string());
+    }
+
+    // The session's XKB configuration, through the X server or the desktop's own settings on Wayland
+    let xkb = sh!("setxkbmap", "-query").stdout;
+    let xkb_field = |name: &str| xkb.lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+        .map(|value| value.trim().to_string());
+    if let Some(layout) = xkb_field("layout") {
+        return format_keyboard_layout(&layout, xkb_field("variant").as_deref());
+    }
+
+    // GNOME stores "[('xkb', 'us+colemak'), ('xkb', 'de')]", the first source is the active one
+    let gnome = sh!("gsettings", "get", "org.gnome.desktop.input-sources", "sources");
+    if let Some(source) = gnome.stdout.split("('xkb', '").nth(1).and_then(|rest| rest.split('\'').next()) {
+        let (layout, variant) = source.split_once('+').map_or((source, None), |(layout, variant)| (layout, Some(variant)));
+        return format_keyboard_layout(layout, variant);
+    }
+
+    // System-wide default as a last resort
+    let localectl = sh!("localectl", "status").stdout;
+    let localectl_field = |name: &str| localectl.lines()
+        .find_map(|line| line.trim().strip_prefix(name)?.strip_prefix(':'))
+        .map(|value| value.trim().to_string());
+    if let Some(layout) = localectl_field("X11 Layout") {
+        return format_keyboard_layout(&layout, localectl_field("X11 Variant").as_deref());
+    }
+    if let Some(keymap) = localectl_field("VC Keymap").filter(|keymap| keymap != "n/a") {
+        return keymap;
+    }
+
+    // The files localectl reads, for when it isn't installed (or with --fast)
+    let default_keyboard = cat("/etc/default/keyboard");
+    let default_field = |name: &str| default_keyboard.lines()
+        .find_map(|line| line.trim().strip_prefix(name)?.strip_prefix('='))
+        .map(|value| value.trim().trim_matches('"').to_string())
+        .filter(|value| !value.is_empty());
+    if let Some(layout) = default_field("XKBLAYOUT") {
+        return format_keyboard_layout(&layout, default_field("XKBVARIANT").as_deref());
+    }
+
+    // Option "XkbLayout" "us"
+    let xorg_keyboard = cat("/etc/X11/xorg.conf.d/00-keyboard.conf");
+    let xorg_option = |name: &str| xorg_keyboard.lines()
+        .find_map(|line| line.trim().strip_prefix("Option")?.trim().strip_prefix(&format!("\"{}\"", name)))
+        .map(|value| value.trim().trim_matches('"').to_string());
+    if let Some(layout) = xorg_option("XkbLayout") {
+        return format_keyboard_layout(&layout, xorg_option("XkbVariant").as_deref());
+    }
+
+    if let Some(keymap) = cat("/etc/vconsole.conf").lines().find_map(|line| line.trim().strip_prefix("KEYMAP=")) {
+        return keymap.trim_matches('"').to_string();
+    }
+
+    "Unknown".to_string()
+}
+
+fn format_keyboard_layout(layout: &str, variant: Option<&str>) -> String {
+    match variant.filter(|variant| !variant.is_empty()) {
+        Some(variant) => format!("{} ({})", layout, variant),
+        None => layout.to_string(),
+    }
+}
+
+/// The user's preferred editor from $VISUAL or $EDITOR, without its path or arguments
+pub fn get_editor() -> String {
+    ["VISUAL", "EDITOR"].iter()
+        .filter_map(|var| provider().env_var(var))
+        .find(|editor| !editor.trim().is_empty())
+        .and_then(|editor| {
+            let program = editor.split_whitespace().next()?.to_string();
+            Some(program.rsplit('/').next().unwrap_or(&program).to_string())
+        })
+        .unwrap_or("None".to_string())
+}
+
+/// Shell prompt frameworks in use, e.g. "oh-my-zsh + powerlevel10k"
+pub fn get_prompt_framework() -> String {
+    // (name, exported variable it sets, marker in the shell's rc file)
+    const FRAMEWORKS: [(&str, &str, &str); 5] = [
+        ("oh-my-zsh", "ZSH", "oh-my-zsh.sh"),
+        ("oh-my-bash", "OSH", "oh-my-bash.sh"),
+        ("powerlevel10k", "POWERLEVEL9K_MODE", "powerlevel10k"),
+        ("starship", "STARSHIP_SHELL", "starship init"),
+        ("oh-my-posh", "POSH_THEME", "oh-my-posh init"),
+    ];
+
+    let home = provider().env_var("HOME").unwrap_or_default();
+    let rc_files: String = [".zshrc", ".bashrc", ".config/fish/config.fish"].iter()
+        .map(|rc| cat(&format!("{}/{}", home, rc)))
+        .collect();
+
+    let frameworks: Vec<&str> = FRAMEWORKS.iter()
+        .filter(|(_, var, marker)| provider().env_var(var).is_some() || rc_files.contains(marker))
+        .map(|(name, _, _)| *name)
+        .collect();
+
+    if frameworks.is_empty() { "None".to_string() } else { frameworks.join(" + ") }
 }
 
-pub fn get_ip_addr() -> String {
-    // Extract IP address from `NetworkData` (prioritizing IPv4 over IPv6)
-    let extract_ip: fn(&NetworkData) -> Option<String> = |network: &NetworkData| {
-        let mut addrs: Vec<IpAddr> = network.ip_networks().iter()
-            .map(|ip| ip.addr).collect();
+/// Path of the current desktop wallpaper, from the desktop's settings or the wallpaper setter's
+/// own command line
+pub fn get_wallpaper() -> Option<String> {
+
+    // macOS: the main display's desktop picture
+    if provider().uname().sysname.as_str() == "Darwin" {
+        let picture = sh!("osascript", "-e", "tell application \"System Events\" to get picture of current desktop");
+        return Some(picture.stdout.trim().to_string()).filter(|path| !path.is_empty());
+    }
 
-        addrs.sort_by(|a, b|
-            if a.is_ipv4() && b.is_ipv6() { Ordering::Less }
-            else if b.is_ipv6() && a.is_ipv4() { Ordering::Greater }
-            else { Ordering::Equal }
-        );
+    let desktop = provider().env_var("XDG_CURRENT_DESKTOP").unwrap_or_default().to_lowercase();
+
+    // GNOME keeps a separate picture for dark mode
+    if desktop.contains("gnome") {
+        let dark = sh!("gsettings", "get", "org.gnome.desktop.interface", "color-scheme").stdout.contains("prefer-dark");
+        let key = if dark { "picture-uri-dark" } else { "picture-uri" };
+        let uri = sh!("gsettings", "get", "org.gnome.desktop.background", key).stdout;
+        if let Some(path) = parse_file_uri(uri.trim().trim_matches('\'')) {
+            return Some(path);
+        }
+    }
 
-        if addrs.len() == 0 { None } else { Some(addrs[0].to_string()) }
+    // Plasma stores it per containment as "Image=file:///..." under [Wallpaper][org.kde.image][General]
+    if desktop.contains("kde") {
+        let applets = cat(&format!("{}/.config/plasma-org.kde.plasma.desktop-appletsrc", provider().env_var("HOME").unwrap_or_default()));
+        if let Some(path) = applets.lines().find_map(|line| parse_file_uri(line.strip_prefix("Image=")?)) {
+            return Some(path);
+        }
+    }
+
+    // swaybg, e.g. "swaybg -o * -i /path/to/image -m fill"
+    let swaybg = sh!("ps", "-C", "swaybg", "-o", "args=").stdout;
+    let mut args = swaybg.split_whitespace();
+    if args.by_ref().any(|arg| arg == "-i" || arg == "--image") && let Some(path) = args.next() {
+        return Some(path.to_string());
+    }
+
+    // feh saves the command that set the wallpaper, e.g. "feh --no-fehbg --bg-fill '/path/to/image'"
+    let fehbg = cat(&format!("{}/.fehbg", provider().env_var("HOME").unwrap_or_default()));
+    fehbg.lines()
+        .filter(|line| line.trim_start().starts_with("feh"))
+        .find_map(|line| line.split('\'').nth(1).map(str::to_string))
+}
+
+/// Local path of a "file://" URI (or an already plain path), with percent-escapes decoded
+fn parse_file_uri(uri: &str) -> Option<String> {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    if !path.starts_with('/') {
+        return None;
+    }
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes.get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+
+        match escaped {
+            Some(escaped) => {
+                decoded.push(escaped);
+                i += 3;
+            },
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            },
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+/// The wallpaper's file name, with its path for the thumbnail the text output draws after it
+pub fn format_wallpaper(path: Option<String>) -> StatValue {
+    let Some(path) = path else {
+        return StatValue::from("Unknown".to_string());
     };
 
-    // Get a list of network interfaces and sort them
-    let networks = Networks::new_with_refreshed_list();
-    let mut networks_sorted: Vec<(&String, &NetworkData)> = networks.into_iter().collect();
-
-    // Sort the interfaces by priority
-    networks_sorted.sort_by_priority(|network| {
-        let nw_name = network.0.to_lowercase();
-
-        // Prioritize physical interfaces: Ethernet, Wifi, WWAN
-        if nw_name.starts_with("en") { 0 }
-        else if nw_name.starts_with("wl") { 1 }
-        else if nw_name.starts_with("wwan") { 2 }
-        // Deprioritize VPN interfaces
-        else if nw_name.starts_with("tailscale") { u32::MAX - 1 }
-        else if nw_name.starts_with("tun") { 1000 }
-        else if nw_name.starts_with("tap") { 1000 }
-        else if nw_name.starts_with("wg") { 1000 }
-        else if nw_name.starts_with("vpn") { 1000 }
-        // Also deprioritize NetworkManager stuff a bit more
-        else if nw_name.starts_with("nm") { 1001 }
-        // Make sure loopback is last
-        else if nw_name == "lo" { u32::MAX }
-        // Default priority for other interfaces (brX, hostX, etc.)
-        else { 69 }
-    });
-
-    // Return the first non-loopback interface with an IP address
-    for network in networks_sorted {
-        if let Some(ip) = extract_ip(network.1) {
-            return ip;
+    // File names can be personal (a photo's, a user's), the extension is kept under `--anonymize`
+    let name = path.rsplit('/').next().unwrap_or(&path).to_string();
+    let extension = name.rsplit_once('.').map(|(_, extension)| format!(".{}", extension)).unwrap_or_default();
+    StatValue::from(redact(name, &format!("wallpaper{}", extension))).image(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_utils::fixture::with_fixture;
+    use serde_json::json;
+
+    #[test]
+    fn reads_the_os_from_os_release() {
+        let fixture = json!({ "files": { "/etc/os-release": "NAME=\"Arch Linux\"\nPRETTY_NAME=\"Arch Linux\"\nID=arch\n" } });
+        assert_eq!(with_fixture(fixture, get_os), "Arch Linux");
+        assert_eq!(with_fixture(json!({ "os_version": "macOS 15.1 Sequoia" }), get_os), "macOS 15.1 Sequoia");
+        assert_eq!(with_fixture(json!({}), get_os), "Unknown OS");
+    }
+
+    #[test]
+    fn reads_the_hosts_os_from_a_sandbox() {
+        let fixture = json!({
+            "files": {
+                "/.flatpak-info": "[Application]\n",
+                "/etc/os-release": "PRETTY_NAME=\"GNOME 47 (Flatpak runtime)\"\n",
+                "/run/host/etc/os-release": "PRETTY_NAME=\"Fedora Linux 41 (Workstation Edition)\"\n",
+            },
+        });
+        assert_eq!(with_fixture(fixture, get_os), "Fedora Linux 41 (Workstation Edition) (seen from Flatpak)");
+    }
+
+    #[test]
+    fn reads_the_kernel_from_uname() {
+        let fixture = json!({ "uname": { "sysname": "Linux", "nodename": "archbox", "release": "6.11.5-arch1-1" } });
+        assert_eq!(with_fixture(fixture, get_kernel), "Linux 6.11.5-arch1-1");
+    }
+
+    #[test]
+    fn formats_the_uptime() {
+        let uptime = with_fixture(json!({ "uptime": 93784 }), get_uptime);
+        assert_eq!(uptime.display, "1 day, 2 hours, 3 minutes");
+        assert_eq!(uptime.values, vec![("seconds", 93784)]);
+        assert_eq!(with_fixture(json!({ "uptime": 61 }), get_uptime).display, "1 minute, 1 second");
+    }
+
+    #[test]
+    fn counts_packages_with_the_script() {
+        let fixture = json!({ "scripts": { "packages.sh": "1204 (pacman), 12 (flatpak)\n" } });
+        assert_eq!(with_fixture(fixture, get_packages), "1204 (pacman), 12 (flatpak)");
+    }
+
+    #[test]
+    fn counts_packages_from_their_databases() {
+        let fixture = json!({
+            "dirs": {
+                "/var/lib/pacman/local": ["ALPM_DB_VERSION", "bash-5.2.037-1", "linux-6.11.5.arch1-1"],
+                "/home/alice/.local/share/flatpak/app": ["org.mozilla.firefox"],
+            },
+            "files": { "/var/lib/dpkg/status": "Package: a\nStatus: install ok installed\n\nPackage: b\nStatus: deinstall ok config-files\n" },
+            "env": { "HOME": "/home/alice" },
+        });
+        assert_eq!(with_fixture(fixture, || get_packages_native("")), "2 (pacman), 1 (dpkg), 1 (flatpak)");
+    }
+
+    #[test]
+    fn finds_the_terminal_and_shell_among_the_ancestors() {
+        let fixture = json!({
+            "files": {
+                "/proc/4242/comm": "zsh\n",
+                "/proc/4242/stat": "4242 (zsh) S 4200 4242 4242 34816 4300 4194304\n",
+                "/proc/4200/comm": "kitty\n",
+            },
+            "links": { "/proc/4200/exe": "/usr/bin/kitty" },
+            "parent_pid": 4242,
+        });
+        with_fixture(fixture, || {
+            assert_eq!(get_terminal(), "kitty");
+            assert_eq!(get_shell(), "zsh");
+        });
+    }
+
+    #[test]
+    fn falls_back_from_the_ancestors_to_the_environment() {
+        let ancestors = |parent: &str| json!({
+            "/proc/4242/comm": "zsh\n",
+            "/proc/4242/stat": "4242 (zsh) S 4200 4242 4242 34816 4300 4194304\n",
+            "/proc/4200/comm": format!("{}\n", parent),
+            "/proc/4200/stat": format!("4200 ({}) S 1 4200 4200 0 -1 4194304\n", parent),
+        });
+        let cases = [
+            // Known by comm alone, without an exe link
+            (ancestors("alacritty"), json!({ "TERM_PROGRAM": "vscode" }), "alacritty"),
+            (ancestors("sshd"), json!({ "TERM_PROGRAM": "vscode", "TERM": "xterm-kitty" }), "vscode"),
+            (ancestors("sshd"), json!({ "TERM": "xterm-kitty" }), "kitty"),
+            (ancestors("sshd"), json!({ "TERM": "xterm-256color" }), "sshd"),
+            (ancestors("zsh"), json!({}), "Unknown"),
+        ];
+        for (files, env, expected) in cases {
+            let fixture = json!({ "files": files, "env": env, "parent_pid": 4242 });
+            assert_eq!(with_fixture(fixture, get_terminal), expected);
         }
     }
-    
-    "No Connection".to_string()
-}
\ No newline at end of file
+
+    #[test]
+    fn finds_the_console() {
+        let fixture = json!({ "links": { "/proc/4242/fd/0": "/dev/tty3" }, "parent_pid": 4242 });
+        assert_eq!(with_fixture(fixture, get_terminal), "TTY3");
+    }
+
+    #[test]
+    fn asks_hyprland_for_its_version() {
+        let fixture = json!({
+            "commands": { "hyprctl -j version": "{\"version\": \"0.45.2\", \"tag\": \"v0.45.2\"}" },
+            "env": { "XDG_SESSION_TYPE": "wayland", "XDG_CURRENT_DESKTOP": "Hyprland", "HYPRLAND_INSTANCE_SIGNATURE": "12f9a0d0" },
+        });
+        assert_eq!(with_fixture(fixture, get_window_manager), "Hyprland 0.45.2");
+    }
+
+    #[test]
+    fn reads_the_desktop_and_its_version() {
+        let fixture = json!({
+            "commands": { "gnome-shell --version": "GNOME Shell 47.1\n" },
+            "env": { "XDG_SESSION_TYPE": "wayland", "XDG_CURRENT_DESKTOP": "ubuntu:GNOME" },
+        });
+        assert_eq!(with_fixture(fixture, get_window_manager), "GNOME 47.1");
+
+        // Without its version command, the desktop's name alone
+        let fixture = json!({ "env": { "XDG_SESSION_TYPE": "wayland", "XDG_CURRENT_DESKTOP": "X-Cinnamon" } });
+        assert_eq!(with_fixture(fixture, get_window_manager), "Cinnamon");
+    }
+
+    #[test]
+    fn keeps_the_wallpaper_path_out_of_the_display() {
+        let value = format_wallpaper(Some("/home/alice/Pictures/sunset.png".to_string()));
+        assert_eq!(value.display, "sunset.png");
+        assert_eq!(value.image.as_deref(), Some("/home/alice/Pictures/sunset.png"));
+        assert!(format_wallpaper(None).image.is_none());
+    }
+}
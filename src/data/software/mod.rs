@@ -1,2 +1,8 @@
 pub mod software;
-mod individual_stats;
\ No newline at end of file
+pub mod individual_stats;
+mod hyprland;
+mod i3_ipc;
+mod shell;
+mod terminal;
+mod uptime;
+mod window_manager;
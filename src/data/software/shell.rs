@@ -0,0 +1,78 @@
+use crate::_utils::system_provider::provider;
+
+/// Process names of the shells vega knows, matched whole so "ssh" or "sshd" don't pass for one
+const KNOWN_SHELLS: [&str; 19] = [
+    "sh", "bash", "zsh", "fish", "dash", "ash", "ksh", "mksh", "oksh", "yash", "csh", "tcsh",
+    "nu", "elvish", "xonsh", "ion", "pwsh", "osh", "ysh",
+];
+
+/// Whether a process is a shell, login shells are named with a leading "-" (e.g. "-zsh")
+pub fn is_shell(process: &str) -> bool {
+    KNOWN_SHELLS.contains(&process.trim_start_matches('-'))
+}
+
+/// The shell vega was started from, or the user's shell when it was started by something else
+/// (a WM keybind, a launcher, a Python script, ...)
+pub fn current_shell(parent: &str) -> String {
+    pick_shell(parent, provider().env_var("SHELL"), provider().login_shell())
+}
+
+/// The parent process when it's a shell, otherwise the name of the first of `$SHELL` and the
+/// passwd entry's shell that's set
+fn pick_shell(parent: &str, env_shell: Option<String>, login_shell: Option<String>) -> String {
+    if is_shell(parent) {
+        return parent.trim_start_matches('-').to_string();
+    }
+
+    env_shell.into_iter()
+        .chain(login_shell)
+        .map(|path| path.trim().rsplit('/').next().unwrap_or_default().to_string())
+        .find(|name| !name.is_empty())
+        .unwrap_or("Unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell(parent: &str, env_shell: Option<&str>, login_shell: Option<&str>) -> String {
+        pick_shell(parent, env_shell.map(str::to_string), login_shell.map(str::to_string))
+    }
+
+    #[test]
+    fn recognizes_shells_by_their_whole_name() {
+        assert!(is_shell("bash"));
+        assert!(is_shell("fish"));
+        assert!(is_shell("nu"));
+        assert!(is_shell("-zsh"));
+        assert!(!is_shell("ssh"));
+        assert!(!is_shell("sshd"));
+        assert!(!is_shell("flash"));
+        assert!(!is_shell(""));
+    }
+
+    #[test]
+    fn keeps_a_shell_parent() {
+        assert_eq!(shell("fish", Some("/bin/zsh"), Some("/bin/bash")), "fish");
+        assert_eq!(shell("-bash", None, None), "bash");
+    }
+
+    #[test]
+    fn falls_back_to_the_shell_variable() {
+        // Run over ssh without a shell in between, or from a WM keybind or launcher
+        assert_eq!(shell("ssh", Some("/usr/bin/zsh"), Some("/bin/bash")), "zsh");
+        assert_eq!(shell("sshd", Some("/usr/bin/fish"), None), "fish");
+        assert_eq!(shell("Hyprland", Some("/bin/bash"), None), "bash");
+    }
+
+    #[test]
+    fn falls_back_to_the_passwd_entry() {
+        assert_eq!(shell("python3", None, Some("/usr/bin/zsh")), "zsh");
+        assert_eq!(shell("python3", Some(""), Some("/bin/bash")), "bash");
+    }
+
+    #[test]
+    fn unknown_without_any_shell() {
+        assert_eq!(shell("systemd", None, None), "Unknown");
+    }
+}
\ No newline at end of file
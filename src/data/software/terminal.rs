@@ -0,0 +1,104 @@
+/// Terminal emulators by process or executable name, with the name they're shown as. comm is cut to
+/// 15 characters, hence "gnome-terminal-".
+const KNOWN_TERMINALS: [(&str, &str); 40] = [
+    ("kitty", "kitty"),
+    ("alacritty", "alacritty"),
+    ("foot", "foot"),
+    ("footclient", "foot"),
+    ("ghostty", "ghostty"),
+    ("wezterm", "wezterm"),
+    ("wezterm-gui", "wezterm"),
+    ("gnome-terminal-server", "gnome-terminal"),
+    ("gnome-terminal-", "gnome-terminal"),
+    ("kgx", "GNOME Console"),
+    ("ptyxis", "ptyxis"),
+    ("ptyxis-agent", "ptyxis"),
+    ("konsole", "konsole"),
+    ("yakuake", "yakuake"),
+    ("xfce4-terminal", "xfce4-terminal"),
+    ("mate-terminal", "mate-terminal"),
+    ("lxterminal", "lxterminal"),
+    ("qterminal", "qterminal"),
+    ("tilix", "tilix"),
+    ("terminator", "terminator"),
+    ("terminology", "terminology"),
+    ("blackbox", "blackbox"),
+    ("guake", "guake"),
+    ("tilda", "tilda"),
+    ("sakura", "sakura"),
+    ("cool-retro-term", "cool-retro-term"),
+    ("contour", "contour"),
+    ("rio", "rio"),
+    ("tabby", "tabby"),
+    ("hyper", "hyper"),
+    ("xterm", "xterm"),
+    ("uxterm", "xterm"),
+    ("urxvt", "urxvt"),
+    ("urxvtd", "urxvt"),
+    ("rxvt", "rxvt"),
+    ("st", "st"),
+    ("Terminal", "Terminal"),
+    ("iTerm2", "iTerm2"),
+    ("Warp", "Warp"),
+    ("WezTerm", "wezterm"),
+];
+
+/// The display name of a terminal emulator from its process name or executable path (`ps` has the
+/// whole path on macOS), or None for anything else
+pub fn terminal_name(process: &str) -> Option<&'static str> {
+    let name = process.rsplit('/').next().unwrap_or(process);
+    KNOWN_TERMINALS.iter().find(|(known, _)| *known == name).map(|(_, display)| *display)
+}
+
+/// The terminal emulator a `$TERM` value names, e.g. "xterm-kitty", "alacritty" or "foot-direct".
+/// Plain "xterm-256color" and the like say nothing about the emulator, so they give None.
+pub fn terminal_from_term(term: &str) -> Option<&'static str> {
+    let name = term.strip_prefix("xterm-").unwrap_or(term);
+    let name = ["-256color", "-direct", "-extra"].iter()
+        .find_map(|suffix| name.strip_suffix(suffix))
+        .unwrap_or(name);
+    terminal_name(name).filter(|_| name != "xterm")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_terminals_by_process_or_path() {
+        let cases = [
+            ("kitty", Some("kitty")),
+            ("/usr/bin/kitty", Some("kitty")),
+            ("gnome-terminal-", Some("gnome-terminal")),
+            ("gnome-terminal-server", Some("gnome-terminal")),
+            ("/Applications/iTerm.app/Contents/MacOS/iTerm2", Some("iTerm2")),
+            ("footclient", Some("foot")),
+            ("kgx", Some("GNOME Console")),
+            ("zsh", None),
+            ("sshd", None),
+            ("stterm", None),
+        ];
+        for (process, expected) in cases {
+            assert_eq!(terminal_name(process), expected, "{}", process);
+        }
+    }
+
+    #[test]
+    fn names_terminals_by_term() {
+        let cases = [
+            ("xterm-kitty", Some("kitty")),
+            ("xterm-ghostty", Some("ghostty")),
+            ("alacritty", Some("alacritty")),
+            ("foot-direct", Some("foot")),
+            ("foot-extra", Some("foot")),
+            ("rxvt-256color", Some("rxvt")),
+            ("xterm-256color", None),
+            ("xterm", None),
+            ("tmux-256color", None),
+            ("linux", None),
+        ];
+        for (term, expected) in cases {
+            assert_eq!(terminal_from_term(term), expected, "{}", term);
+        }
+    }
+}
\ No newline at end of file
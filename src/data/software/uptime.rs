@@ -0,0 +1,41 @@
+use crate::_utils::system_provider::provider;
+use crate::_utils::units::now;
+use crate::config::config::cache_dir;
+use std::fs;
+
+/// When the system booted, as seconds since the epoch
+pub fn boot_time() -> u64 {
+    now().saturating_sub(provider().uptime())
+}
+
+/// The longest uptime seen so far and the boot it belongs to, updated with the current one
+///
+/// Kept as "seconds\tboot time" in `$XDG_CACHE_HOME/vega/uptime_record`, so it only covers the boots
+/// vega was run during.
+pub fn uptime_record() -> (u64, u64) {
+    let current = (provider().uptime(), boot_time());
+
+    // A simulated system's uptime has nothing to do with this machine's record
+    if provider().is_simulated() {
+        return current;
+    }
+    let path = cache_dir().map(|dir| dir.join("uptime_record"));
+
+    let saved = path.as_ref()
+        .and_then(|path| provider().read_file(&path.to_string_lossy()))
+        .and_then(|content| {
+            let (seconds, boot) = content.trim().split_once('\t')?;
+            Some((seconds.parse().ok()?, boot.parse().ok()?))
+        });
+
+    match saved {
+        Some(saved) if saved.0 >= current.0 => saved,
+        _ => {
+            if let Some(path) = path {
+                let _ = path.parent().map(fs::create_dir_all);
+                let _ = fs::write(path, format!("{}\t{}\n", current.0, current.1));
+            }
+            current
+        },
+    }
+}
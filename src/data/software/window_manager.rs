@@ -0,0 +1,305 @@
+use crate::_utils::status::warn_module;
+use crate::_utils::which::which;
+use crate::_utils::system_provider::provider;
+use crate::sh;
+#[cfg(target_os = "linux")]
+use crate::_utils::read_file::cat;
+#[cfg(target_os = "linux")]
+use std::os::fd::AsRawFd;
+#[cfg(not(target_os = "macos"))]
+use wayland_client::protocol::wl_registry::{self, WlRegistry};
+#[cfg(not(target_os = "macos"))]
+use wayland_client::{Dispatch, QueueHandle};
+#[cfg(not(target_os = "macos"))]
+use x11rb::connection::Connection;
+#[cfg(not(target_os = "macos"))]
+use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, Window};
+#[cfg(not(target_os = "macos"))]
+use x11rb::rust_connection::RustConnection;
+
+
+/// Where a macOS window manager's version comes from
+enum MacVersion {
+    /// Its CLI's output for these arguments
+    Cli(&'static str, &'static [&'static str]),
+    /// CFBundleShortVersionString of its app in /Applications
+    Bundle(&'static str),
+}
+
+/// The running macOS window manager with its version, e.g. "yabai 7.1.1 (skhd 0.3.9)", or plain
+/// "aqua" when none of the known ones run
+///
+/// skhd only binds keys, so it's appended to whichever window manager it drives.
+pub fn get_macos_wm() -> String {
+    // (process name, display name, version source), tiling WMs before window snapping tools
+    const MACOS_WMS: [(&str, &str, MacVersion); 5] = [
+        ("yabai", "yabai", MacVersion::Cli("yabai", &["--version"])),
+        ("AeroSpace", "AeroSpace", MacVersion::Cli("aerospace", &["--version"])),
+        ("Amethyst", "Amethyst", MacVersion::Bundle("Amethyst")),
+        ("Rectangle", "Rectangle", MacVersion::Bundle("Rectangle")),
+        ("Loop", "Loop", MacVersion::Bundle("Loop")),
+    ];
+
+    // One process listing instead of a pgrep per window manager
+    let processes = sh!("ps", "-axco", "comm=").stdout;
+    let running = |name: &str| processes.lines().any(|process| process.trim() == name);
+
+    let wm = MACOS_WMS.iter()
+        .find(|(process, ..)| running(process))
+        .map(|(_, name, version)| with_version(name, macos_wm_version(version)))
+        .unwrap_or("aqua".to_string());
+
+    if running("skhd") {
+        format!("{} ({})", wm, with_version("skhd", macos_wm_version(&MacVersion::Cli("skhd", &["--version"]))))
+    } else {
+        wm
+    }
+}
+
+fn macos_wm_version(source: &MacVersion) -> Option<String> {
+    let output = match source {
+        MacVersion::Cli(program, args) => provider().run(program, &args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>()),
+        MacVersion::Bundle(app) => sh!("defaults", "read", format!("/Applications/{}.app/Contents/Info", app), "CFBundleShortVersionString"),
+    };
+    if output.err_code != 0 {
+        return None;
+    }
+
+    // e.g. "yabai-v7.1.1", "skhd-v0.3.9", "aerospace CLI client version: 0.15.2-Beta 8f2d4d1" or "1.2.0"
+    output.stdout.lines().next()?
+        .split([' ', '-'])
+        .map(|word| word.trim_start_matches('v'))
+        .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+fn with_version(name: &str, version: Option<String>) -> String {
+    match version {
+        Some(version) => format!("{} {}", name, version),
+        None => name.to_string(),
+    }
+}
+
+/// bspwm's gap and border width from `bspc`, e.g. "10px gaps, 2px border", or `None` when bspwm
+/// isn't running
+pub fn get_bspwm_gaps() -> Option<String> {
+    let setting = |name: &str| {
+        let output = sh!("bspc", "config", name);
+        (output.err_code == 0).then(|| output.stdout.trim().to_string())
+    };
+
+    Some(format!("{}px gaps, {}px border", setting("window_gap")?, setting("border_width")?))
+}
+
+/// The desktop's name from a `$XDG_CURRENT_DESKTOP` value, e.g. "GNOME" for "ubuntu:GNOME" or
+/// "pop:GNOME" and "Cinnamon" for "X-Cinnamon"
+///
+/// Distro prefixes are skipped in favor of the first entry in the alias table, otherwise the last
+/// entry is used with its first letter capitalized. Returns `None` for an empty value.
+pub fn normalize_desktop(desktop: &str) -> Option<String> {
+    const DESKTOP_ALIASES: [(&str, &str); 22] = [
+        ("gnome", "GNOME"),
+        ("gnome-classic", "GNOME Classic"),
+        ("gnome-flashback", "GNOME Flashback"),
+        ("kde", "KDE"),
+        ("plasma", "KDE"),
+        ("xfce", "XFCE"),
+        ("cinnamon", "Cinnamon"),
+        ("mate", "MATE"),
+        ("lxqt", "LXQt"),
+        ("lxde", "LXDE"),
+        ("budgie", "Budgie"),
+        ("unity", "Unity"),
+        ("pantheon", "Pantheon"),
+        ("deepin", "Deepin"),
+        ("dde", "Deepin"),
+        ("cosmic", "COSMIC"),
+        ("enlightenment", "Enlightenment"),
+        ("ukui", "UKUI"),
+        ("hyprland", "Hyprland"),
+        ("sway", "Sway"),
+        ("wayfire", "Wayfire"),
+        ("niri", "niri"),
+    ];
+
+    // "X-" marks names that aren't registered with freedesktop.org, e.g. "X-Cinnamon"
+    let entries: Vec<&str> = desktop.split(':')
+        .map(|entry| entry.trim())
+        .map(|entry| entry.strip_prefix("X-").unwrap_or(entry))
+        .filter(|entry| !entry.is_empty())
+        .collect();
+
+    let known = entries.iter().find_map(|entry| DESKTOP_ALIASES.iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(entry))
+        .map(|(_, name)| name.to_string()));
+
+    known.or_else(|| entries.last().map(|entry| {
+        let mut chars = entry.chars();
+        chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+    }))
+}
+
+/// Appends the desktop environment's version to a `$XDG_CURRENT_DESKTOP` value
+///
+/// `$XDG_CURRENT_DESKTOP` is a colon-separated list (e.g. `ubuntu:GNOME`), so the first entry with a
+/// known version command wins. Returns `None` if no entry is recognized or its version can't be read.
+pub fn get_de_with_version(desktop: &str) -> Option<String> {
+    const DE_VERSION_COMMANDS: [(&str, &str); 6] = [
+        ("GNOME", "gnome-shell --version"),
+        ("KDE", "plasmashell --version"),
+        ("XFCE", "xfce4-session --version"),
+        ("Cinnamon", "cinnamon --version"),
+        ("MATE", "mate-session --version"),
+        ("LXQt", "lxqt-session --version"),
+    ];
+
+    for entry in desktop.split(':') {
+        let Some((name, command)) = DE_VERSION_COMMANDS.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(entry) || entry.eq_ignore_ascii_case(&format!("X-{}", name))) else {
+            continue
+        };
+
+        // Only the executable name needs to resolve, the rest are its arguments
+        let executable = command.split_whitespace().next()?;
+        if which(executable).is_none() {
+            warn_module("software", &format!("{} not found, {} version unavailable", executable, name));
+            return None;
+        }
+
+        // Output looks like "GNOME Shell 46.2" or "xfce4-session 4.18.1 (Xfce 4.18)", take the first version-like word
+        let args: Vec<String> = command.split_whitespace().skip(1).map(str::to_string).collect();
+        let output = provider().run(executable, &args);
+        let version = output.stdout.lines().next()?
+            .split_whitespace()
+            .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))?
+            .to_string();
+
+        return Some(format!("{} {}", name, version));
+    }
+
+    None
+}
+
+/// Queries the running X11 window manager through EWMH
+///
+/// Follows `_NET_SUPPORTING_WM_CHECK` on the root window to the WM's check window, then reads its
+/// `_NET_WM_NAME` (falling back to the legacy `WM_NAME`). Returns `None` if there is no X server
+/// or the WM does not advertise itself.
+#[cfg(not(target_os = "macos"))]
+pub fn get_x11_wm() -> Option<String> {
+    let (conn, screen_num) = x11rb::connect(None).ok()?;
+    let root = conn.setup().roots.get(screen_num)?.root;
+
+    let check_atom = intern_atom(&conn, b"_NET_SUPPORTING_WM_CHECK")?;
+    let wm_window: Window = conn.get_property(false, root, check_atom, AtomEnum::WINDOW, 0, 1).ok()?
+        .reply().ok()?
+        .value32()?
+        .next()?;
+
+    // Prefer the UTF-8 EWMH name, but some WMs (e.g. dwm) only set the legacy property
+    let name_atom = intern_atom(&conn, b"_NET_WM_NAME")?;
+    let utf8_atom = intern_atom(&conn, b"UTF8_STRING")?;
+    let name = read_string_property(&conn, wm_window, name_atom, utf8_atom)
+        .or_else(|| read_string_property(&conn, wm_window, AtomEnum::WM_NAME.into(), AtomEnum::STRING.into()))?;
+
+    Some(name)
+}
+
+#[cfg(target_os = "macos")]
+pub fn get_x11_wm() -> Option<String> {
+    None
+}
+
+/// Queries the running Wayland compositor through the Wayland socket
+///
+/// The compositor is named after the process on the other end of the socket (via `SO_PEERCRED`)
+/// where the platform supports it, otherwise it is inferred from the compositor-specific globals
+/// it advertises on `wl_registry`. Returns `None` if there is no Wayland session.
+#[cfg(not(target_os = "macos"))]
+pub fn get_wayland_compositor() -> Option<String> {
+    let conn = wayland_client::Connection::connect_to_env().ok()?;
+
+    if let Some(name) = socket_peer_name(&conn) {
+        return Some(name);
+    }
+
+    let mut globals = WaylandGlobals(Vec::new());
+    let mut queue = conn.new_event_queue();
+    conn.display().get_registry(&queue.handle(), ());
+    queue.roundtrip(&mut globals).ok()?;
+
+    // Ordered most specific first, since e.g. Hyprland also exposes the generic wlroots protocols
+    const COMPOSITOR_GLOBALS: [(&str, &str); 9] = [
+        ("hyprland_", "Hyprland"),
+        ("zcosmic_", "COSMIC"),
+        ("river_", "river"),
+        ("treeland_", "Treeland"),
+        ("org_kde_", "KWin"),
+        ("gtk_shell1", "Mutter"),
+        ("weston_", "Weston"),
+        ("zwlr_", "wlroots-based"),
+        ("xdg_wm_base", "Unknown Wayland compositor"),
+    ];
+
+    COMPOSITOR_GLOBALS.iter()
+        .find(|(prefix, _)| globals.0.iter().any(|global| global.starts_with(prefix)))
+        .map(|(_, name)| name.to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn get_wayland_compositor() -> Option<String> {
+    None
+}
+
+#[cfg(not(target_os = "macos"))]
+struct WaylandGlobals(Vec<String>);
+
+#[cfg(not(target_os = "macos"))]
+impl Dispatch<WlRegistry, ()> for WaylandGlobals {
+    fn event(state: &mut Self, _: &WlRegistry, event: wl_registry::Event, _: &(), _: &wayland_client::Connection, _: &QueueHandle<Self>) {
+        if let wl_registry::Event::Global { interface, .. } = event {
+            state.0.push(interface);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn socket_peer_name(conn: &wayland_client::Connection) -> Option<String> {
+    let backend = conn.backend();
+    let mut cred: libc::ucred = unsafe { std::mem::zeroed() };
+    let mut len = size_of::<libc::ucred>() as libc::socklen_t;
+
+    let result = unsafe {
+        libc::getsockopt(
+            backend.poll_fd().as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if result != 0 || cred.pid <= 0 {
+        return None;
+    }
+
+    let name = cat(&format!("/proc/{}/comm", cred.pid)).trim().to_string();
+    if name.is_empty() { None } else { Some(name) }
+}
+
+#[cfg(all(not(target_os = "linux"), not(target_os = "macos")))]
+fn socket_peer_name(_conn: &wayland_client::Connection) -> Option<String> {
+    None
+}
+
+#[cfg(not(target_os = "macos"))]
+fn intern_atom(conn: &RustConnection, name: &[u8]) -> Option<u32> {
+    Some(conn.intern_atom(true, name).ok()?.reply().ok()?.atom).filter(|&atom| atom != 0)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn read_string_property(conn: &RustConnection, window: Window, property: u32, kind: u32) -> Option<String> {
+    let reply = conn.get_property(false, window, property, kind, 0, 1024).ok()?.reply().ok()?;
+    let name = String::from_utf8_lossy(&reply.value).trim_end_matches('\0').trim().to_string();
+
+    if name.is_empty() { None } else { Some(name) }
+}
@@ -0,0 +1,210 @@
+use crate::_utils::hwmon::{hwmon_devices, is_hot, read_sensor};
+use crate::_utils::read_file::cat;
+use crate::_utils::units::human_bytes;
+use crate::_utils::status::warn_module;
+use crate::_utils::system_provider::provider;
+use crate::_utils::which::which;
+use crate::config::context::Context;
+use crate::data::common::StatValue;
+use crate::data::hardware::disk::get_mounts;
+use crate::sh;
+use std::fs;
+use std::path::Path;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const DISK_IO_SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct DiskIo {
+    pub disk: String,
+    pub read_per_sec: f64,
+    pub write_per_sec: f64,
+}
+
+/// Health of every imported zpool, e.g. "rpool ONLINE, tank DEGRADED (3 scrub errors)",
+/// or `None` without ZFS
+pub fn get_zpool_health() -> Option<String> {
+    which("zpool")?;
+
+    // "-H" prints one tab-separated "<name>\t<health>" line per pool
+    let pools = sh!("zpool", "list", "-H", "-o", "name,health");
+    if pools.err_code != 0 || pools.stdout.trim().is_empty() {
+        return None;
+    }
+
+    let health: Vec<String> = pools.stdout.lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(name, health)| {
+            // "  scan: scrub repaired 0B in 00:01:02 with 0 errors on Sun Jun  9 00:25:03 2024"
+            let errors = sh!("zpool", "status", name).stdout.lines()
+                .find(|line| line.trim().starts_with("scan: scrub"))
+                .and_then(|line| line.split(" with ").nth(1)?.split_whitespace().next()?.parse::<u64>().ok())
+                .unwrap_or(0);
+
+            match errors {
+                0 => format!("{} {}", name, health),
+                _ => format!("{} {} ({} scrub errors)", name, health, errors),
+            }
+        })
+        .collect();
+
+    Some(health.join(", "))
+}
+
+/// Error counters of every mounted btrfs filesystem, e.g. "/ OK, /data 2 errors",
+/// or `None` without btrfs
+pub fn get_btrfs_health() -> Option<String> {
+    let mut mounts = get_mounts();
+    mounts.retain(|mount| mount.fs_type == "btrfs");
+
+    // Subvolumes of one filesystem share a device, only report each filesystem once
+    mounts.sort_by(|a, b| a.device.cmp(&b.device).then(a.mount_point.len().cmp(&b.mount_point.len())));
+    mounts.dedup_by(|a, b| a.device == b.device);
+
+    if mounts.is_empty() {
+        return None;
+    }
+    if which("btrfs").is_none() {
+        return Some("btrfs-progs not installed".to_string());
+    }
+
+    let health: Vec<String> = mounts.iter()
+        .map(|mount| {
+            // One "[/dev/sda].write_io_errs    0" line per counter per device, reading them needs root
+            let stats = sh!("btrfs", "device", "stats", mount.mount_point);
+            let errors: u64 = stats.stdout.lines()
+                .filter_map(|line| line.split_whitespace().nth(1)?.parse::<u64>().ok())
+                .sum();
+
+            match (stats.err_code, errors) {
+                (0, 0) => format!("{} OK", mount.mount_point),
+                (0, errors) => format!("{} {} errors", mount.mount_point, errors),
+                _ => format!("{} Unknown (needs root)", mount.mount_point),
+            }
+        })
+        .collect();
+
+    Some(health.join(", "))
+}
+
+/// Drive temperatures from the kernel's hwmon sensors, e.g. "nvme0 42°C, sda 35°C",
+/// or `None` if no drive exposes one
+pub fn get_drive_temps() -> Option<StatValue> {
+    let mut hottest = i64::MIN;
+    let mut temps: Vec<String> = hwmon_devices().into_iter()
+        .filter(|(name, _)| matches!(name.as_str(), "nvme" | "drivetemp"))
+        .filter_map(|(_, hwmon)| {
+            let millidegrees = read_sensor(&hwmon, "temp1_input")?;
+            hottest = hottest.max(millidegrees / 1000);
+
+            // nvme sensors hang off the controller (nvme0), drivetemp ones off the SCSI device,
+            // whose block device name (sda) is more recognizable
+            let device = fs::canonicalize(hwmon.join("device")).ok()?;
+            let name = provider().list_dir(&device.join("block").to_string_lossy()).into_iter().next()
+                .or_else(|| Some(device.file_name()?.to_string_lossy().to_string()))?;
+
+            Some(format!("{} {}°C", name, millidegrees / 1000))
+        })
+        .collect();
+
+    temps.sort();
+    if temps.is_empty() { None } else { Some(StatValue::from(temps.join(", ")).warning(is_hot(hottest))) }
+}
+
+/// Overall SMART health of every drive smartctl can see, e.g. "2 drives passed" or "FAILED: /dev/sda",
+/// or `None` without smartmontools
+pub fn get_smart_status() -> Option<String> {
+    if which("smartctl").is_none() {
+        warn_module("storage", "smartctl not found, SMART status unavailable");
+        return None;
+    }
+
+    let scan: serde_json::Value = serde_json::from_str(&sh!("smartctl", "--scan", "--json").stdout).ok()?;
+    let devices: Vec<&str> = scan.get("devices")?.as_array()?.iter()
+        .filter_map(|device| device.get("name")?.as_str())
+        .collect();
+
+    let mut passed = 0;
+    let mut failed: Vec<&str> = Vec::new();
+    let mut unknown = 0;
+    for device in &devices {
+        // "smart_status" is missing when smartctl couldn't open the device, usually for lack of root
+        let health: Option<serde_json::Value> = serde_json::from_str(&sh!("smartctl", "-H", "--json", device).stdout).ok();
+        match health.as_ref().and_then(|health| health.get("smart_status")?.get("passed")?.as_bool()) {
+            Some(true) => passed += 1,
+            Some(false) => failed.push(device),
+            None => unknown += 1,
+        }
+    }
+
+    Some(if !failed.is_empty() {
+        format!("FAILED: {}", failed.join(", "))
+    } else if unknown == devices.len() {
+        "Unknown (needs root)".to_string()
+    } else {
+        format!("{} drive{} passed", passed, if passed == 1 { "" } else { "s" })
+    })
+}
+
+/// Starts measuring the read/write rate of the disk holding `/` in the background
+///
+/// The rate is the difference between two reads of `/proc/diskstats` `DISK_IO_SAMPLE_INTERVAL` apart,
+/// so this should be started before the other gatherers run to hide the delay behind them.
+pub fn start_disk_io_sample() -> JoinHandle<Option<DiskIo>> {
+    let context = Context::current();
+    thread::spawn(move || context.enter(|| {
+        let disk = get_root_disk()?;
+
+        let start = Instant::now();
+        let (read_before, written_before) = read_disk_sectors(&disk)?;
+        thread::sleep(DISK_IO_SAMPLE_INTERVAL);
+        let (read_after, written_after) = read_disk_sectors(&disk)?;
+        let elapsed = start.elapsed().as_secs_f64();
+
+        // diskstats always counts 512 byte sectors, regardless of the device's sector size
+        Some(DiskIo {
+            disk,
+            read_per_sec: (read_after - read_before) as f64 * 512.0 / elapsed,
+            write_per_sec: (written_after - written_before) as f64 * 512.0 / elapsed,
+        })
+    }))
+}
+
+pub fn get_disk_io(sample: Option<&DiskIo>) -> String {
+    match sample {
+        Some(sample) => format!(
+            "R {}/s W {}/s ({})",
+            human_bytes(sample.read_per_sec), human_bytes(sample.write_per_sec), sample.disk
+        ),
+        None => "Not Supported".to_string(),
+    }
+}
+
+/// The whole disk (e.g. nvme0n1) backing the root filesystem
+fn get_root_disk() -> Option<String> {
+    let root = get_mounts().into_iter().find(|mount| mount.mount_point == "/")?;
+
+    // Resolve /dev/mapper/* and /dev/disk/by-* symlinks to the kernel name (dm-0, nvme0n1p2)
+    let device = fs::canonicalize(&root.device).ok()?;
+    let name = device.file_name()?.to_string_lossy().to_string();
+
+    // Partitions live in their disk's sysfs directory
+    let sys_block = Path::new("/sys/class/block").join(&name);
+    if sys_block.join("partition").exists() {
+        let disk = fs::canonicalize(&sys_block).ok()?;
+        return Some(disk.parent()?.file_name()?.to_string_lossy().to_string());
+    }
+
+    Some(name)
+}
+
+/// Total sectors read and written by a disk since boot
+fn read_disk_sectors(disk: &str) -> Option<(u64, u64)> {
+    // "259 0 nvme0n1 <reads> <merged> <sectors read> <ms> <writes> <merged> <sectors written> ..."
+    let diskstats = cat("/proc/diskstats");
+    let fields: Vec<&str> = diskstats.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| fields.get(2) == Some(&disk))?;
+
+    Some((fields.get(5)?.parse().ok()?, fields.get(9)?.parse().ok()?))
+}
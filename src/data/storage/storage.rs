@@ -0,0 +1,13 @@
+use crate::data::common::{format_section, Section};
+use crate::data::registry::{gather_stats, Samples};
+use crate::data::storage::individual_stats::DiskIo;
+use std::thread::JoinHandle;
+
+pub fn get_storage_info(disk_io_sample: Option<JoinHandle<Option<DiskIo>>>) -> Section {
+    let samples = Samples {
+        disk_io: disk_io_sample.and_then(|sample| sample.join().ok()).flatten(),
+        ..Samples::default()
+    };
+
+    format_section("Storage", gather_stats("storage", samples))
+}
@@ -0,0 +1,62 @@
+use crate::_utils::system_provider::provider;
+use crate::_utils::units::{format_timestamp, local_time, now};
+use crate::sh;
+use std::ffi::CStr;
+
+/// e.g. "2024-05-01 09:31"
+pub fn get_clock() -> String {
+    format_timestamp(now()).unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// The zone's name with its current abbreviation and offset, e.g. "Europe/Berlin (CEST, UTC+02:00)"
+pub fn get_timezone() -> String {
+    let local = local_time(now());
+    let abbreviation = local.filter(|local| !local.tm_zone.is_null())
+        .map(|local| unsafe { CStr::from_ptr(local.tm_zone) }.to_string_lossy().into_owned());
+    let offset = local.map(|local| {
+        let minutes = local.tm_gmtoff / 60;
+        format!("UTC{}{:02}:{:02}", if minutes < 0 { '-' } else { '+' }, minutes.abs() / 60, minutes.abs() % 60)
+    });
+
+    let details: Vec<String> = abbreviation.into_iter().chain(offset).collect();
+    match (get_zone_name(), details.is_empty()) {
+        (Some(name), true) => name,
+        (Some(name), false) => format!("{} ({})", name, details.join(", ")),
+        (None, false) => details.join(", "),
+        (None, true) => "Unknown".to_string(),
+    }
+}
+
+/// The IANA zone name, from $TZ or else the zoneinfo file /etc/localtime links to
+fn get_zone_name() -> Option<String> {
+    // "TZ=:Europe/Berlin" is the same as "TZ=Europe/Berlin"
+    if let Some(tz) = provider().env_var("TZ").filter(|tz| !tz.is_empty()) {
+        return Some(tz.trim_start_matches(':').to_string());
+    }
+
+    // e.g. "/usr/share/zoneinfo/Europe/Berlin", or on macOS "/var/db/timezone/zoneinfo/Europe/Berlin"
+    provider().read_link("/etc/localtime")
+        .and_then(|target| target.split_once("zoneinfo/").map(|(_, name)| name.to_string()))
+        // Debian copies the zone into a regular file instead, and keeps its name in a file of its own
+        .or_else(|| provider().read_file("/etc/timezone").map(|name| name.trim().to_string()))
+        .filter(|name| !name.is_empty())
+}
+
+/// Whether systemd-timesyncd or another NTP client keeps the clock in sync, e.g. "Synchronized",
+/// `None` without a running systemd to ask
+pub fn get_ntp_status() -> Option<String> {
+    let output = sh!("timedatectl", "show", "--property=NTP", "--property=NTPSynchronized");
+    if output.err_code != 0 {
+        return None;
+    }
+
+    let property = |name: &str| output.stdout.lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+        .map(|value| value == "yes");
+    match (property("NTP"), property("NTPSynchronized")) {
+        (_, Some(true)) => Some("Synchronized".to_string()),
+        (Some(false), _) => Some("Disabled".to_string()),
+        (_, Some(false)) => Some("Not synchronized".to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,2 @@
+pub mod time;
+pub mod individual_stats;
\ No newline at end of file
@@ -0,0 +1,11 @@
+use crate::config::context::config;
+use crate::data::common::{format_section, Section};
+use crate::data::registry::{gather_stats, Samples};
+
+pub fn get_time_info() -> Section {
+    if !config().get_bool("time.enabled", false) {
+        return Section::default();
+    }
+
+    format_section("Time", gather_stats("time", Samples::default()))
+}
@@ -0,0 +1,178 @@
+use crate::_utils::read_file::cat;
+use crate::_utils::run_command::ShellReturn;
+use crate::_utils::status::warn_module;
+use crate::_utils::system_provider::provider;
+use crate::_utils::which::which;
+use crate::config::config::cache_dir;
+use crate::config::context::Context;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Cached versions are reprobed after a day even if the binary is unchanged, since version manager
+/// shims (rustup, pyenv, nvm, ...) switch toolchains without touching the shim itself
+const CACHE_MAX_AGE: u64 = 24 * 60 * 60;
+
+/// (name in the config, label, binaries to try, version argument)
+const KNOWN_TOOLCHAINS: [(&str, &str, &[&str], &str); 8] = [
+    ("rustc", "RST", &["rustc"], "--version"),
+    ("go", "GO", &["go"], "version"),
+    ("python", "PY", &["python3", "python"], "--version"),
+    ("node", "NOD", &["node"], "--version"),
+    ("java", "JAV", &["java"], "-version"),
+    ("ruby", "RB", &["ruby"], "--version"),
+    ("gcc", "GCC", &["gcc"], "--version"),
+    ("zig", "ZIG", &["zig"], "version"),
+];
+
+/// Versions of the given toolchains as (name, label, version), leaving out ones that aren't installed
+///
+/// Each binary is probed in parallel, and probes still running after `timeout` show as "Timed out".
+/// Results are cached by binary path and modification time in `$XDG_CACHE_HOME/vega/toolchains`.
+pub fn get_toolchain_versions(names: &[&str], timeout: Duration) -> Vec<(String, String, String)> {
+    let mut cache = read_cache();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or_default();
+
+    let toolchains: Vec<(String, String, String, u64, &str)> = names.iter()
+        .filter_map(|name| {
+            let (label, binaries, version_arg) = KNOWN_TOOLCHAINS.iter()
+                .find(|(known, ..)| known == name)
+                .map(|(_, label, binaries, version_arg)| (label.to_string(), binaries.to_vec(), *version_arg))
+                .unwrap_or((name.chars().take(3).collect::<String>().to_uppercase(), vec![*name], "--version"));
+
+            let path = binaries.iter().find_map(|binary| which(binary))?;
+            let modified = provider().modified(&path.to_string_lossy())?
+                .duration_since(UNIX_EPOCH).ok()?
+                .as_secs();
+
+            Some((name.to_string(), label, path.to_string_lossy().to_string(), modified, version_arg))
+        })
+        .collect();
+
+    // Probe everything that isn't cached at once, so the total wait is at most one timeout
+    let deadline = Instant::now() + timeout;
+    let (sender, receiver) = mpsc::channel();
+    let mut probes = Vec::new();
+    let context = Context::current();
+    for (_, _, path, modified, version_arg) in &toolchains {
+        let is_cached = cache.get(path).is_some_and(|(cached_modified, cached_at, _)| {
+            cached_modified == modified && now.saturating_sub(*cached_at) < CACHE_MAX_AGE
+        });
+        if is_cached {
+            continue;
+        }
+
+        let (sender, context) = (sender.clone(), context.clone());
+        let (path, version_arg) = (path.clone(), version_arg.to_string());
+        probes.push(thread::spawn(move || context.enter(|| {
+            // Probes still running at the deadline are killed rather than left behind
+            if let Some(output) = provider().run_until(&path, &[version_arg], deadline) {
+                let _ = sender.send((path, parse_version(&output)));
+            }
+        })));
+    }
+    drop(sender);
+
+    let mut finished = Vec::new();
+    while let Ok((path, version)) = receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        if let Some(version) = version {
+            let modified = toolchains.iter().find(|(_, _, p, ..)| *p == path).map(|(_, _, _, modified, _)| *modified).unwrap_or_default();
+            cache.insert(path.clone(), (modified, now, version));
+        }
+        finished.push(path);
+    }
+    // The probes return right after the deadline, having killed what's left, which mustn't be cut
+    // short by vega exiting
+    probes.into_iter().for_each(|probe| { let _ = probe.join(); });
+    if !finished.is_empty() {
+        write_cache(&cache);
+    }
+
+    toolchains.into_iter()
+        .map(|(name, label, path, modified, _)| {
+            let version = cache.get(&path)
+                .filter(|(cached_modified, ..)| *cached_modified == modified)
+                .map(|(_, _, version)| version.clone())
+                .unwrap_or_else(|| if finished.contains(&path) {
+                    "Unknown".to_string()
+                } else {
+                    warn_module("toolchains", &format!("{} took longer than {}ms to report its version", path, timeout.as_millis()));
+                    "Timed out".to_string()
+                });
+            (name, label, version)
+        })
+        .collect()
+}
+
+/// Picks the version number out of whatever `<path> <version_arg>` printed, e.g.
+/// "rustc 1.80.0 (051478957 2024-07-21)", "go version go1.22.1 linux/amd64" or "v20.11.0"
+fn parse_version(output: &ShellReturn) -> Option<String> {
+    // Some tools (older Python, java) print their version to stderr
+    let text = format!("{}{}", output.stdout, output.stderr);
+
+    text.lines().next()?
+        .split_whitespace()
+        .map(|word| word.trim_matches('"').trim_start_matches("go").trim_start_matches('v'))
+        .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+/// Reads the cache as `path -> (modification time, cached at, version)`
+fn read_cache() -> HashMap<String, (u64, u64, String)> {
+    // The cache holds this machine's binaries, not a simulated system's
+    let Some(dir) = cache_dir().filter(|_| !provider().is_simulated()) else {
+        return HashMap::new();
+    };
+
+    // One "path\tmodified\tcached_at\tversion" line per binary
+    cat(&dir.join("toolchains").to_string_lossy()).lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let path = fields.next()?.to_string();
+            let modified = fields.next()?.parse().ok()?;
+            let cached_at = fields.next()?.parse().ok()?;
+            let version = fields.next()?.to_string();
+            Some((path, (modified, cached_at, version)))
+        })
+        .collect()
+}
+
+fn write_cache(cache: &HashMap<String, (u64, u64, String)>) {
+    let Some(dir) = cache_dir().filter(|_| !provider().is_simulated()) else {
+        return;
+    };
+
+    let content: String = cache.iter()
+        .map(|(path, (modified, cached_at, version))| format!("{}\t{}\t{}\t{}\n", path, modified, cached_at, version))
+        .collect();
+
+    // The cache is only an optimization, failing to write it just means probing again next time
+    let _ = fs::create_dir_all(&dir).and_then(|_| fs::write(dir.join("toolchains"), content));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_utils::fixture::with_fixture;
+    use serde_json::json;
+
+    #[test]
+    fn probes_the_installed_toolchains() {
+        let fixture = json!({
+            "commands": {
+                "rustc --version": "rustc 1.80.0 (051478957 2024-07-21)",
+                "go version": "go version go1.22.1 linux/amd64",
+                "java -version": { "stderr": "openjdk version \"21.0.2\" 2024-01-16", "code": 0 },
+            },
+        });
+        let versions = with_fixture(fixture, || get_toolchain_versions(&["rustc", "go", "java", "zig"], Duration::from_secs(5)));
+
+        assert_eq!(versions, vec![
+            ("rustc".to_string(), "RST".to_string(), "1.80.0".to_string()),
+            ("go".to_string(), "GO".to_string(), "1.22.1".to_string()),
+            ("java".to_string(), "JAV".to_string(), "21.0.2".to_string()),
+        ]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,2 @@
+pub mod toolchains;
+mod individual_stats;
@@ -0,0 +1,22 @@
+use crate::config::context::config;
+use crate::data::common::{format_section, Section, Stat};
+use crate::data::toolchains::individual_stats::get_toolchain_versions;
+use std::time::Duration;
+
+pub fn get_toolchains_info() -> Section {
+    let config = config();
+    if !config.get_bool("toolchains.enabled", false) {
+        return Section::default();
+    }
+
+    let mut names = config.get_str_list("toolchains.list");
+    if names.is_empty() {
+        names = vec!["rustc", "go", "python", "node"];
+    }
+    let timeout = Duration::from_millis(config.get_int("toolchains.timeout_ms", 500).max(0) as u64);
+
+    let versions = get_toolchain_versions(&names, timeout);
+    format_section("Toolchains", versions.into_iter()
+        .map(|(name, label, version)| Stat { id: format!("toolchain_{}", name), label, value: version.into() })
+        .collect())
+}
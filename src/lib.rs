@@ -0,0 +1,15 @@
+//! vega as a library: describe a system with a `config::context::Context` made from your own `Args`,
+//! `Config` and `SystemProvider`, gather it with `data::common::get_system_info(&context)` and turn
+//! it into output with `output::render::renderer(&context, logo).render(&info)`, or implement
+//! `Renderer` for a new target. Nothing reads the host's command line or config file, but a few
+//! things stay process-wide rather than per context: the color depth and background of the
+//! terminal on stdout (when the options don't say), and the logos, which include the user's own
+//! from `~/.config/vega/logos`.
+pub mod args;
+pub mod bench;
+pub mod config;
+pub mod data;
+pub mod _utils;
+pub mod logo;
+pub mod output;
+pub mod theme;
@@ -0,0 +1,57 @@
+use crate::_utils::terminal::query_terminal;
+use crate::config::context::args;
+use std::env;
+
+/// How image logos are drawn, detected from the terminal or forced with `--backend`, see `backend()`
+pub fn select_backend() -> Backend {
+    // Images need a terminal that can draw them, which --ascii says this isn't
+    if args().ascii {
+        return Backend::Ascii;
+    }
+
+    match args().backend.as_deref() {
+        Some("kitty") => Backend::Kitty,
+        Some("sixel") => Backend::Sixel,
+        Some("iterm2") => Backend::Iterm2,
+        Some("braille") => Backend::Braille,
+        Some("ascii") => Backend::Ascii,
+        _ => detect_backend(),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Kitty,
+    Sixel,
+    Iterm2,
+    /// Dithered braille dots, for terminals without any image protocol
+    Braille,
+    /// Ignore images and always use ASCII art
+    Ascii,
+}
+
+fn detect_backend() -> Backend {
+    let term = env::var("TERM").unwrap_or_default();
+    let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+
+    // Terminals implementing the kitty graphics protocol
+    if term.contains("kitty") || term.contains("ghostty") || env::var_os("KITTY_WINDOW_ID").is_some()
+        || matches!(term_program.as_str(), "ghostty" | "WezTerm" | "Konsole") {
+        return Backend::Kitty;
+    }
+
+    // Terminals implementing iTerm2's inline images
+    if matches!(term_program.as_str(), "iTerm.app" | "vscode" | "Tabby" | "mintty" | "rio")
+        || env::var("LC_TERMINAL").is_ok_and(|terminal| terminal == "iTerm2") {
+        return Backend::Iterm2;
+    }
+
+    // Everything else is asked for its primary device attributes, sixel support is attribute 4,
+    // e.g. "ESC [?62;4;22c"
+    let attributes = query_terminal("\x1b[c", |response| response.ends_with(b"c"));
+    let supports_sixel = attributes.as_deref()
+        .and_then(|attributes| attributes.strip_prefix("\x1b[?")?.strip_suffix('c'))
+        .is_some_and(|attributes| attributes.split(';').any(|attribute| attribute == "4"));
+
+    if supports_sixel { Backend::Sixel } else { Backend::Braille }
+}
@@ -0,0 +1,326 @@
+use crate::_utils::read_file::expand_home;
+use crate::_utils::terminal::cell_size;
+use crate::config::context::config;
+use crate::logo::backend::Backend;
+use crate::logo::logo::Logo;
+use crate::theme::appearance::Appearance;
+use crate::theme::theme::background;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::imageops::FilterType;
+use image::{AnimationDecoder, DynamicImage, ImageFormat, RgbaImage};
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Write};
+use std::process;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+/// kitty image id and play time of the looping animated logo, if one is showing
+static ANIMATION: OnceLock<(u32, Duration)> = OnceLock::new();
+
+/// Cell size assumed when the terminal doesn't report its pixel dimensions
+const DEFAULT_CELL_SIZE: (u32, u32) = (10, 20);
+
+/// Builds a logo that draws an image `cols` cells wide with the given backend, or None if the
+/// image can't be read or the backend can't draw images
+///
+/// Space for the image is reserved by printing blank lines first (so any scrolling happens before
+/// it is drawn), then it is drawn with the cursor saved and restored around it. The logo's lines
+/// only move the cursor past the image, so the stats end up to its right.
+pub fn get_image_logo(path: &str, cols: u32, backend: Backend) -> Option<Logo> {
+    let image = image::open(expand_home(path)).ok()?;
+    let rows = rows_for_width(&image, cols);
+
+    // Braille is plain text, so it needs no cursor tricks and can be cropped like any other logo
+    if backend == Backend::Braille {
+        let image = image.resize_exact(cols * 2, rows * 4, FilterType::Triangle);
+        return Some(Logo {
+            rows: rows as u16,
+            cols: cols as u16,
+            content: encode_braille(&image.to_rgba8()).into_iter(),
+            graphics: false,
+        });
+    }
+
+    // Only kitty can play animations, everything else shows the first frame
+    let animation = (backend == Backend::Kitty).then(|| load_animation(path)).flatten();
+    let graphic = match animation {
+        Some(frames) => encode_kitty_animation(&frames, cols, rows)?,
+        None => encode(&image, cols, rows, backend)?,
+    };
+
+    let skip = format!("\x1b[{}C", cols);
+    let mut content = vec![skip.clone(); rows as usize];
+    content[0] = format!("{}\x1b[{}A\x1b7{}\x1b8{}", "\n".repeat(rows as usize), rows, graphic, skip);
+
+    Some(Logo {
+        rows: rows as u16,
+        cols: cols as u16,
+        content: content.into_iter(),
+        graphics: true,
+    })
+}
+
+/// A one row tall image to print inline with text, or None if the image can't be read or the
+/// backend only does text. The cursor ends up just past the image.
+pub fn render_thumbnail(path: &str, backend: Backend) -> Option<String> {
+    let image = image::open(expand_home(path)).ok()?;
+
+    let (cell_width, cell_height) = cell_size().unwrap_or(DEFAULT_CELL_SIZE);
+    let cols = ((cell_height * image.width()) as f64 / (cell_width * image.height()) as f64).round().clamp(1.0, 8.0) as u32;
+
+    Some(format!("\x1b7{}\x1b8\x1b[{}C", encode(&image, cols, 1, backend)?, cols))
+}
+
+/// Rows needed to show an image `cols` cells wide without stretching it
+fn rows_for_width(image: &DynamicImage, cols: u32) -> u32 {
+    let (cell_width, cell_height) = cell_size().unwrap_or(DEFAULT_CELL_SIZE);
+    ((cols * cell_width) as f64 * image.height() as f64 / image.width() as f64 / cell_height as f64).round().max(1.0) as u32
+}
+
+/// Escape sequences drawing an image over `cols` x `rows` cells, for the backends that draw pixels
+fn encode(image: &DynamicImage, cols: u32, rows: u32, backend: Backend) -> Option<String> {
+    let (cell_width, cell_height) = cell_size().unwrap_or(DEFAULT_CELL_SIZE);
+    let image = image.resize_exact(cols * cell_width, rows * cell_height, FilterType::Triangle);
+
+    match backend {
+        Backend::Kitty => Some(encode_kitty(&image.to_rgba8(), cols, rows)),
+        Backend::Iterm2 => encode_iterm2(&image, cols, rows),
+        Backend::Sixel => Some(encode_sixel(&image.to_rgba8())),
+        Backend::Braille | Backend::Ascii => None,
+    }
+}
+
+/// kitty graphics protocol: raw RGBA
+fn encode_kitty(image: &RgbaImage, cols: u32, rows: u32) -> String {
+    // C=1 keeps the cursor in place, q=2 suppresses the terminal's replies
+    kitty_command(&format!("a=T,f=32,s={},v={},c={},r={},C=1,q=2", image.width(), image.height(), cols, rows), image.as_raw())
+}
+
+/// A kitty graphics command, its payload split into chunks of at most 4096 base64 bytes
+fn kitty_command(control: &str, payload: &[u8]) -> String {
+    let payload = BASE64.encode(payload);
+    let chunks: Vec<&str> = payload.as_bytes()
+        .chunks(4096)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+
+    if chunks.is_empty() {
+        return format!("\x1b_G{}\x1b\\", control);
+    }
+
+    chunks.iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let more = (i + 1 < chunks.len()) as u8;
+            match i {
+                0 => format!("\x1b_G{},m={};{}\x1b\\", control, more, chunk),
+                _ => format!("\x1b_Gm={};{}\x1b\\", more, chunk),
+            }
+        })
+        .collect()
+}
+
+/// The frames of an animated GIF or APNG with their delays in milliseconds, or None for a still image
+fn load_animation(path: &str) -> Option<Vec<(RgbaImage, u32)>> {
+    let reader = || File::open(expand_home(path)).ok().map(BufReader::new);
+
+    let frames = match ImageFormat::from_path(expand_home(path)).ok()? {
+        ImageFormat::Gif => GifDecoder::new(reader()?).ok()?.into_frames().collect_frames().ok()?,
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(reader()?).ok()?;
+            if !decoder.is_apng().ok()? {
+                return None;
+            }
+            decoder.apng().ok()?.into_frames().collect_frames().ok()?
+        },
+        _ => return None,
+    };
+
+    if frames.len() < 2 {
+        return None;
+    }
+
+    Some(frames.into_iter()
+        .map(|frame| {
+            let (numerator, denominator) = frame.delay().numer_denom_ms();
+            // Browsers treat tiny delays as 100ms too, plenty of GIFs rely on it
+            let delay = match numerator / denominator.max(1) {
+                0..=10 => 100,
+                delay => delay,
+            };
+            (frame.into_buffer(), delay)
+        })
+        .collect())
+}
+
+/// kitty graphics protocol animation: every frame is uploaded up front as PNG and the terminal plays
+/// them, once or looping until `finish_animation` stops it
+fn encode_kitty_animation(frames: &[(RgbaImage, u32)], cols: u32, rows: u32) -> Option<String> {
+    let (cell_width, cell_height) = cell_size().unwrap_or(DEFAULT_CELL_SIZE);
+    let id = process::id();
+    let looping = config().get_str("logo.animation") == Some("loop");
+
+    let mut sequence = String::new();
+    for (i, (frame, delay)) in frames.iter().enumerate() {
+        let frame = DynamicImage::ImageRgba8(frame.clone()).resize_exact(cols * cell_width, rows * cell_height, FilterType::Triangle);
+        let mut png = Vec::new();
+        frame.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).ok()?;
+
+        match i {
+            0 => {
+                sequence.push_str(&kitty_command(&format!("a=T,i={},f=100,c={},r={},C=1,q=2", id, cols, rows), &png));
+                sequence.push_str(&kitty_command(&format!("a=a,i={},r=1,z={},q=2", id, delay), &[]));
+            },
+            _ => sequence.push_str(&kitty_command(&format!("a=f,i={},f=100,z={},q=2", id, delay), &png)),
+        }
+    }
+
+    // v is one more than the number of loops to play, with 1 looping forever
+    sequence.push_str(&kitty_command(&format!("a=a,i={},s=3,v={},q=2", id, if looping { 1 } else { 2 }), &[]));
+
+    if looping {
+        let duration = config().get_int("logo.animation_duration_ms", 3000).max(0) as u64;
+        let _ = ANIMATION.set((id, Duration::from_millis(duration)));
+    }
+
+    Some(sequence)
+}
+
+/// Lets a looping animated logo play for its configured duration, then stops it on the current frame
+pub fn finish_animation() {
+    let Some((id, duration)) = ANIMATION.get() else {
+        return;
+    };
+
+    let _ = io::stdout().flush();
+    thread::sleep(*duration);
+    print!("{}", kitty_command(&format!("a=a,i={},s=1,q=2", id), &[]));
+    let _ = io::stdout().flush();
+}
+
+/// Braille patterns, each cell a 2x4 grid of dots, Floyd-Steinberg dithered to one color
+///
+/// Dots are raised where the image is bright, or dark on light terminals, and never where it is
+/// transparent.
+fn encode_braille(image: &RgbaImage) -> Vec<String> {
+    let (width, height) = image.dimensions();
+    let invert = background() == Appearance::Light;
+
+    let mut levels: Vec<f32> = image.pixels()
+        .map(|pixel| {
+            let [r, g, b, a] = pixel.0.map(|channel| channel as f32 / 255.0);
+            let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+            (if invert { 1.0 - luminance } else { luminance }) * a
+        })
+        .collect();
+
+    let mut dots = vec![false; levels.len()];
+    for y in 0..height as usize {
+        for x in 0..width as usize {
+            let i = y * width as usize + x;
+            dots[i] = levels[i] >= 0.5;
+
+            // Spread the rounding error onto the pixels not yet visited
+            let error = levels[i] - if dots[i] { 1.0 } else { 0.0 };
+            let mut spread = |dx: isize, dy: usize, weight: f32| {
+                let nx = x as isize + dx;
+                if nx >= 0 && (nx as u32) < width && ((y + dy) as u32) < height {
+                    levels[(y + dy) * width as usize + nx as usize] += error * weight;
+                }
+            };
+            spread(1, 0, 7.0 / 16.0);
+            spread(-1, 1, 3.0 / 16.0);
+            spread(0, 1, 5.0 / 16.0);
+            spread(1, 1, 1.0 / 16.0);
+        }
+    }
+
+    // Dot numbering within a cell runs down the left column, then the right, with the bottom row last
+    const DOT_BITS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+    (0..height / 4).map(|row| {
+        (0..width / 2).map(|col| {
+            let bits: u32 = (0..4).flat_map(|dy| (0..2).map(move |dx| (dx, dy)))
+                .filter(|(dx, dy)| dots[((row * 4 + dy) * width + col * 2 + dx) as usize])
+                .map(|(dx, dy)| DOT_BITS[dy as usize][dx as usize])
+                .sum();
+            char::from_u32(0x2800 + bits).unwrap_or(' ')
+        }).collect()
+    }).collect()
+}
+
+/// iTerm2 inline images, which take an encoded image file
+fn encode_iterm2(image: &DynamicImage, cols: u32, rows: u32) -> Option<String> {
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).ok()?;
+
+    Some(format!("\x1b]1337;File=inline=1;width={};height={};preserveAspectRatio=0:{}\x07", cols, rows, BASE64.encode(png)))
+}
+
+/// Sixel, quantized to a 6x6x6 color cube with transparent pixels left unpainted
+fn encode_sixel(image: &RgbaImage) -> String {
+    let (width, height) = image.dimensions();
+    let color_index = |pixel: &image::Rgba<u8>| -> Option<usize> {
+        let [r, g, b, a] = pixel.0;
+        let level = |channel: u8| (channel as usize * 5 + 127) / 255;
+        (a >= 128).then(|| level(r) * 36 + level(g) * 6 + level(b))
+    };
+
+    // Background select 1 keeps unpainted pixels transparent
+    let mut sixel = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+    for index in 0..216 {
+        let percent = |level: usize| level * 100 / 5;
+        sixel.push_str(&format!("#{};2;{};{};{}", index, percent(index / 36), percent(index / 6 % 6), percent(index % 6)));
+    }
+
+    // Each band is 6 pixel rows, drawn once per color present in it
+    for band_top in (0..height).step_by(6) {
+        let mut bands: Vec<Option<Vec<u8>>> = vec![None; 216];
+
+        for x in 0..width {
+            for bit in 0..6 {
+                let y = band_top + bit;
+                if y >= height {
+                    break;
+                }
+                if let Some(index) = color_index(image.get_pixel(x, y)) {
+                    bands[index].get_or_insert_with(|| vec![0; width as usize])[x as usize] |= 1 << bit;
+                }
+            }
+        }
+
+        for (index, band) in bands.iter().enumerate() {
+            let Some(band) = band else {
+                continue;
+            };
+            sixel.push_str(&format!("#{}", index));
+            push_sixel_runs(&mut sixel, band);
+            // Return to the start of the band for the next color
+            sixel.push('$');
+        }
+        sixel.push('-');
+    }
+
+    sixel.push_str("\x1b\\");
+    sixel
+}
+
+/// Appends sixel characters for a band, run-length encoding repeats as `!<count><char>`
+fn push_sixel_runs(sixel: &mut String, band: &[u8]) {
+    let mut i = 0;
+    while i < band.len() {
+        let run = band[i..].iter().take_while(|bits| **bits == band[i]).count();
+        let c = (63 + band[i]) as char;
+
+        if run > 3 {
+            sixel.push_str(&format!("!{}{}", run, c));
+        } else {
+            (0..run).for_each(|_| sixel.push(c));
+        }
+        i += run;
+    }
+}
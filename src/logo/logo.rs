@@ -1,62 +1,350 @@
-use crate::_utils::run_command::ShellReturn;
-use crate::sh;
+use crate::_utils::read_file::cat;
+use crate::_utils::system_provider::provider;
+use crate::_utils::sandbox::os_release;
+use crate::_utils::status::{warn, EXIT_NO_INPUT, EXIT_USAGE};
+use crate::_utils::terminal::{filter_columns, visible_width};
+use crate::config::config::config_dir;
+use crate::config::context::{args, backend, config};
+use crate::logo::backend::Backend;
+use crate::logo::graphics::get_image_logo;
+use crate::theme::color::color_depth;
+use crate::theme::theme::adapt_logo_line;
 use std::collections::HashMap;
-use std::process::Command;
-use std::str::Lines;
+use std::fs;
+use std::io::{self, Read};
+use std::mem::zeroed;
+use std::process::exit;
 use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-static LOGOS: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
-    HashMap::from([
+/// Every available logo by name: the bundled art preprocessed by build.rs, plus plain text art
+/// from `~/.config/vega/logos/<name>`, which takes precedence over a bundled logo of the same name
+pub static LOGOS: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
+    let mut logos = HashMap::from([
+        ("almalinux", include_str!("../../static/logos/sh/almalinux")),
+        ("alpine", include_str!("../../static/logos/sh/alpine")),
+        ("apple", include_str!("../../static/logos/sh/apple")),
         ("arch", include_str!("../../static/logos/sh/arch")),
-    ])
+        ("armbian", include_str!("../../static/logos/sh/armbian")),
+        ("artix", include_str!("../../static/logos/sh/artix")),
+        ("asahi", include_str!("../../static/logos/sh/asahi")),
+        ("bazzite", include_str!("../../static/logos/sh/bazzite")),
+        ("cachyos", include_str!("../../static/logos/sh/cachyos")),
+        ("centos", include_str!("../../static/logos/sh/centos")),
+        ("chimeraos", include_str!("../../static/logos/sh/chimeraos")),
+        ("christmas", include_str!("../../static/logos/sh/christmas")),
+        ("clear-linux", include_str!("../../static/logos/sh/clear-linux")),
+        ("debian", include_str!("../../static/logos/sh/debian")),
+        ("deepin", include_str!("../../static/logos/sh/deepin")),
+        ("elementary", include_str!("../../static/logos/sh/elementary")),
+        ("endeavour", include_str!("../../static/logos/sh/endeavour")),
+        ("fedora", include_str!("../../static/logos/sh/fedora")),
+        ("freebsd", include_str!("../../static/logos/sh/freebsd")),
+        ("garuda", include_str!("../../static/logos/sh/garuda")),
+        ("gentoo", include_str!("../../static/logos/sh/gentoo")),
+        ("halloween", include_str!("../../static/logos/sh/halloween")),
+        ("kali", include_str!("../../static/logos/sh/kali")),
+        ("linux", include_str!("../../static/logos/sh/linux")),
+        ("mageia", include_str!("../../static/logos/sh/mageia")),
+        ("manjaro", include_str!("../../static/logos/sh/manjaro")),
+        ("mint", include_str!("../../static/logos/sh/mint")),
+        ("mx", include_str!("../../static/logos/sh/mx")),
+        ("neon", include_str!("../../static/logos/sh/neon")),
+        ("nixos", include_str!("../../static/logos/sh/nixos")),
+        ("nobara", include_str!("../../static/logos/sh/nobara")),
+        ("opensuse", include_str!("../../static/logos/sh/opensuse")),
+        ("parrot", include_str!("../../static/logos/sh/parrot")),
+        ("popos", include_str!("../../static/logos/sh/popos")),
+        ("rocky", include_str!("../../static/logos/sh/rocky")),
+        ("rpi", include_str!("../../static/logos/sh/rpi")),
+        ("slackware", include_str!("../../static/logos/sh/slackware")),
+        ("solus", include_str!("../../static/logos/sh/solus")),
+        ("steamos", include_str!("../../static/logos/sh/steamos")),
+        ("ubuntu", include_str!("../../static/logos/sh/ubuntu")),
+        ("void", include_str!("../../static/logos/sh/void")),
+        ("zorin", include_str!("../../static/logos/sh/zorin")),
+    ]);
+
+    // User logos live for the rest of the program like the bundled ones, so leaking them is fine
+    let user_logos = config_dir()
+        .and_then(|dir| fs::read_dir(dir.join("logos")).ok())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| Some((entry.file_name().into_string().ok()?, fs::read_to_string(entry.path()).ok()?)));
+    for (name, art) in user_logos {
+        logos.insert(Box::leak(name.into_boxed_str()), Box::leak(from_plain_art(&art).into_boxed_str()));
+    }
+
+    logos
 });
 
+/// os-release IDs whose logo goes by a different name
+const DISTRO_ALIASES: [(&str, &str); 11] = [
+    ("clear-linux-os", "clear-linux"),
+    ("endeavouros", "endeavour"),
+    ("fedora-asahi-remix", "asahi"),
+    ("linuxmint", "mint"),
+    ("macos", "apple"),
+    ("opensuse-leap", "opensuse"),
+    ("opensuse-slowroll", "opensuse"),
+    ("opensuse-tumbleweed", "opensuse"),
+    ("pop", "popos"),
+    ("raspbian", "rpi"),
+    ("suse", "opensuse"),
+];
+
+#[derive(Clone, Default)]
 pub struct Logo {
     pub rows: u16,
     pub cols: u16,
-    pub content: Lines<'static>,
+    pub content: std::vec::IntoIter<String>,
+    /// Drawn by the terminal as an image, so the lines only move the cursor and can't be cropped
+    pub graphics: bool,
+}
+
+impl Logo {
+
+    /// Parses a logo from the registry, a "<rows> <cols>" header followed by the padded, colored rows
+    fn parse(logo: &'static str) -> Logo {
+        let mut content = logo.lines();
+
+        let first_line = content.next().unwrap_or_default();
+        let mut logo_metadata = first_line.split_whitespace();
+        let rows = logo_metadata.next().and_then(|rows| rows.parse::<u16>().ok()).unwrap_or(0);
+        let cols = logo_metadata.next().and_then(|cols| cols.parse::<u16>().ok()).unwrap_or(0);
+
+        Logo {
+            rows,
+            cols,
+            content: content.map(str::to_string).collect::<Vec<_>>().into_iter(),
+            graphics: false,
+        }
+    }
+
+    /// Shrinks the logo to at most `max_cols` columns so it doesn't wrap on narrow terminals
+    ///
+    /// Logos that only slightly overflow lose columns on the right, which tend to be the least
+    /// important part of the art. Ones that would lose more than a quarter of their width are
+    /// downscaled instead, dropping evenly spaced columns and rows to keep their proportions.
+    pub fn fit_to_width(&mut self, max_cols: usize) {
+        let cols = self.cols as usize;
+        if cols <= max_cols || self.graphics {
+            return;
+        }
+
+        let lines: Vec<String> = self.content.by_ref().collect();
+        let lines: Vec<String> = if max_cols * 4 >= cols * 3 {
+            lines.iter().map(|line| filter_columns(line, |column| column < max_cols)).collect()
+        } else {
+            // Keep a column (or row) whenever its scaled position moves on to the next one
+            let keep = |index: usize, from: usize, to: usize| index * to / from != (index + 1) * to / from;
+            lines.iter()
+                .enumerate()
+                .filter(|(row, _)| keep(*row, cols, max_cols))
+                .map(|(_, line)| filter_columns(line, |column| keep(column, cols, max_cols)))
+                .collect()
+        };
+
+        self.rows = lines.len() as u16;
+        self.cols = lines.iter().map(|line| visible_width(line)).max().unwrap_or(0) as u16;
+        self.content = lines.into_iter();
+    }
+
 }
 
 pub fn get_logo() -> Logo {
 
-    let os_distro: &str = match sh!("uname").stdout.trim() {
-        "Linux" => {
-            &sh!("awk -F= '/^ID=/ {{ gsub(/\"/, \"\", $2); print $2 }}' /etc/os-release").stdout.trim().to_owned()
+    if let Some(logo) = args().image.as_deref().and_then(get_configured_image_logo) {
+        return logo;
+    }
+
+    if let Some(path) = &args().logo_file {
+        return Logo::parse(Box::leak(from_plain_art(&read_logo_file(path)).into_boxed_str()));
+    }
+
+    match args().logo.as_deref() {
+        Some("random") => return Logo::parse(get_random_logo()),
+        Some(name) => match find_logo(name) {
+            Some(logo) => return Logo::parse(logo),
+            None => warn(&format!("unknown logo '{}', see `vega logo list`", name)),
         },
-        "Darwin" => "macos",
-        "FreeBSD" => "freebsd",
-        _ => "unknown",
+        None => {},
+    }
+
+    if let Some(logo) = config().get_str("logo.image").and_then(get_configured_image_logo) {
+        return logo;
+    }
+
+    if config().get_bool("logo.seasonal", false) && let Some(logo) = get_seasonal_logo() {
+        return Logo::parse(logo);
+    }
+
+    // Try the exact distro first, then the distros it declares itself like (e.g. an Arch
+    // derivative gets the Arch logo), and finally the generic Tux
+    let logo = get_distro_ids().iter()
+        .find_map(|id| find_logo(id))
+        .unwrap_or(LOGOS["linux"]);
+
+    Logo::parse(logo)
+
+}
+
+/// An image logo drawn with the detected backend, or None to fall back to ASCII art
+fn get_configured_image_logo(path: &str) -> Option<Logo> {
+    if backend() == Backend::Ascii {
+        return None;
+    }
+
+    let cols = config().get_int("logo.image_width", 30).clamp(1, 500) as u32;
+    let logo = get_image_logo(path, cols, backend());
+    if logo.is_none() {
+        warn(&format!("couldn't read image '{}', using the distro logo", path));
+    }
+    logo
+}
+
+/// Any logo, chosen using the clock as a cheap source of randomness
+fn get_random_logo() -> &'static str {
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.subsec_nanos()).unwrap_or_default();
+
+    // HashMap order isn't stable between runs, so sort before picking
+    let mut names: Vec<&str> = LOGOS.keys().copied().collect();
+    names.sort_unstable();
+
+    LOGOS[names[seed as usize % names.len()]]
+}
+
+/// Holiday art for the current date, if there is any
+fn get_seasonal_logo() -> Option<&'static str> {
+    // (logo, month, first day, last day)
+    const SEASONS: [(&str, i32, i32, i32); 2] = [
+        ("halloween", 10, 24, 31),
+        ("christmas", 12, 18, 26),
+    ];
+
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut local: libc::tm = unsafe { zeroed() };
+    if unsafe { libc::localtime_r(&now, &mut local) }.is_null() {
+        return None;
+    }
+
+    SEASONS.iter()
+        .find(|(_, month, first, last)| local.tm_mon + 1 == *month && (*first..=*last).contains(&local.tm_mday))
+        .and_then(|(logo, ..)| find_logo(logo))
+}
+
+/// Prints the names of all available logos, one per line
+pub fn list_logos() {
+    let mut names: Vec<&str> = LOGOS.keys().copied().collect();
+    names.sort_unstable();
+
+    for name in names {
+        println!("{}", name);
+    }
+}
+
+/// Prints a logo with its colors, as it would appear next to the stats
+pub fn show_logo(name: &str) {
+    let Some(logo) = find_logo(name) else {
+        eprintln!("vega: unknown logo '{}', see `vega logo list`", name);
+        exit(EXIT_USAGE);
     };
 
-    let mut content = match os_distro {
-        "alpine" => include_str!("../../static/logos/sh/alpine"),
-        "arch" => include_str!("../../static/logos/sh/arch"),
-        "artix" => include_str!("../../static/logos/sh/artix"),
-        "debian" => include_str!("../../static/logos/sh/debian"),
-        "endeavouros" => include_str!("../../static/logos/sh/endeavour"),
-        "fedora" => include_str!("../../static/logos/sh/fedora"),
-        "freebsd" => include_str!("../../static/logos/sh/freebsd"),
-        "gentoo" => include_str!("../../static/logos/sh/gentoo"),
-        "linuxmint" => include_str!("../../static/logos/sh/mint"),
-        "manjaro" => include_str!("../../static/logos/sh/manjaro"),
-        "macos" => include_str!("../../static/logos/sh/apple"),
-        "nixos" => include_str!("../../static/logos/sh/nixos"),
-        "nobara" => include_str!("../../static/logos/sh/nobara"),
-        "pop" => include_str!("../../static/logos/sh/popos"),
-        "raspbian" => include_str!("../../static/logos/sh/rpi"),
-        "ubuntu" => include_str!("../../static/logos/sh/ubuntu"),
-        _ => "",
-    }.lines();
-
-    let first_line = content.next().unwrap();
-    let mut logo_metadata = first_line.split_whitespace();
-    let rows = logo_metadata.next().unwrap().parse::<u16>().unwrap();
-    let cols = logo_metadata.next().unwrap().parse::<u16>().unwrap();
-    
-    Logo {
-        rows,
-        cols,
-        content,   
-    }
-
-}
\ No newline at end of file
+    for line in Logo::parse(logo).content {
+        println!("{}", adapt_logo_line(&line, color_depth()));
+    }
+}
+
+/// Looks up a logo by its name or an os-release ID
+pub fn find_logo(name: &str) -> Option<&'static str> {
+    let name = DISTRO_ALIASES.iter()
+        .find(|(id, _)| *id == name)
+        .map_or(name, |(_, logo)| logo);
+
+    LOGOS.get(name).copied()
+}
+
+/// Reads art for `--logo-file`, where "-" reads it from stdin, e.g. `figlet hello | vega --logo-file -`
+fn read_logo_file(path: &str) -> String {
+    let art = if path == "-" {
+        let mut art = String::new();
+        io::stdin().read_to_string(&mut art).map(|_| art)
+    } else {
+        fs::read_to_string(path)
+    };
+
+    art.unwrap_or_else(|err| {
+        eprintln!("vega: can't read logo file '{}': {}", path, err);
+        exit(EXIT_NO_INPUT);
+    })
+}
+
+/// Converts plain text art, optionally colored with ANSI escapes, into the registry format by
+/// adding the size header and padding every row to the same visible width
+fn from_plain_art(art: &str) -> String {
+    let rows: Vec<&str> = art.trim_end_matches('\n').lines().collect();
+    let cols = rows.iter().map(|row| visible_width(row)).max().unwrap_or(0);
+
+    let padded: Vec<String> = rows.iter()
+        .map(|row| format!("{}{}\x1b[0m", row, " ".repeat(cols - visible_width(row))))
+        .collect();
+
+    format!("{} {}\n{}", rows.len(), cols, padded.join("\n"))
+}
+
+/// The distro's os-release `ID` followed by its `ID_LIKE` entries, most specific first
+fn get_distro_ids() -> Vec<String> {
+    match provider().uname().sysname.as_str() {
+        "Linux" => {
+            let os_release = os_release();
+            let field = |name: &str| os_release.lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+                .map(|value| value.trim().trim_matches('"').trim_matches('\'').to_string())
+                .unwrap_or_default();
+
+            // ID_LIKE is a space separated list, e.g. ID_LIKE="ubuntu debian"
+            let mut ids: Vec<String> = derivative_id().into_iter().map(str::to_string).collect();
+            ids.push(field("ID"));
+            ids.extend(field("ID_LIKE").split_whitespace().map(str::to_string));
+            ids
+        },
+        "Darwin" => vec!["macos".to_string()],
+        "FreeBSD" => vec!["freebsd".to_string()],
+        _ => Vec::new(),
+    }
+}
+
+/// Derivatives that keep their base's os-release ID (MX Linux says debian, Armbian says debian
+/// or ubuntu), recognized by the release files they add
+fn derivative_id() -> Option<&'static str> {
+    if provider().exists("/etc/armbian-release") {
+        Some("armbian")
+    } else if provider().exists("/etc/mx-version") || cat("/etc/lsb-release").lines().any(|line| line.trim() == "DISTRIB_ID=MX") {
+        Some("mx")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_utils::fixture::with_fixture;
+    use serde_json::json;
+
+    fn distro_ids(files: serde_json::Value) -> Vec<String> {
+        with_fixture(json!({ "uname": { "sysname": "Linux" }, "files": files }), get_distro_ids)
+    }
+
+    #[test]
+    fn recognizes_derivatives_that_report_their_base() {
+        let debian = "ID=debian\n";
+        assert_eq!(distro_ids(json!({ "/etc/os-release": "ID=linuxmint\nID_LIKE=\"ubuntu debian\"\n" })), ["linuxmint", "ubuntu", "debian"]);
+        assert_eq!(distro_ids(json!({ "/etc/os-release": debian })), ["debian"]);
+        assert_eq!(distro_ids(json!({ "/etc/os-release": debian, "/etc/mx-version": "MX-23.4_x64 Libretto" })), ["mx", "debian"]);
+        assert_eq!(distro_ids(json!({ "/etc/os-release": debian, "/etc/lsb-release": "DISTRIB_ID=MX\nDISTRIB_RELEASE=23.4\n" })), ["mx", "debian"]);
+        assert_eq!(distro_ids(json!({ "/etc/os-release": "ID=ubuntu\n", "/etc/armbian-release": "BOARD=rock-5b\n" })), ["armbian", "ubuntu"]);
+        assert!(find_logo("mx").is_some() && find_logo("armbian").is_some());
+    }
+}
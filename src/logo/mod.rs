@@ -1 +1,3 @@
+pub mod backend;
+pub mod graphics;
 pub mod logo;
\ No newline at end of file
@@ -1,38 +1,67 @@
-use crate::data::common::get_system_info;
-use crate::logo::logo::get_logo;
-
-mod data;
-mod _utils;
-mod logo;
+use vega::_utils::status::{exit_code, print_warnings, warn};
+use vega::_utils::terminal::strip_escapes;
+use vega::args::args::{Args, CopyMode, Subcommand};
+use vega::bench::bench::{collect_only, run_bench};
+use vega::config::context::Context;
+use vega::data::common::get_system_info;
+use vega::logo::graphics::finish_animation;
+use vega::logo::logo::{get_logo, list_logos, show_logo};
+use vega::output::clipboard::copy_to_clipboard;
+use vega::output::json::SCHEMA;
+use vega::output::render::renderer;
+use vega::output::segment::print_segment;
+use vega::theme::color::init_colors;
+use std::panic;
+use std::process::exit;
 
 fn main() {
-    
-    let mut logo = get_logo();
-    let system_info = get_system_info();
-    
-    system_info.for_each(|info| {
-        // Print logo
-        let content = logo.content.next();
-        if let Some(content) = content {
-            print!("{}", content);
-        }
-        else { 
-            print!("{}", " ".repeat(logo.cols as usize));
-        }
-        
-        // Print data
-        println!("   {}", info)
-    });
-    
-    // Finish logo
-    loop {
-        let content = logo.content.next();
-        if let Some(content) = content {
-            print!("{}", content);
-        } else {
-            break
+
+    // Parse arguments up front so bad options fail before any gathering, and likewise load a
+    // --simulate fixture before anything asks the system a question
+    let context = Context::from_args(Args::from_env());
+    context.clone().install();
+    let args = &context.args;
+    init_colors();
+
+    // Crashing modules are reported as failures, without the panic message under --quiet
+    if args.quiet {
+        panic::set_hook(Box::new(|_| {}));
+    }
+
+    match &args.subcommand {
+        Some(Subcommand::LogoList) => return list_logos(),
+        Some(Subcommand::LogoShow(name)) => return show_logo(name),
+        Some(Subcommand::Schema) => return print!("{}", SCHEMA),
+        Some(Subcommand::Bench { runs, save }) => return run_bench(*runs, *save),
+        Some(Subcommand::Segment(name)) => return print_segment(name),
+        None => {},
+    }
+
+    if let Some(timings) = args.collect_only {
+        collect_only(timings);
+        print_warnings();
+        exit(exit_code());
+    }
+
+    let renderer = renderer(&context, get_logo);
+    let output = renderer.render(&get_system_info(&context));
+    print!("{}", output);
+
+    if let Some(mode) = args.copy {
+        let text = match mode {
+            CopyMode::Plain => output.lines().map(|line| strip_escapes(line).trim_end().to_string() + "\n").collect(),
+            CopyMode::Ansi => output,
+        };
+        if let Err(err) = copy_to_clipboard(text.trim_end()) {
+            warn(&format!("can't copy to the clipboard: {}", err));
         }
     }
-    
-    println!("\n")
+
+    finish_animation();
+
+    // JSON, YAML and TOML carry the warnings in the document itself
+    if !renderer.includes_warnings() {
+        print_warnings();
+    }
+    exit(exit_code());
 }
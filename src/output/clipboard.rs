@@ -0,0 +1,56 @@
+use crate::_utils::which::search_path;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Puts text on the system clipboard, preferring the desktop's own tool and falling back to the
+/// OSC 52 escape, which most terminals honor even over SSH
+pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    // This machine's clipboard even under --simulate, the fixture has nothing to say about it
+    let tools: &[(&str, &[&str])] = if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else if env::var_os("WAYLAND_DISPLAY").is_some() {
+        &[("wl-copy", &[])]
+    } else if env::var_os("DISPLAY").is_some() {
+        &[("xclip", &["-selection", "clipboard"]), ("xsel", &["--clipboard", "--input"])]
+    } else {
+        &[]
+    };
+
+    if let Some((tool, args)) = tools.iter().find(|(tool, _)| search_path(tool).is_some()) {
+        return pipe_to(tool, args, text);
+    }
+
+    osc52(text)
+}
+
+fn pipe_to(tool: &str, args: &[&str], text: &str) -> Result<(), String> {
+    let mut child = Command::new(tool)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| format!("can't run {}: {}", tool, err))?;
+
+    child.stdin.take()
+        .ok_or(format!("can't write to {}", tool))?
+        .write_all(text.as_bytes())
+        .map_err(|err| format!("can't write to {}: {}", tool, err))?;
+
+    match child.wait() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{} exited with {}", tool, status)),
+        Err(err) => Err(format!("{} failed: {}", tool, err)),
+    }
+}
+
+/// "ESC ] 52 ; c ; <base64> BEL" sets the clipboard, written to the terminal directly so it also
+/// works when stdout is piped
+fn osc52(text: &str) -> Result<(), String> {
+    let mut tty = OpenOptions::new().write(true).open("/dev/tty").map_err(|err| format!("no terminal to copy through: {}", err))?;
+    write!(tty, "\x1b]52;c;{}\x07", BASE64.encode(text)).map_err(|err| err.to_string())
+}
@@ -0,0 +1,52 @@
+use crate::_utils::status::warnings;
+use crate::_utils::terminal::strip_escapes;
+use crate::data::common::SystemInfo;
+use serde_json::{json, Map, Value};
+
+/// Version of the machine-readable output, bumped whenever a field is removed, renamed or changes
+/// type. Adding fields doesn't bump it, so parsers should ignore fields they don't know.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// JSON Schema describing the output, shown by `vega schema`
+pub const SCHEMA: &str = include_str!("../../static/schema.json");
+
+/// The gathered stats as a JSON document matching `SCHEMA`
+pub fn to_json(info: &SystemInfo) -> Value {
+    let sections: Vec<Value> = info.sections.iter().map(|section| {
+        let stats: Vec<Value> = section.stats.iter().map(|stat| {
+            let values: Map<String, Value> = stat.value.values.iter()
+                .map(|(name, value)| (name.to_string(), json!(value)))
+                .collect();
+
+            json!({
+                "id": stat.id,
+                "label": stat.label,
+                "display": strip_escapes(&stat.value.display),
+                "values": values,
+            })
+        }).collect();
+
+        json!({
+            "id": section_id(&section.title),
+            "title": section.title,
+            "stats": stats,
+        })
+    }).collect();
+
+    let warnings: Vec<Value> = warnings().into_iter()
+        .map(|warning| json!({ "module": warning.module, "message": warning.message }))
+        .collect();
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "user": info.user,
+        "host": info.host,
+        "sections": sections,
+        "warnings": warnings,
+    })
+}
+
+/// Stable identifier for a section, e.g. "raspberry_pi" for "Raspberry Pi"
+pub fn section_id(title: &str) -> String {
+    title.to_lowercase().replace(' ', "_")
+}
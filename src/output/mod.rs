@@ -0,0 +1,7 @@
+pub mod clipboard;
+pub mod json;
+pub mod oneline;
+pub mod render;
+pub mod segment;
+pub mod serialize;
+pub mod template;
@@ -0,0 +1,56 @@
+use crate::_utils::terminal::strip_escapes;
+use crate::data::common::{Stat, SystemInfo};
+
+/// The stats with the given IDs on a single line, e.g. "arch | 6.9.3 | up 3d4h | 1842 pkgs | 12.1/32.0 GiB"
+///
+/// Stats that weren't gathered are skipped, so one field list works across machines.
+pub fn to_oneline(info: &SystemInfo, fields: &[&str], separator: &str) -> String {
+    let stats: Vec<&Stat> = info.sections.iter().flat_map(|section| &section.stats).collect();
+
+    fields.iter()
+        .filter_map(|field| match *field {
+            // The title's, which is shorter than the user stat's
+            "user" => Some(info.user.clone()),
+            "host" => Some(info.host.clone()),
+            id => stats.iter().find(|stat| stat.id == id).map(|stat| compact(stat)),
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// A stat shortened to fit a prompt, falling back to its display string
+fn compact(stat: &Stat) -> String {
+    let display = strip_escapes(&stat.value.display);
+    let value = |name: &str| stat.value.values.iter().find(|(key, _)| *key == name).map(|(_, value)| *value);
+
+    match stat.id.as_str() {
+        // "Arch Linux" -> "arch", "macOS 15.1 Sequoia" -> "macos"
+        "os" => display.split_whitespace().next().unwrap_or_default().to_lowercase(),
+        // "Linux 6.9.3" -> "6.9.3"
+        "kernel" => display.split_whitespace().last().unwrap_or_default().to_string(),
+        "uptime" => match value("seconds") {
+            Some(seconds) => format!("up {}", short_duration(seconds)),
+            None => display,
+        },
+        // "1204 (pacman), 12 (flatpak)" -> "1216 pkgs"
+        "packages" => {
+            let total: u64 = display.split(", ").filter_map(|count| count.split_whitespace().next()?.parse::<u64>().ok()).sum();
+            format!("{} {}", total, if total == 1 { "pkg" } else { "pkgs" })
+        },
+        "ram" | "disk" => match (value("used_bytes"), value("total_bytes")) {
+            (Some(used), Some(total)) => format!("{:.1}/{:.1} GiB", used as f64 / 1073741824.0, total as f64 / 1073741824.0),
+            _ => display,
+        },
+        _ => display,
+    }
+}
+
+/// The two largest units of a duration, e.g. "3d4h", "4h12m" or "12m"
+fn short_duration(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h{}m", hours, minutes),
+        _ => format!("{}d{}h", days, hours),
+    }
+}
@@ -0,0 +1,388 @@
+use crate::_utils::terminal::{stdout_is_terminal, strip_escapes, terminal_width, to_ascii, truncate_to_width, visible_width};
+use crate::args::args::{Args, Format};
+use crate::config::context::{backend, config, Context};
+use crate::data::common::{format_system_info, Stat, SystemInfo};
+use crate::data::registry::stat;
+use crate::logo::backend::Backend;
+use crate::logo::logo::Logo;
+use crate::output::json::to_json;
+use crate::output::oneline::to_oneline;
+use crate::output::serialize::serialize;
+use crate::output::template::fill_template;
+use crate::theme::color::{color_depth, requested, ColorDepth};
+use crate::theme::theme::adapt_logo_line;
+
+/// Turns the gathered stats into output. The layout lives in the renderers, so a new output target
+/// is a new renderer rather than a change to how stats are gathered or arranged.
+pub trait Renderer {
+    fn render(&self, info: &SystemInfo) -> String;
+
+    /// Whether the output carries the warnings itself, otherwise they are printed to stderr after it
+    fn includes_warnings(&self) -> bool {
+        false
+    }
+}
+
+/// The logo with the stats beside it, colored with ANSI escapes for a terminal
+pub struct AnsiRenderer {
+    pub logo: Logo,
+    /// The colors the terminal takes
+    pub depth: ColorDepth,
+    /// Blank lines before the output and columns before each line, so nothing sits flush against
+    /// the window edge of terminals without padding of their own
+    pub padding_top: usize,
+    pub padding_left: usize,
+    pub constraints: Constraints,
+    /// How to draw the thumbnails of stats with an image (the wallpaper), None when stdout isn't a
+    /// terminal that would show them
+    pub thumbnails: Option<Backend>,
+}
+
+/// The stats alone, without a logo or escapes, e.g. for logs
+pub struct PlainRenderer {
+    pub constraints: Constraints,
+}
+
+/// What the text output has to fit into, e.g. a tmux pane, a fixed-size MOTD or a serial console
+#[derive(Clone, Copy, Default)]
+pub struct Constraints {
+    /// Columns to fit into instead of the terminal's width, longer lines are cut off
+    pub width: Option<usize>,
+    pub max_lines: Option<usize>,
+    /// Only ASCII box drawing, bars and ellipses
+    pub ascii: bool,
+}
+
+impl Constraints {
+
+    /// The constraints given with `--width`, `--max-lines` and `--ascii`
+    pub fn from_args(args: &Args) -> Constraints {
+        Constraints { width: args.width, max_lines: args.max_lines, ascii: args.ascii }
+    }
+
+    fn apply(&self, lines: Vec<String>) -> Vec<String> {
+        lines.into_iter()
+            .take(self.max_lines.unwrap_or(usize::MAX))
+            .map(|line| if self.ascii { to_ascii(&line) } else { line })
+            .map(|line| match self.width {
+                Some(width) => truncate_to_width(&line, width),
+                None => line,
+            })
+            .collect()
+    }
+
+}
+
+/// The JSON document described by `vega schema`
+pub struct JsonRenderer;
+
+/// The logo and stats as a standalone HTML page, colored like in the terminal
+pub struct HtmlRenderer {
+    pub logo: Logo,
+    /// The colors of the page, usually 24-bit since the browser shows them rather than a terminal
+    pub depth: ColorDepth,
+}
+
+/// Each stat as a sentence, e.g. "Operating system: Arch Linux.", without the logo or colors so
+/// speech output reads it naturally
+pub struct AccessibleRenderer;
+
+/// A few stats on one line for shell prompts and tmux status lines, see `to_oneline`
+pub struct OnelineRenderer {
+    /// Stat IDs, plus "user" and "host" from the title
+    pub fields: Vec<String>,
+    pub separator: String,
+}
+
+/// The JSON a Waybar custom module with `"return-type": "json"` reads: a one-line summary as the
+/// bar text and the full output as its tooltip
+pub struct WaybarRenderer {
+    pub text: OnelineRenderer,
+}
+
+/// The stats laid out by a user's template, see `fill_template`
+pub struct TemplateRenderer {
+    pub template: String,
+}
+
+/// YAML, TOML, TSV or env output, see `serialize`
+pub struct StructuredRenderer {
+    pub format: Format,
+}
+
+/// The renderer for the context's `--format`, laying out with its options, config and theme
+pub fn renderer(context: &Context, logo: impl FnOnce() -> Logo) -> Box<dyn Renderer> {
+    let renderer = context.enter(|| format_renderer(&context.args, logo));
+    Box::new(InContext { context: context.clone(), renderer })
+}
+
+/// The renderer for `--format` alone, built in the context it will render in
+fn format_renderer(args: &Args, logo: impl FnOnce() -> Logo) -> Box<dyn Renderer> {
+    match args.format {
+        Format::Text => Box::new(AnsiRenderer {
+            logo: logo(),
+            depth: color_depth(),
+            padding_top: config().get_int("layout.padding_top", 0).max(0) as usize,
+            padding_left: config().get_int("layout.padding_left", 0).max(0) as usize,
+            constraints: Constraints::from_args(args),
+            thumbnails: stdout_is_terminal().then(backend),
+        }),
+        Format::Plain => Box::new(PlainRenderer { constraints: Constraints::from_args(args) }),
+        Format::Json => Box::new(JsonRenderer),
+        // Colored even when written to a file or pipe, unless colors were turned off on purpose
+        Format::Html => Box::new(HtmlRenderer {
+            logo: logo(),
+            depth: if requested() == Some(false) { ColorDepth::None } else { ColorDepth::TrueColor },
+        }),
+        Format::Accessible => Box::new(AccessibleRenderer),
+        Format::Oneline => Box::new(oneline_renderer("oneline.fields", &["os", "kernel", "uptime", "packages", "ram"])),
+        Format::Template => Box::new(TemplateRenderer { template: args.template.clone().unwrap_or_default() }),
+        Format::Waybar => Box::new(WaybarRenderer { text: oneline_renderer("waybar.fields", &["uptime", "ram"]) }),
+        format => Box::new(StructuredRenderer { format }),
+    }
+}
+
+/// Renders in a context, so the layout follows its config and theme wherever `render` is called
+struct InContext {
+    context: Context,
+    renderer: Box<dyn Renderer>,
+}
+
+impl Renderer for InContext {
+    fn render(&self, info: &SystemInfo) -> String {
+        self.context.enter(|| self.renderer.render(info))
+    }
+
+    fn includes_warnings(&self) -> bool {
+        self.renderer.includes_warnings()
+    }
+}
+
+/// A one-line renderer showing the stats listed under a config key, or the defaults
+fn oneline_renderer(key: &str, default: &[&str]) -> OnelineRenderer {
+    let config = config();
+    let fields = config.get_str_list(key);
+    let fields = if fields.is_empty() { default.to_vec() } else { fields };
+    OnelineRenderer {
+        fields: fields.into_iter().map(str::to_string).collect(),
+        separator: config.get_str("oneline.separator").unwrap_or(" | ").to_string(),
+    }
+}
+
+impl Renderer for AnsiRenderer {
+    fn render(&self, info: &SystemInfo) -> String {
+        let system_info = format_system_info(info, self.depth, self.thumbnails);
+
+        // Leave room for the padding, the stats and the gap before them so nothing wraps on narrow terminals
+        let mut logo = self.logo.clone();
+        if let Some(width) = self.constraints.width.or_else(terminal_width) {
+            let info_width = system_info.iter().map(|info| visible_width(info)).max().unwrap_or(0);
+            logo.fit_to_width(width.saturating_sub(self.padding_left + info_width + 3));
+        }
+
+        let margin = " ".repeat(self.padding_left);
+        let lines: Vec<String> = vec![String::new(); self.padding_top].into_iter()
+            .chain(side_by_side(logo, system_info, self.depth).into_iter().map(|line| format!("{}{}", margin, line)))
+            .collect();
+
+        // The blank lines after the output would only eat into a line limit
+        match self.constraints.max_lines {
+            Some(_) => self.constraints.apply(lines).join("\n") + "\n",
+            None => self.constraints.apply(lines).join("\n") + "\n\n\n",
+        }
+    }
+}
+
+impl Renderer for PlainRenderer {
+    fn render(&self, info: &SystemInfo) -> String {
+        let lines: Vec<String> = format_system_info(info, ColorDepth::None, None).iter().map(|line| strip_escapes(line)).collect();
+        self.constraints.apply(lines).into_iter()
+            .map(|line| line + "\n")
+            .collect()
+    }
+}
+
+impl Renderer for JsonRenderer {
+    fn render(&self, info: &SystemInfo) -> String {
+        format!("{:#}\n", to_json(info))
+    }
+
+    fn includes_warnings(&self) -> bool {
+        true
+    }
+}
+
+impl Renderer for HtmlRenderer {
+    fn render(&self, info: &SystemInfo) -> String {
+        // An image logo only makes sense to the terminal drawing it
+        let logo = if self.logo.graphics { Logo::default() } else { self.logo.clone() };
+
+        let lines: Vec<String> = side_by_side(logo, format_system_info(info, self.depth, None), self.depth).iter()
+            .map(|line| ansi_to_html(line))
+            .collect();
+
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}@{}</title>\n</head>\n\
+             <body style=\"background: #1e1e2e; color: #cdd6f4;\">\n<pre style=\"font-family: monospace;\">\n{}\n</pre>\n</body>\n</html>\n",
+            escape_html(&info.user), escape_html(&info.host), lines.join("\n")
+        )
+    }
+}
+
+impl Renderer for AccessibleRenderer {
+    fn render(&self, info: &SystemInfo) -> String {
+        let sentence = |name: &str, value: &str| if value.ends_with('.') {
+            format!("{}: {}\n", name, value)
+        } else {
+            format!("{}: {}.\n", name, value)
+        };
+
+        let mut output = sentence("User", &info.user) + &sentence("Host", &info.host);
+        for section in &info.sections {
+            output += &if section.title.is_empty() {
+                "\n".to_string()
+            } else {
+                format!("\n{} section.\n", section.title)
+            };
+            for Stat { id, label, value } in &section.stats {
+                let name = stat(id).map_or(label.as_str(), |stat| stat.name);
+                let name = if value.warning { format!("{} (warning)", name) } else { name.to_string() };
+                output += &sentence(&name, &strip_escapes(&value.display));
+            }
+        }
+        output
+    }
+}
+
+impl Renderer for OnelineRenderer {
+    fn render(&self, info: &SystemInfo) -> String {
+        let fields: Vec<&str> = self.fields.iter().map(String::as_str).collect();
+        to_oneline(info, &fields, &self.separator) + "\n"
+    }
+}
+
+impl Renderer for TemplateRenderer {
+    fn render(&self, info: &SystemInfo) -> String {
+        let output = fill_template(&self.template, info);
+        if output.ends_with('\n') { output } else { output + "\n" }
+    }
+}
+
+impl Renderer for WaybarRenderer {
+    fn render(&self, info: &SystemInfo) -> String {
+        // The tooltip is Pango markup, monospaced so the labels line up
+        let escape = |text: &str| text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+        let tooltip: Vec<String> = format_system_info(info, ColorDepth::None, None).iter().map(|line| escape(&strip_escapes(line))).collect();
+        let warning = info.sections.iter().flat_map(|section| &section.stats).any(|stat| stat.value.warning);
+
+        let output = serde_json::json!({
+            "text": escape(self.text.render(info).trim_end()),
+            "tooltip": format!("<tt>{}</tt>", tooltip.join("\n")),
+            "class": if warning { "warning" } else { "normal" },
+        });
+        format!("{}\n", output)
+    }
+}
+
+impl Renderer for StructuredRenderer {
+    fn render(&self, info: &SystemInfo) -> String {
+        serialize(info, &self.format)
+    }
+
+    // TSV and env have nowhere to put them
+    fn includes_warnings(&self) -> bool {
+        matches!(self.format, Format::Yaml | Format::Toml)
+    }
+}
+
+/// Each stat line with a logo line in front of it, then whatever is left of the logo
+fn side_by_side(mut logo: Logo, system_info: Vec<String>, depth: ColorDepth) -> Vec<String> {
+    let mut lines: Vec<String> = system_info.into_iter()
+        .map(|info| match logo.content.next() {
+            Some(content) => format!("{}   {}", adapt_logo_line(&content, depth), info),
+            None => format!("{}   {}", " ".repeat(logo.cols as usize), info),
+        })
+        .collect();
+
+    lines.extend(logo.content.map(|content| adapt_logo_line(&content, depth).into_owned()));
+    lines
+}
+
+/// Turns the SGR escapes vega prints (bold, dim, 16 and 24-bit foreground colors) into styled spans
+fn ansi_to_html(line: &str) -> String {
+    const COLORS: [&str; 16] = [
+        "#45475a", "#f38ba8", "#a6e3a1", "#f9e2af", "#89b4fa", "#f5c2e7", "#94e2d5", "#bac2de",
+        "#585b70", "#f38ba8", "#a6e3a1", "#f9e2af", "#89b4fa", "#f5c2e7", "#94e2d5", "#a6adc8",
+    ];
+
+    let mut html = String::new();
+    let mut open: Option<String> = None;
+    let (mut color, mut bold, mut dim) = (None, false, false);
+
+    // Spans are opened lazily with the text they style, so runs of escapes don't leave empty ones
+    let mut push_text = |html: &mut String, text: &str, style: String| {
+        if text.is_empty() {
+            return;
+        }
+        if open.as_ref() != Some(&style) {
+            if open.take().is_some() {
+                html.push_str("</span>");
+            }
+            if !style.is_empty() {
+                html.push_str(&format!("<span style=\"{}\">", style));
+                open = Some(style);
+            }
+        }
+        html.push_str(&escape_html(text));
+    };
+
+    let mut rest = line;
+    loop {
+        let style = [
+            color.as_ref().map(|color| format!("color: {}", color)),
+            bold.then(|| "font-weight: bold".to_string()),
+            dim.then(|| "opacity: 0.7".to_string()),
+        ].into_iter().flatten().collect::<Vec<_>>().join("; ");
+
+        let Some(start) = rest.find("\x1b[") else {
+            push_text(&mut html, rest, style);
+            break;
+        };
+        push_text(&mut html, &rest[..start], style);
+
+        let sequence = &rest[start + 2..];
+        let Some(end) = sequence.find(|c: char| c.is_ascii_alphabetic()) else {
+            break;
+        };
+        rest = &sequence[end + 1..];
+        if !sequence[end..].starts_with('m') {
+            continue;
+        }
+
+        let mut params = sequence[..end].split(';').map(|param| param.parse::<u8>().unwrap_or(0));
+        while let Some(param) = params.next() {
+            match param {
+                0 => (color, bold, dim) = (None, false, false),
+                1 => bold = true,
+                2 => dim = true,
+                30..=37 => color = Some(COLORS[(param - 30) as usize].to_string()),
+                90..=97 => color = Some(COLORS[(param - 82) as usize].to_string()),
+                38 if params.next() == Some(2) => {
+                    let [r, g, b] = [params.next(), params.next(), params.next()].map(Option::unwrap_or_default);
+                    color = Some(format!("#{:02x}{:02x}{:02x}", r, g, b));
+                },
+                39 => color = None,
+                _ => {},
+            }
+        }
+    }
+
+    if open.is_some() {
+        html.push_str("</span>");
+    }
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
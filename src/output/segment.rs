@@ -0,0 +1,82 @@
+use crate::_utils::status::EXIT_USAGE;
+use crate::_utils::system_provider::provider;
+use crate::config::config::cache_dir;
+use crate::config::context::{args, config, theme};
+use crate::data::registry::{stat, Samples};
+use crate::theme::color::{adapt, adapt_escapes, color_depth, force_colors, ColorDepth};
+use crate::theme::theme::parse_color;
+use colored::{Color, Colorize};
+use std::fs;
+use std::process::exit;
+use std::time::{Duration, SystemTime};
+
+/// Prints one stat as a colored segment for a prompt or status line, e.g. `vega segment uptime` from
+/// a starship custom command or tmux's `status-right`
+///
+/// Prompts run this on every keystroke, so the segment is cached for `segments.ttl` seconds (60 by
+/// default) and only the one stat is gathered when it's stale.
+pub fn print_segment(name: &str) {
+    let Some(def) = stat(name) else {
+        eprintln!("vega: unknown segment '{}', expected a stat ID such as os, uptime or ram", name);
+        exit(EXIT_USAGE);
+    };
+
+    // Prompts capture the output, which would otherwise turn the colors off. `--color never` and
+    // NO_COLOR still do, for cached segments as well.
+    force_colors(None);
+
+    let config = config();
+    let setting = |key: &str| config.get(&format!("segments.{}.{}", name, key)).or_else(|| config.get(&format!("segments.{}", key)));
+    let ttl = setting("ttl").and_then(|ttl| ttl.as_integer()).unwrap_or(60).max(0) as u64;
+    // The cache holds the real machine's unredacted values, neither a fixture's nor redacted ones
+    let path = cache_dir()
+        .filter(|_| !args().anonymize && !provider().is_simulated())
+        .map(|dir| dir.join("segments").join(name));
+
+    let cached = path.as_ref()
+        .filter(|path| fs::metadata(path).ok()
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age < Duration::from_secs(ttl)))
+        .and_then(|path| fs::read_to_string(path).ok());
+    if let Some(segment) = cached {
+        return print!("{}", adapt_escapes(&segment, color_depth()));
+    }
+
+    // Asked for by name, so the stat's config toggle doesn't apply
+    let Some(value) = (def.gather)(&Samples::for_stat(name)).into_iter().next() else {
+        return;
+    };
+    let text = setting("format").and_then(|format| format.as_str()).unwrap_or("{value}")
+        .replace("{value}", &value.display)
+        .replace("{label}", def.label)
+        .replace("{icon}", def.icon)
+        .replace("{name}", def.name);
+
+    let color = setting("color").and_then(|color| color.as_str()).and_then(parse_color)
+        .unwrap_or(if value.warning { theme().warning } else { theme().accent });
+    let style = setting("style").and_then(|style| style.as_str()).unwrap_or("ansi");
+    let segment = match style {
+        // tmux doesn't interpret escapes in #() output, only its own #[...] styles
+        "tmux" => format!("#[fg={}]{}#[default]", tmux_color(color), text),
+        "none" => text,
+        _ => text.color(adapt(color)).to_string(),
+    };
+
+    // The cache is only an optimization, failing to write it just means gathering again next time.
+    // Colors turned off for this run would stick around, so those segments aren't kept.
+    if let Some(path) = path.filter(|_| matches!(style, "tmux" | "none") || color_depth() != ColorDepth::None) {
+        let _ = path.parent().map(fs::create_dir_all);
+        let _ = fs::write(path, &segment);
+    }
+    print!("{}", segment);
+}
+
+/// A color as tmux names it, e.g. "brightblue" or "#bd93f9"
+fn tmux_color(color: Color) -> String {
+    match color {
+        Color::TrueColor { r, g, b } => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        Color::AnsiColor(n) => format!("colour{}", n),
+        color => format!("{:?}", color).to_lowercase(),
+    }
+}
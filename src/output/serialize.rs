@@ -0,0 +1,260 @@
+use crate::_utils::terminal::strip_escapes;
+use crate::args::args::Format;
+use crate::config::context::args;
+use crate::data::common::{Stat, StatValue, SystemInfo};
+use crate::output::json::{to_json, SCHEMA_VERSION};
+use serde_json::Value;
+
+/// The gathered stats in a machine-readable format. The structured formats are written from the same
+/// JSON document, so they all share its schema and `schema_version`.
+pub fn serialize(info: &SystemInfo, format: &Format) -> String {
+    match format {
+        Format::Tsv => return to_tsv(info),
+        Format::Env => return to_env(info),
+        Format::Eww => return to_eww(info),
+        Format::Conky => return to_conky(info),
+        _ => {},
+    }
+
+    let document = to_json(info);
+
+    match format {
+        Format::Yaml => yaml_lines(&document).join("\n") + "\n",
+        Format::Toml => toml::to_string(&without_nulls(document)).unwrap_or_default(),
+        _ => format!("{:#}\n", document),
+    }
+}
+
+/// TOML has no null, so keys without a value are left out instead
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(map.into_iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(key, value)| (key, without_nulls(value)))
+            .collect()),
+        Value::Array(items) => Value::Array(items.into_iter().map(without_nulls).collect()),
+        value => value,
+    }
+}
+
+/// Block-style YAML for a map or list, one line per scalar
+fn yaml_lines(value: &Value) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    match value {
+        Value::Object(map) => for (key, value) in map {
+            match yaml_scalar(value) {
+                Some(scalar) => lines.push(format!("{}: {}", key, scalar)),
+                None => {
+                    lines.push(format!("{}:", key));
+                    lines.extend(yaml_lines(value).into_iter().map(|line| format!("  {}", line)));
+                },
+            }
+        },
+        Value::Array(items) => for item in items {
+            match yaml_scalar(item) {
+                Some(scalar) => lines.push(format!("- {}", scalar)),
+                // The item's first line goes on the dash, the rest line up with it
+                None => lines.extend(yaml_lines(item).into_iter().enumerate().map(|(i, line)| {
+                    format!("{} {}", if i == 0 { "-" } else { " " }, line)
+                })),
+            }
+        },
+        scalar => lines.extend(yaml_scalar(scalar)),
+    }
+
+    lines
+}
+
+/// A value that fits on one line, or None for a non-empty map or list. Strings are always double
+/// quoted, JSON's string escapes are valid YAML too.
+fn yaml_scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(map) if map.is_empty() => Some("{}".to_string()),
+        Value::Array(items) if items.is_empty() => Some("[]".to_string()),
+        Value::Object(_) | Value::Array(_) => None,
+        scalar => Some(scalar.to_string()),
+    }
+}
+
+/// Every stat keyed by its label, numbering repeated labels (HDD, HDD2, ...) so each key is unique
+fn numbered_stats(info: &SystemInfo) -> Vec<(String, &StatValue)> {
+    numbered_by(info, |stat| &stat.label, "")
+}
+
+/// Every stat keyed by its ID, numbering repeated IDs (disk, disk_2, ...) so each key is unique
+fn numbered_ids(info: &SystemInfo) -> Vec<(String, &StatValue)> {
+    numbered_by(info, |stat| &stat.id, "_")
+}
+
+fn numbered_by<'a>(info: &'a SystemInfo, key: fn(&Stat) -> &String, separator: &str) -> Vec<(String, &'a StatValue)> {
+    let stats: Vec<_> = info.sections.iter().flat_map(|section| &section.stats).collect();
+
+    stats.iter().enumerate().map(|(i, stat)| {
+        let repeats = stats[..i].iter().filter(|previous| key(previous) == key(stat)).count();
+        let key = if repeats == 0 { key(stat).clone() } else { format!("{}{}{}", key(stat), separator, repeats + 1) };
+        (key, &stat.value)
+    }).collect()
+}
+
+/// A header line and a single row of tab separated values, so rows from many hosts can simply be
+/// concatenated. Columns are `user`, `host` and stat labels, either those given with `--fields` or
+/// every stat shown.
+fn to_tsv(info: &SystemInfo) -> String {
+    let mut columns = vec![("user".to_string(), info.user.clone()), ("host".to_string(), info.host.clone())];
+    columns.extend(numbered_stats(info).into_iter().map(|(label, value)| (label, strip_escapes(&value.display))));
+
+    let args = args();
+    let fields: Vec<&str> = match &args.fields {
+        Some(fields) => fields.split(',').map(str::trim).collect(),
+        None => columns.iter().map(|(column, _)| column.as_str()).collect(),
+    };
+
+    // A tab or newline inside a value would shift every column after it
+    let row: Vec<String> = fields.iter()
+        .map(|field| columns.iter()
+            .find(|(column, _)| column.eq_ignore_ascii_case(field))
+            .map(|(_, value)| value.replace(['\t', '\n', '\r'], " "))
+            .unwrap_or_default())
+        .collect();
+
+    format!("{}\n{}\n", fields.join("\t"), row.join("\t"))
+}
+
+/// `VEGA_<LABEL>="value"` lines to `eval` in a shell script, plus `VEGA_<LABEL>_<VALUE>=<number>`
+/// for typed values, e.g. VEGA_RAM_USED_BYTES
+fn to_env(info: &SystemInfo) -> String {
+    let mut lines = vec![
+        format!("VEGA_SCHEMA_VERSION={}", SCHEMA_VERSION),
+        format!("VEGA_USER={}", shell_quote(&info.user)),
+        format!("VEGA_HOST={}", shell_quote(&info.host)),
+    ];
+
+    for (label, value) in numbered_stats(info) {
+        let name = env_name(&label);
+        lines.push(format!("VEGA_{}={}", name, shell_quote(&strip_escapes(&value.display))));
+        lines.extend(value.values.iter().map(|(key, number)| format!("VEGA_{}_{}={}", name, env_name(key), number)));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// `(defvar vega_<id> "value")` lines for an eww config to `(include ...)`, plus
+/// `vega_<id>_<value>` numbers for typed values, e.g. vega_ram_used_bytes
+fn to_eww(info: &SystemInfo) -> String {
+    // Yuck strings take the same escapes as JSON
+    let string = |value: &str| Value::String(value.to_string()).to_string();
+
+    let mut lines = vec![
+        format!("(defvar vega_user {})", string(&info.user)),
+        format!("(defvar vega_host {})", string(&info.host)),
+    ];
+    for (id, value) in numbered_ids(info) {
+        lines.push(format!("(defvar vega_{} {})", id, string(&strip_escapes(&value.display))));
+        lines.extend(value.values.iter().map(|(key, number)| format!("(defvar vega_{}_{} {})", id, key, number)));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// The text output in conky markup, for `${execpi 60 vega --export conky}`: the title and section
+/// titles in `color1`, labels in `color2` and values lined up with `${goto}`
+fn to_conky(info: &SystemInfo) -> String {
+    // execpi parses the output as conky text, where $ and # are special
+    let escape = |text: &str| strip_escapes(text).replace('$', "$$").replace('#', "\\#");
+
+    let mut lines = vec![format!("${{color1}}{}@{}${{color}}", escape(&info.user), escape(&info.host))];
+    for section in &info.sections {
+        lines.push(String::new());
+        if !section.title.is_empty() {
+            lines.push(format!("${{color1}}{}${{color}}", escape(&section.title)));
+        }
+        for stat in &section.stats {
+            lines.push(format!("${{color2}}{}${{color}}${{goto 48}}{}", escape(&stat.label), escape(&stat.value.display)));
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// A label as a variable name, e.g. "I/O" becomes "I_O"
+fn env_name(label: &str) -> String {
+    label.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
+
+/// Double quotes a value for POSIX shells, escaping the characters that are still special inside
+/// them. Newlines need no escaping there, they stay part of the value.
+fn shell_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::common::format_section;
+    use std::process::Command;
+
+    /// Values with everything a shell or a TSV parser could trip over
+    const TRICKY: [&str; 6] = [
+        "say \"hi\"",
+        "$HOME and ${PATH} and $(id) and `id`",
+        "back\\slash\\",
+        "two\nlines",
+        "tab\tseparated",
+        "it's 100% #1!",
+    ];
+
+    fn info() -> SystemInfo {
+        let stats = TRICKY.iter().enumerate()
+            .map(|(i, value)| Stat { id: format!("s{}", i), label: format!("S{}", i), value: StatValue::from(value.to_string()) })
+            .collect();
+        SystemInfo { user: "alice".to_string(), host: "o'brien".to_string(), sections: vec![format_section("Software", stats)] }
+    }
+
+    #[test]
+    fn env_values_survive_eval() {
+        let env = to_env(&info());
+        let mut names = vec!["VEGA_USER".to_string(), "VEGA_HOST".to_string()];
+        names.extend((0..TRICKY.len()).map(|i| format!("VEGA_S{}", i)));
+        // Each value followed by a NUL, which none of them contain
+        let script = names.iter().map(|name| format!("printf '%s\\0' \"${}\"", name)).collect::<Vec<_>>().join("; ");
+
+        let output = Command::new("sh").arg("-c").arg(format!("eval \"$1\"; {}", script)).arg("sh").arg(&env).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        let values: Vec<&str> = std::str::from_utf8(&output.stdout).unwrap().split_terminator('\0').collect();
+        let mut expected = vec!["alice", "o'brien"];
+        expected.extend(TRICKY);
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn quotes_for_the_shell() {
+        assert_eq!(shell_quote("a \"b\" $c `d` \\e"), "\"a \\\"b\\\" \\$c \\`d\\` \\\\e\"");
+        assert_eq!(shell_quote(""), "\"\"");
+    }
+
+    #[test]
+    fn tsv_keeps_one_value_per_column() {
+        let tsv = to_tsv(&info());
+        let lines: Vec<&str> = tsv.lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        let (header, row): (Vec<&str>, Vec<&str>) = (lines[0].split('\t').collect(), lines[1].split('\t').collect());
+        assert_eq!(header, ["user", "host", "S0", "S1", "S2", "S3", "S4", "S5"]);
+        assert_eq!(row.len(), header.len());
+        // Quotes and $ are kept as they are, tabs and newlines become spaces
+        assert_eq!(row[2..], ["say \"hi\"", TRICKY[1], "back\\slash\\", "two lines", "tab separated", "it's 100% #1!"]);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::_utils::terminal::strip_escapes;
+use crate::data::common::{Stat, SystemInfo};
+
+/// Fills a user's template, e.g. "{os} on {kernel}, up {uptime}"
+///
+/// `{id}` is a stat's value as displayed and `{id.value}` one of its typed values (e.g.
+/// `{ram.used_bytes}`), `{user}` and `{host}` are the title's, and `{{`/`}}` are literal braces.
+/// Placeholders for stats that weren't gathered are left empty.
+pub fn fill_template(template: &str, info: &SystemInfo) -> String {
+    let stats: Vec<&Stat> = info.sections.iter().flat_map(|section| &section.stats).collect();
+    let lookup = |placeholder: &str| -> String {
+        let (id, value) = match placeholder.split_once('.') {
+            Some((id, value)) => (id, Some(value)),
+            None => (placeholder, None),
+        };
+
+        match (id, value) {
+            ("user", None) => info.user.clone(),
+            ("host", None) => info.host.clone(),
+            (id, None) => stats.iter().find(|stat| stat.id == id)
+                .map(|stat| strip_escapes(&stat.value.display))
+                .unwrap_or_default(),
+            (id, Some(value)) => stats.iter().find(|stat| stat.id == id)
+                .and_then(|stat| stat.value.values.iter().find(|(name, _)| *name == value))
+                .map(|(_, number)| number.to_string())
+                .unwrap_or_default(),
+        }
+    };
+
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(after) = rest.strip_prefix("{{") {
+            filled.push('{');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("}}") {
+            filled.push('}');
+            rest = after;
+        } else if let Some(end) = rest.find('}').filter(|_| rest.starts_with('{')) {
+            filled.push_str(&lookup(rest[1..end].trim()));
+            rest = &rest[end + 1..];
+        } else {
+            // A lone brace is kept as it is
+            filled.push_str(&rest[..1]);
+            rest = &rest[1..];
+        }
+    }
+    filled.push_str(rest);
+
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::common::{format_section, StatValue};
+
+    fn info() -> SystemInfo {
+        let stat = |id: &str, value: StatValue| Stat { id: id.to_string(), label: id.to_uppercase(), value };
+        SystemInfo {
+            user: "alice".to_string(),
+            host: "archbox".to_string(),
+            sections: vec![format_section("Software", vec![
+                stat("os", StatValue::from("Arch Linux".to_string())),
+                stat("ram", StatValue::from("\x1b[1m4096MB / 16384MB\x1b[0m".to_string()).with("used_bytes", 4294967296)),
+            ])],
+        }
+    }
+
+    #[test]
+    fn fills_stats_values_and_the_title() {
+        assert_eq!(fill_template("{user}@{host}: {os}, { ram }", &info()), "alice@archbox: Arch Linux, 4096MB / 16384MB");
+        assert_eq!(fill_template("{ram.used_bytes} bytes", &info()), "4294967296 bytes");
+    }
+
+    #[test]
+    fn leaves_unknown_and_missing_placeholders_empty() {
+        assert_eq!(fill_template("[{nonsense}]", &info()), "[]");
+        // A real stat that wasn't gathered, and a value the stat doesn't have
+        assert_eq!(fill_template("[{kernel}] [{os.seconds}] [{ram.total_bytes}]", &info()), "[] [] []");
+    }
+
+    #[test]
+    fn unescapes_braces_and_keeps_lone_ones() {
+        assert_eq!(fill_template("{{os}} is {os}", &info()), "{os} is Arch Linux");
+        assert_eq!(fill_template("}} {{{os}}}", &info()), "} {Arch Linux}");
+        assert_eq!(fill_template("a } b { c", &info()), "a } b { c");
+        assert_eq!(fill_template("unclosed {os", &info()), "unclosed {os");
+    }
+}
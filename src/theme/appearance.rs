@@ -0,0 +1,63 @@
+use crate::_utils::system_provider::provider;
+use crate::_utils::read_file::{cat, expand_home};
+use crate::_utils::which::which;
+use crate::sh;
+use std::env;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Appearance {
+    Dark,
+    Light,
+    Unknown,
+}
+
+impl fmt::Display for Appearance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Appearance::Dark => "Dark",
+            Appearance::Light => "Light",
+            Appearance::Unknown => "Unknown",
+        })
+    }
+}
+
+/// The OS-wide light/dark appearance preference, see `appearance()` for the one of the current context
+pub fn get_appearance() -> Appearance {
+
+    // macOS only sets AppleInterfaceStyle while dark mode is on
+    if provider().uname().sysname.as_str() == "Darwin" {
+        return match sh!("defaults", "read", "-g", "AppleInterfaceStyle").stdout.trim() {
+            "Dark" => Appearance::Dark,
+            _ => Appearance::Light,
+        };
+    }
+
+    // GNOME and most GTK desktops: 'prefer-dark', 'prefer-light' or 'default'
+    if which("gsettings").is_some() {
+        let scheme = sh!("gsettings", "get", "org.gnome.desktop.interface", "color-scheme");
+        match scheme.stdout.trim().trim_matches('\'') {
+            "prefer-dark" => return Appearance::Dark,
+            "prefer-light" | "default" if scheme.err_code == 0 => return Appearance::Light,
+            _ => {},
+        }
+    }
+
+    // KDE: the color scheme name in kdeglobals' [General] group, e.g. "BreezeDark" or "BreezeLight"
+    let kdeglobals = cat(&expand_home("~/.config/kdeglobals").to_string_lossy());
+    let scheme = kdeglobals.lines()
+        .skip_while(|line| line.trim() != "[General]")
+        .skip(1)
+        .take_while(|line| !line.trim().starts_with('['))
+        .find_map(|line| line.trim().strip_prefix("ColorScheme="));
+    if let Some(scheme) = scheme.map(str::to_lowercase).filter(|scheme| !scheme.is_empty()) {
+        return if scheme.contains("dark") { Appearance::Dark } else { Appearance::Light };
+    }
+
+    // Last resort, a forced GTK theme variant like "Adwaita:dark"
+    match env::var("GTK_THEME") {
+        Ok(theme) if theme.to_lowercase().contains("dark") => Appearance::Dark,
+        _ => Appearance::Unknown,
+    }
+
+}
@@ -0,0 +1,38 @@
+use crate::_utils::terminal::query_terminal;
+use crate::theme::appearance::Appearance;
+use std::sync::LazyLock;
+
+/// Whether the terminal background is light or dark, queried once per run with OSC 11
+pub static TERMINAL_BACKGROUND: LazyLock<Appearance> = LazyLock::new(query_terminal_background);
+
+fn query_terminal_background() -> Appearance {
+
+    // The reply is terminated by either BEL or ST (ESC \)
+    let response = query_terminal("\x1b]11;?\x1b\\", |response| response.ends_with(b"\x07") || response.ends_with(b"\x1b\\"));
+
+    match response.as_deref().and_then(parse_rgb_luminance) {
+        Some(luminance) if luminance > 0.5 => Appearance::Light,
+        Some(_) => Appearance::Dark,
+        None => Appearance::Unknown,
+    }
+
+}
+
+/// Parses the relative luminance (0.0 - 1.0) out of a reply like `ESC ]11;rgb:1e1e/1e1e/2e2e BEL`
+fn parse_rgb_luminance(response: &str) -> Option<f64> {
+    let rgb = response.split("rgb:").nth(1)?;
+    let channels: Vec<f64> = rgb.split('/')
+        .take(3)
+        .map(|channel| {
+            // Each channel has 1-4 hex digits, scale it by its own maximum
+            let digits: String = channel.chars().take_while(char::is_ascii_hexdigit).take(4).collect();
+            let max = (1u32 << (4 * digits.len())) - 1;
+            u32::from_str_radix(&digits, 16).ok().map(|value| value as f64 / max as f64)
+        })
+        .collect::<Option<_>>()?;
+
+    match channels[..] {
+        [r, g, b] => Some(0.2126 * r + 0.7152 * g + 0.0722 * b),
+        _ => None,
+    }
+}
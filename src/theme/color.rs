@@ -0,0 +1,275 @@
+use crate::_utils::terminal::stdout_is_terminal;
+use crate::config::context::args;
+use colored::Color;
+use regex::Regex;
+use crate::sh;
+use std::env;
+use std::sync::{LazyLock, OnceLock};
+
+/// How many colors the output can use, from none to 24-bit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    None,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+/// How many colors the terminal takes, looked up once, see `terminal_depth`
+static TERMINAL_DEPTH: LazyLock<ColorDepth> = LazyLock::new(terminal_depth);
+
+/// How `paint` sets text apart besides its color
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Regular,
+    Bold,
+    Dimmed,
+}
+
+/// Set by output that isn't shown by this terminal as is, see `force_colors`
+static FORCED: OnceLock<ColorDepth> = OnceLock::new();
+
+/// The colors the theme's 24-bit colors fall back to on 16-color terminals, as xterm shows them
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::White, (229, 229, 229)),
+    (Color::BrightBlack, (127, 127, 127)),
+    (Color::BrightRed, (255, 0, 0)),
+    (Color::BrightGreen, (0, 255, 0)),
+    (Color::BrightYellow, (255, 255, 0)),
+    (Color::BrightBlue, (92, 92, 255)),
+    (Color::BrightMagenta, (255, 0, 255)),
+    (Color::BrightCyan, (0, 255, 255)),
+    (Color::BrightWhite, (255, 255, 255)),
+];
+
+/// The depth for vega's own stdout, from `--color`, NO_COLOR/CLICOLOR(_FORCE), whether stdout is a
+/// terminal, and then COLORTERM, TERM and terminfo for how many colors it takes
+pub fn color_depth() -> ColorDepth {
+    FORCED.get().copied().unwrap_or_else(detect)
+}
+
+/// Hands the depth to `colored`, which otherwise makes its own (NO_COLOR and tty only) decision
+pub fn init_colors() {
+    colored::control::set_override(color_depth() != ColorDepth::None);
+}
+
+/// Colors output that's captured or saved rather than shown by this terminal as is (prompt segments,
+/// HTML pages), at `depth` or the terminal's own depth when None, unless colors were turned off with
+/// `--color never`, NO_COLOR or CLICOLOR=0. Only the first call counts.
+pub fn force_colors(depth: Option<ColorDepth>) {
+    let depth = match requested() {
+        Some(false) => ColorDepth::None,
+        _ => depth.unwrap_or_else(|| TERMINAL_DEPTH.max(ColorDepth::Ansi16)),
+    };
+    let _ = FORCED.set(depth);
+    init_colors();
+}
+
+fn detect() -> ColorDepth {
+    match requested() {
+        Some(false) => ColorDepth::None,
+        Some(true) => TERMINAL_DEPTH.max(ColorDepth::Ansi16),
+        None if !stdout_is_terminal() => ColorDepth::None,
+        None => *TERMINAL_DEPTH,
+    }
+}
+
+/// Whether colors were turned on or off on purpose, with `--color` or else the environment, None
+/// when it's up to the terminal
+pub fn requested() -> Option<bool> {
+    let set = |name: &str| env::var(name).is_ok_and(|value| !value.is_empty() && value != "0");
+
+    if args().color.is_some() {
+        return args().color;
+    }
+
+    // https://no-color.org and https://bixense.com/clicolors, CLICOLOR_FORCE winning over both
+    if set("CLICOLOR_FORCE") {
+        return Some(true);
+    }
+    if set("NO_COLOR") || env::var("CLICOLOR").is_ok_and(|value| value == "0") {
+        return Some(false);
+    }
+
+    None
+}
+
+/// How many colors the terminal takes: COLORTERM announces 24-bit support, TERM usually has the
+/// rest (`xterm-256color`, `*-direct`, `dumb`), and terminfo answers for the others
+fn terminal_depth() -> ColorDepth {
+    if env::var("COLORTERM").is_ok_and(|value| value == "truecolor" || value == "24bit") {
+        return ColorDepth::TrueColor;
+    }
+
+    let term = env::var("TERM").unwrap_or_default();
+    match term.as_str() {
+        "dumb" => return ColorDepth::None,
+        term if term.ends_with("-direct") => return ColorDepth::TrueColor,
+        term if term.contains("256color") => return ColorDepth::Ansi256,
+        _ => {},
+    }
+
+    // tput only knows what terminfo says about TERM, without one it's a plain 16 color terminal
+    let colors = (!term.is_empty())
+        .then(|| sh!("tput", "colors").stdout.trim().parse::<u32>().ok())
+        .flatten();
+    match colors {
+        Some(colors) if colors >= 1 << 24 => ColorDepth::TrueColor,
+        Some(colors) if colors >= 256 => ColorDepth::Ansi256,
+        Some(colors) if colors < 8 => ColorDepth::None,
+        _ => ColorDepth::Ansi16,
+    }
+}
+
+/// The closest color vega's stdout can show, see `adapt_to`
+pub fn adapt(color: Color) -> Color {
+    adapt_to(color, color_depth())
+}
+
+/// The closest color output at `depth` can show, 24-bit ones become the nearest of the 256 or 16 colors
+pub fn adapt_to(color: Color, depth: ColorDepth) -> Color {
+    match (depth, color) {
+        (ColorDepth::Ansi256, Color::TrueColor { r, g, b }) => Color::AnsiColor(ansi_256(r, g, b)),
+        (ColorDepth::Ansi16 | ColorDepth::None, Color::TrueColor { r, g, b }) => ansi_16((r, g, b)),
+        (ColorDepth::Ansi16 | ColorDepth::None, Color::AnsiColor(index)) => ansi_16(ansi_256_rgb(index)),
+        _ => color,
+    }
+}
+
+/// Removes the color and style escapes from a line when output at `depth` has no colors, e.g. the
+/// ones baked into logos, leaving other escapes (cursor movement, images) alone
+pub fn adapt_escapes(line: &str, depth: ColorDepth) -> String {
+    static SGR: LazyLock<Regex> = LazyLock::new(|| Regex::new("\x1b\\[[0-9;]*m").unwrap());
+
+    match depth {
+        ColorDepth::None => SGR.replace_all(line, "").into_owned(),
+        _ => line.to_string(),
+    }
+}
+
+/// Text in a color (or the default one), bold or dimmed, as output at `depth` shows it. Unlike
+/// `colored`, this doesn't depend on whether vega's own stdout takes colors, so output that isn't
+/// printed (HTML pages, a library user's) gets the depth it asks for.
+pub fn paint(text: &str, color: Option<Color>, style: Style, depth: ColorDepth) -> String {
+    if depth == ColorDepth::None {
+        return text.to_string();
+    }
+
+    let codes: Vec<String> = [
+        (style == Style::Bold).then(|| "1".to_string()),
+        (style == Style::Dimmed).then(|| "2".to_string()),
+        color.map(|color| adapt_to(color, depth).to_fg_str().into_owned()),
+    ].into_iter().flatten().collect();
+
+    if codes.is_empty() {
+        return text.to_string();
+    }
+    format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text)
+}
+
+/// The index of the closest color in xterm's 6x6x6 cube or grayscale ramp
+fn ansi_256(r: u8, g: u8, b: u8) -> u8 {
+    // The cube's levels are 0, 95, 135, 175, 215 and 255
+    let level = |value: u8| if value < 48 { 0 } else if value < 115 { 1 } else { (value - 35) / 40 };
+    let cube = 16 + 36 * level(r) + 6 * level(g) + level(b);
+
+    // The ramp runs from 8 to 238 in steps of 10
+    let average = (r as u16 + g as u16 + b as u16) / 3;
+    let gray = 232 + ((average.saturating_sub(3)) / 10).min(23) as u8;
+
+    if distance(ansi_256_rgb(gray), (r, g, b)) < distance(ansi_256_rgb(cube), (r, g, b)) { gray } else { cube }
+}
+
+/// The RGB value of one of the 256 colors, the first 16 as xterm shows them
+fn ansi_256_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_16[index as usize].1,
+        16..=231 => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let index = index - 16;
+            (level(index / 36), level(index / 6 % 6), level(index % 6))
+        },
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        },
+    }
+}
+
+fn ansi_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI_16.iter()
+        .min_by_key(|(_, ansi)| distance(*ansi, rgb))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::White)
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let channel = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    channel(r1, r2) + channel(g1, g2) + channel(b1, b2)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_nearest_cube_level() {
+        assert_eq!(ansi_256(0, 0, 0), 16);
+        // The first level is 95, so 47 is still closer to 0 and 48 already closer to 95
+        assert_eq!(ansi_256(47, 255, 255), 51);
+        assert_eq!(ansi_256(48, 255, 255), 87);
+        // Between 95 and 135 the midpoint is 115
+        assert_eq!(ansi_256(114, 255, 255), 87);
+        assert_eq!(ansi_256(115, 255, 255), 123);
+        assert_eq!(ansi_256(255, 255, 255), 231);
+        assert_eq!(ansi_256(255, 0, 0), 196);
+    }
+
+    #[test]
+    fn picks_the_grayscale_ramp_for_grays() {
+        assert_eq!(ansi_256(8, 8, 8), 232);
+        assert_eq!(ansi_256(238, 238, 238), 255);
+        assert_eq!(ansi_256(128, 128, 128), 244);
+        // Dark tints are closer to a gray than to black
+        assert_eq!(ansi_256(47, 0, 0), 233);
+    }
+
+    #[test]
+    fn gives_the_rgb_of_each_range() {
+        assert_eq!(ansi_256_rgb(0), (0, 0, 0));
+        assert_eq!(ansi_256_rgb(15), (255, 255, 255));
+        assert_eq!(ansi_256_rgb(16), (0, 0, 0));
+        assert_eq!(ansi_256_rgb(87), (95, 255, 255));
+        assert_eq!(ansi_256_rgb(231), (255, 255, 255));
+        assert_eq!(ansi_256_rgb(232), (8, 8, 8));
+        assert_eq!(ansi_256_rgb(255), (238, 238, 238));
+        // Every cube and ramp color maps back to itself
+        for index in 16..=255 {
+            let (r, g, b) = ansi_256_rgb(index);
+            assert_eq!(ansi_256(r, g, b), index);
+        }
+    }
+
+    #[test]
+    fn picks_the_nearest_of_the_16_colors() {
+        assert_eq!(ansi_16((0, 0, 0)), Color::Black);
+        assert_eq!(ansi_16((8, 8, 8)), Color::Black);
+        assert_eq!(ansi_16((238, 238, 238)), Color::White);
+        assert_eq!(ansi_16((255, 255, 255)), Color::BrightWhite);
+        assert_eq!(adapt_to(Color::AnsiColor(196), ColorDepth::Ansi16), Color::BrightRed);
+        assert_eq!(adapt_to(Color::TrueColor { r: 255, g: 255, b: 255 }, ColorDepth::Ansi256), Color::AnsiColor(231));
+    }
+
+    #[test]
+    fn paints_at_the_given_depth() {
+        assert_eq!(paint("vega", Some(Color::Red), Style::Bold, ColorDepth::Ansi16), "\x1b[1;31mvega\x1b[0m");
+        assert_eq!(paint("vega", Some(Color::TrueColor { r: 255, g: 0, b: 0 }), Style::Regular, ColorDepth::Ansi256), "\x1b[38;5;196mvega\x1b[0m");
+        assert_eq!(paint("vega", Some(Color::Red), Style::Bold, ColorDepth::None), "vega");
+    }
+}
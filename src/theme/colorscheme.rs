@@ -0,0 +1,61 @@
+use crate::_utils::read_file::expand_home;
+use crate::config::config::cache_home;
+use std::fs;
+use toml::{Table, Value};
+
+/// Builds a theme table from pywal's generated `~/.cache/wal/colors.json`
+///
+/// pywal follows the terminal color order, so color1-6 are red, green, yellow, blue, magenta and
+/// cyan, 9-14 their light variants, and 4/6 are used for the accent and labels and 1 for warnings.
+pub fn pywal_theme() -> Option<Table> {
+    let content = fs::read_to_string(cache_home()?.join("wal").join("colors.json")).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+
+    let colors = json.get("colors")?;
+    let color = |n: u8| colors.get(format!("color{}", n))?.as_str().map(str::to_string);
+
+    theme_table(color(4)?, color(6)?, color(1)?, [
+        ("red", color(1)), ("green", color(2)), ("yellow", color(3)),
+        ("blue", color(4)), ("magenta", color(5)), ("cyan", color(6)), ("grey", color(7)),
+        ("lightred", color(9)), ("lightgreen", color(10)), ("lightyellow", color(11)),
+        ("lightblue", color(12)), ("lightmagenta", color(13)), ("lightcyan", color(14)), ("white", color(15)),
+    ])
+}
+
+/// Builds a theme table from a base16 scheme YAML file (`base00: "181818"` ...)
+///
+/// Only the flat `baseXX: hex` lines are needed, so the file is read line by line rather than with
+/// a full YAML parser.
+pub fn base16_theme(path: &str) -> Option<Table> {
+    let content = fs::read_to_string(expand_home(path)).ok()?;
+    let colors: Vec<(String, String)> = content.lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let value = value.split('#').next()?.trim().trim_matches(|c| c == '"' || c == '\'');
+            Some((key.trim().to_lowercase(), format!("#{}", value.trim_start_matches('#'))))
+        })
+        .collect();
+    let color = |key: &str| colors.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+
+    // base08-0E are the accent hues, base03 a muted grey and base07 the brightest foreground
+    theme_table(color("base0d")?, color("base0c")?, color("base08")?, [
+        ("red", color("base08")), ("green", color("base0b")), ("yellow", color("base0a")),
+        ("blue", color("base0d")), ("magenta", color("base0e")), ("cyan", color("base0c")), ("grey", color("base03")),
+        ("lightred", color("base08")), ("lightgreen", color("base0b")), ("lightyellow", color("base0a")),
+        ("lightblue", color("base0d")), ("lightmagenta", color("base0e")), ("lightcyan", color("base0c")), ("white", color("base07")),
+    ])
+}
+
+fn theme_table(accent: String, label: String, warning: String, logo: [(&str, Option<String>); 14]) -> Option<Table> {
+    let mut table = Table::new();
+    table.insert("accent".to_string(), Value::String(accent));
+    table.insert("label".to_string(), Value::String(label));
+    table.insert("warning".to_string(), Value::String(warning));
+    table.insert("logo".to_string(), Value::Table(
+        logo.into_iter()
+            .filter_map(|(name, color)| Some((name.to_string(), Value::String(color?))))
+            .collect()
+    ));
+
+    Some(table)
+}
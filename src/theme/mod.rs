@@ -0,0 +1,5 @@
+pub mod appearance;
+pub mod background;
+pub mod color;
+mod colorscheme;
+pub mod theme;
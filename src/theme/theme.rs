@@ -0,0 +1,203 @@
+use crate::_utils::status::config_error;
+use crate::config::config::config_dir;
+use crate::config::context::{appearance, config, theme};
+use crate::theme::appearance::Appearance;
+use crate::theme::background::TERMINAL_BACKGROUND;
+use crate::theme::color::{adapt_escapes, adapt_to, ColorDepth};
+use crate::theme::colorscheme::{base16_theme, pywal_theme};
+use colored::Color;
+use std::borrow::Cow;
+use std::fs;
+use toml::Table;
+
+const BUILTIN_THEMES: [(&str, &str); 3] = [
+    ("dracula", include_str!("../../static/themes/dracula.toml")),
+    ("gruvbox", include_str!("../../static/themes/gruvbox.toml")),
+    ("catppuccin", include_str!("../../static/themes/catppuccin.toml")),
+];
+
+/// The ANSI codes the build script bakes into logos, keyed by the color names used in logo files
+const LOGO_COLOR_CODES: [(&str, &str); 14] = [
+    ("red", "\x1b[0;31m"),
+    ("green", "\x1b[0;32m"),
+    ("yellow", "\x1b[0;33m"),
+    ("blue", "\x1b[0;34m"),
+    ("magenta", "\x1b[0;35m"),
+    ("cyan", "\x1b[0;36m"),
+    ("grey", "\x1b[0;37m"),
+    ("lightred", "\x1b[0;91m"),
+    ("lightgreen", "\x1b[0;92m"),
+    ("lightyellow", "\x1b[0;93m"),
+    ("lightblue", "\x1b[0;94m"),
+    ("lightmagenta", "\x1b[0;95m"),
+    ("lightcyan", "\x1b[0;96m"),
+    ("white", "\x1b[0;97m"),
+];
+
+pub struct Theme {
+    /// Color of the `user@host` title
+    pub accent: Color,
+    /// Color of the stat labels
+    pub label: Color,
+    /// Color of values that need attention, e.g. running as root
+    pub warning: Color,
+    /// Text between a label and its value
+    pub separator: String,
+    /// Characters for the filled and empty parts of usage bars
+    pub bar: (char, char),
+    /// Replacement colors for the logo, keyed by logo color name (`red`, `lightcyan`, ...)
+    pub logo_palette: Vec<(String, Color)>,
+}
+
+impl Default for Theme {
+    /// Blue accents, brightened on dark backgrounds when `theme.adaptive` is enabled
+    fn default() -> Self {
+        let accent = match background() {
+            Appearance::Dark => Color::BrightBlue,
+            Appearance::Light | Appearance::Unknown => Color::Blue,
+        };
+
+        Theme {
+            accent,
+            label: accent,
+            warning: Color::Red,
+            separator: " = ".to_string(),
+            bar: ('█', '░'),
+            logo_palette: Vec::new(),
+        }
+    }
+}
+
+impl Theme {
+
+    /// Overrides fields with the ones present in a theme file, leaving the rest untouched
+    fn apply(&mut self, table: &Table) {
+        if let Some(color) = table.get("accent").and_then(|v| v.as_str()).and_then(parse_color) {
+            self.accent = color;
+        }
+        if let Some(color) = table.get("label").and_then(|v| v.as_str()).and_then(parse_color) {
+            self.label = color;
+        }
+        if let Some(color) = table.get("warning").and_then(|v| v.as_str()).and_then(parse_color) {
+            self.warning = color;
+        }
+        if let Some(separator) = table.get("separator").and_then(|v| v.as_str()) {
+            self.separator = separator.to_string();
+        }
+        if let Some(bar) = table.get("bar").and_then(|v| v.as_array()) {
+            let chars: Vec<char> = bar.iter().filter_map(|v| v.as_str()?.chars().next()).collect();
+            if let [filled, empty] = chars[..] {
+                self.bar = (filled, empty);
+            }
+        }
+        if let Some(logo) = table.get("logo").and_then(|v| v.as_table()) {
+            self.logo_palette = logo.iter()
+                .filter_map(|(name, color)| Some((name.clone(), parse_color(color.as_str()?)?)))
+                .collect();
+        }
+    }
+
+}
+
+/// Recolors a logo line with the theme's logo palette, and swaps colors that would be
+/// near-invisible on a light terminal background for darker ones. Without colors at `depth`, it only
+/// drops them.
+pub fn adapt_logo_line(line: &str, depth: ColorDepth) -> Cow<'_, str> {
+    let theme = theme();
+    if depth == ColorDepth::None {
+        return Cow::Owned(adapt_escapes(line, depth));
+    }
+    if theme.logo_palette.is_empty() && background() != Appearance::Light {
+        return Cow::Borrowed(line);
+    }
+
+    let mut line = line.to_string();
+
+    for (name, color) in &theme.logo_palette {
+        // A palette entry for a base color also covers its light variant unless that is set explicitly
+        let codes = LOGO_COLOR_CODES.iter().filter(|(logo_name, _)| {
+            logo_name == name || (logo_name.strip_prefix("light") == Some(name) && !theme.logo_palette.iter().any(|(n, _)| n == logo_name))
+        });
+        for (_, code) in codes {
+            line = line.replace(code, &format!("\x1b[0;{}m", adapt_to(*color, depth).to_fg_str()));
+        }
+    }
+
+    if background() == Appearance::Light {
+        const LIGHT_BACKGROUND_SWAPS: [(&str, &str); 3] = [
+            ("\x1b[0;97m", "\x1b[0;30m"),   // white -> black
+            ("\x1b[0;37m", "\x1b[0;90m"),   // grey -> dark grey
+            ("\x1b[0;93m", "\x1b[0;33m"),   // light yellow -> yellow
+        ];
+
+        for (from, to) in LIGHT_BACKGROUND_SWAPS {
+            line = line.replace(from, to);
+        }
+    }
+
+    Cow::Owned(line)
+}
+
+/// Parses a color name understood by `colored` ("blue", "bright cyan") or a `#rrggbb` hex color
+pub fn parse_color(color: &str) -> Option<Color> {
+    match color.strip_prefix('#') {
+        Some(hex) if hex.len() == 6 => {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            Some(Color::TrueColor { r: channel(0)?, g: channel(2)?, b: channel(4)? })
+        },
+        Some(_) => None,
+        None => color.parse().ok(),
+    }
+}
+
+/// Builds the theme named by `theme.name`, looking at the generated colorschemes (`pywal`, `base16`)
+/// and built-in themes first, and then at `<config dir>/themes/<name>.toml`
+pub fn load_theme() -> Theme {
+    let mut theme = Theme::default();
+    let config = config();
+    let name = config.get_str("theme.name").unwrap_or("default");
+
+    if name == "default" {
+        return theme;
+    }
+
+    // Generated colorschemes are converted into theme tables so they go through the same path
+    if name == "pywal" || name == "base16" {
+        let table = match name {
+            "pywal" => pywal_theme(),
+            _ => config.get_str("theme.base16_file").and_then(base16_theme),
+        };
+
+        match table {
+            Some(table) => theme.apply(&table),
+            None => config_error(&format!("could not read {} colorscheme", name)),
+        }
+        return theme;
+    }
+
+    let content = match BUILTIN_THEMES.iter().find(|(builtin, _)| *builtin == name) {
+        Some((_, content)) => Some(content.to_string()),
+        None => config_dir().and_then(|dir| fs::read_to_string(dir.join("themes").join(format!("{}.toml", name))).ok()),
+    };
+
+    match content.map(|content| content.parse::<Table>()) {
+        Some(Ok(table)) => theme.apply(&table),
+        Some(Err(err)) => config_error(&format!("ignoring invalid theme '{}': {}", name, err.message())),
+        None => config_error(&format!("theme '{}' not found", name)),
+    }
+
+    theme
+}
+
+/// The background vega's output is drawn on when `theme.adaptive` is enabled, preferring what the
+/// terminal itself reports over the OS-wide appearance
+pub fn background() -> Appearance {
+    if !config().get_bool("theme.adaptive", false) {
+        return Appearance::Unknown;
+    }
+
+    match *TERMINAL_BACKGROUND {
+        Appearance::Unknown => appearance(),
+        background => background,
+    }
+}
@@ -15,7 +15,8 @@ count_cmd() {
     n=$(
         LC_ALL=C "$@" 2>/dev/null | wc -l | tr -d '[:space:]' || echo 0
     )
-    [ "${n:-0}" -gt 0 ] && append "$n ($label)"
+    # Never fail, a manager with nothing installed shouldn't abort the script under `set -e`
+    [ "${n:-0}" -gt 0 ] && append "$n ($label)" || :
 }
 
 ##############################################################################
@@ -68,16 +69,36 @@ have flatpak && count_cmd flatpak flatpak list
 
 ##############################################################################
 # Nix
+# NixOS installs user packages through per-user, home-manager and `nix profile`
+# profiles rather than ~/.nix-profile, so check every profile that exists
 ##############################################################################
 if have nix-store; then
+    state_home=${XDG_STATE_HOME:-$HOME/.local/state}
     [ -e /run/current-system/sw ] && \
         count_cmd nix-system  nix-store -qR /run/current-system/sw
-    [ -e "$HOME/.nix-profile" ] && \
-        count_cmd nix-user    nix-store -qR "$HOME/.nix-profile"
+    [ -e "/etc/profiles/per-user/${USER:-$(id -un)}" ] && \
+        count_cmd nix-user    nix-store -qR "/etc/profiles/per-user/${USER:-$(id -un)}"
+    if [ -e "$HOME/.nix-profile" ]; then
+        count_cmd nix-profile nix-store -qR "$HOME/.nix-profile"
+    elif [ -e "$state_home/nix/profile" ]; then
+        count_cmd nix-profile nix-store -qR "$state_home/nix/profile"
+    fi
+    [ -e "$state_home/nix/profiles/home-manager" ] && \
+        count_cmd home-manager nix-store -qR "$state_home/nix/profiles/home-manager"
     [ -e /nix/var/nix/profiles/default ] && \
         count_cmd nix-default nix-store -qR /nix/var/nix/profiles/default
 fi
 
+##############################################################################
+# Guix
+##############################################################################
+if have guix; then
+    [ -e /run/current-system/profile ] && \
+        count_cmd guix-system guix package -p /run/current-system/profile -I
+    [ -e "$HOME/.guix-profile" ] && \
+        count_cmd guix-user   guix package -I
+fi
+
 ##############################################################################
 # Final output
 ##############################################################################